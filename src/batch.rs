@@ -0,0 +1,299 @@
+// The "Batch Query" window: runs the active document's query, with its
+// language and jq options, against a set of files or every open tab, lists
+// each input's result or error, and saves the results to a folder (one file
+// per input) or to one file as a JSON array or NDJSON.
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, channel};
+
+use eframe::egui;
+use egui::Color32;
+use serde_json::{Value, json};
+
+use crate::document::{Document, preview};
+use crate::jq_options::{JqLimits, JqOptions};
+use crate::query::{self, QueryLanguage, result_size};
+
+#[derive(Clone)]
+enum Source {
+    File(PathBuf),
+    // A tab's title and text, as they were when added
+    Tab(String, String),
+}
+
+impl Source {
+    fn name(&self) -> String {
+        match self {
+            Source::File(path) => path
+                .file_name()
+                .map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned()),
+            Source::Tab(title, _) => title.clone(),
+        }
+    }
+
+    fn read(&self) -> Result<String, String> {
+        match self {
+            Source::File(path) => std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e)),
+            Source::Tab(_, text) => Ok(text.clone()),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BatchPanel {
+    sources: Vec<Source>,
+    // What the results are of, e.g. "jq: .items[]"
+    query_label: String,
+    raw_output: bool,
+    // One per source while or after running; `None` until it has run
+    results: Vec<Option<Result<Value, String>>>,
+    running: Option<Receiver<(usize, Result<Value, String>)>>,
+    // Save the results as `{"source": name, "result": ...}`
+    with_source: bool,
+    status: Option<Result<String, String>>,
+}
+
+impl BatchPanel {
+    fn start(&mut self, ctx: &egui::Context, document: &Document) {
+        if document.jq_query_input.trim().is_empty() {
+            self.status = Some(Err("The active document's query field is empty.".to_string()));
+            return;
+        }
+        let language = document.query_language;
+        let (query, options) = (document.jq_query_input.clone(), document.jq_options.clone());
+        self.query_label = format!("{}: {}", language.label(), query);
+        self.raw_output = language == QueryLanguage::Jq && options.raw_output;
+        self.results = self.sources.iter().map(|_| None).collect();
+        self.status = None;
+
+        let (sender, receiver) = channel();
+        let (ctx, sources) = (ctx.clone(), self.sources.clone());
+        std::thread::spawn(move || {
+            for (index, source) in sources.iter().enumerate() {
+                let options = if language == QueryLanguage::Jq { options.clone() } else { JqOptions::default() };
+                let outcome = source.read().and_then(|input| query::evaluate(language, &input, &query, &options, &JqLimits::default()));
+                if sender.send((index, outcome)).is_err() {
+                    return;
+                }
+                ctx.request_repaint();
+            }
+        });
+        self.running = Some(receiver);
+    }
+
+    // The successful results with their sources' names
+    fn successes(&self) -> Vec<(String, &Value)> {
+        self.sources
+            .iter()
+            .zip(&self.results)
+            .filter_map(|(source, result)| match result {
+                Some(Ok(value)) => Some((source.name(), value)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn wrap(&self, name: &str, value: &Value) -> Value {
+        if self.with_source { json!({ "source": name, "result": value }) } else { value.clone() }
+    }
+
+    // One file per result, named after its input, e.g. `orders.result.json`.
+    // Raw jq output is written as the text jq printed. Existing files are
+    // never replaced, and a folder holding inputs is refused outright.
+    fn write_to_folder(&self, dir: &Path) -> Result<String, String> {
+        let canonical = |path: &Path| path.canonicalize().ok();
+        let target = canonical(dir);
+        if self.sources.iter().any(|source| match source {
+            Source::File(path) => path.parent().and_then(canonical) == target,
+            Source::Tab(..) => false,
+        }) {
+            return Err(format!("{} holds inputs of this batch; pick another folder.", dir.display()));
+        }
+        let mut taken = HashSet::new();
+        let successes = self.successes();
+        for (name, value) in &successes {
+            let stem = Path::new(name).file_stem().map_or_else(|| name.clone(), |stem| stem.to_string_lossy().into_owned());
+            let (extension, text) = match value {
+                Value::String(text) if self.raw_output && !self.with_source => ("txt", text.clone()),
+                _ => ("json", serde_json::to_string_pretty(&self.wrap(name, value)).map_err(|e| e.to_string())?),
+            };
+            let mut file_name = format!("{}.result.{}", stem, extension);
+            let mut counter = 2;
+            while dir.join(&file_name).exists() || !taken.insert(file_name.clone()) {
+                file_name = format!("{}_{}.result.{}", stem, counter, extension);
+                counter += 1;
+            }
+            let path = dir.join(&file_name);
+            std::fs::write(&path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        }
+        Ok(format!("Wrote {} files to {}.", successes.len(), dir.display()))
+    }
+
+    fn write_combined(&self, path: &Path, ndjson: bool) -> Result<String, String> {
+        let values: Vec<Value> = self.successes().iter().map(|(name, value)| self.wrap(name, value)).collect();
+        let text = if ndjson {
+            values.iter().map(|value| format!("{}\n", value)).collect()
+        } else {
+            serde_json::to_string_pretty(&values).map_err(|e| e.to_string())?
+        };
+        std::fs::write(path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(format!("Wrote {} results to {}.", values.len(), path.display()))
+    }
+
+    // Returns a result to open as a document: its title and value
+    pub fn show(&mut self, ui: &mut egui::Ui, documents: &[Document], active: usize) -> Option<(String, Value)> {
+        if let Some(receiver) = &self.running {
+            for (index, outcome) in receiver.try_iter() {
+                self.results[index] = Some(outcome);
+            }
+            if self.results.iter().all(Option::is_some) {
+                self.running = None;
+            }
+        }
+        let idle = self.running.is_none();
+
+        ui.add_enabled_ui(idle, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Add Files…").clicked()
+                    && let Some(paths) = rfd::FileDialog::new().pick_files()
+                {
+                    self.sources.extend(paths.into_iter().map(Source::File));
+                    self.results.clear();
+                }
+                if ui.button("Add Folder…").on_hover_text("Adds the .json files in a folder").clicked()
+                    && let Some(dir) = rfd::FileDialog::new().pick_folder()
+                {
+                    match json_files_in(&dir) {
+                        Ok(paths) => self.sources.extend(paths.into_iter().map(Source::File)),
+                        Err(e) => self.status = Some(Err(e)),
+                    }
+                    self.results.clear();
+                }
+                if ui.button("Add Open Tabs").clicked() {
+                    self.sources
+                        .extend(documents.iter().map(|doc| Source::Tab(doc.title.clone(), doc.input_json.clone())));
+                    self.results.clear();
+                }
+                if ui.button("Clear").clicked() {
+                    self.sources.clear();
+                    self.results.clear();
+                }
+            });
+        });
+
+        let document = &documents[active];
+        ui.horizontal(|ui| {
+            let can_run = idle && !self.sources.is_empty();
+            if ui
+                .add_enabled(can_run, egui::Button::new(format!("Run on {} Inputs", self.sources.len())))
+                .on_hover_text("Runs the active document's query, with its language and options")
+                .clicked()
+            {
+                self.start(ui.ctx(), document);
+            }
+            if !idle {
+                ui.spinner();
+            }
+        });
+        ui.label(egui::RichText::new(format!("{}: {}", document.query_language.label(), document.jq_query_input)).monospace());
+
+        ui.separator();
+        let mut open = None;
+        let mut remove = None;
+        egui::ScrollArea::vertical()
+            .id_salt("batch_results")
+            .max_height(300.0)
+            .show(ui, |ui| {
+                for (index, source) in self.sources.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        match self.results.get(index) {
+                            Some(Some(Ok(value))) => {
+                                ui.colored_label(Color32::LIGHT_GREEN, "✔");
+                                if ui.link(source.name()).on_hover_text(preview(value)).clicked() {
+                                    open = Some((format!("Batch result for {}", source.name()), value.clone()));
+                                }
+                                ui.weak(result_size(value));
+                            }
+                            Some(Some(Err(e))) => {
+                                ui.colored_label(Color32::LIGHT_RED, "✖");
+                                ui.label(source.name());
+                                ui.colored_label(Color32::LIGHT_RED, e.lines().next().unwrap_or_default())
+                                    .on_hover_text(e);
+                            }
+                            Some(None) => {
+                                ui.weak("…");
+                                ui.label(source.name());
+                            }
+                            None => {
+                                ui.label(source.name());
+                                if idle && ui.small_button("✖").on_hover_text("Remove").clicked() {
+                                    remove = Some(index);
+                                }
+                            }
+                        }
+                    });
+                }
+            });
+        if let Some(index) = remove {
+            self.sources.remove(index);
+        }
+
+        let finished = idle && !self.results.is_empty();
+        if finished {
+            let failed = self.results.iter().filter(|result| matches!(result, Some(Err(_)))).count();
+            ui.label(format!(
+                "{} succeeded, {} failed · {}",
+                self.results.len() - failed,
+                failed,
+                self.query_label
+            ));
+        }
+        ui.separator();
+        ui.checkbox(&mut self.with_source, "Wrap each result as {\"source\", \"result\"}");
+        ui.add_enabled_ui(finished, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Write to Folder…").clicked()
+                    && let Some(dir) = rfd::FileDialog::new().pick_folder()
+                {
+                    self.status = Some(self.write_to_folder(&dir));
+                }
+                if ui.button("Save as JSON Array…").clicked()
+                    && let Some(path) = rfd::FileDialog::new()
+                        .add_filter("JSON", &["json"])
+                        .set_file_name("results.json")
+                        .save_file()
+                {
+                    self.status = Some(self.write_combined(&path, false));
+                }
+                if ui.button("Save as NDJSON…").clicked()
+                    && let Some(path) = rfd::FileDialog::new()
+                        .add_filter("NDJSON", &["ndjson", "jsonl"])
+                        .set_file_name("results.ndjson")
+                        .save_file()
+                {
+                    self.status = Some(self.write_combined(&path, true));
+                }
+            });
+        });
+        match &self.status {
+            Some(Ok(message)) => {
+                ui.label(message);
+            }
+            Some(Err(e)) => {
+                ui.colored_label(Color32::RED, e);
+            }
+            None => {}
+        }
+        open
+    }
+}
+
+fn json_files_in(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
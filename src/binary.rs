@@ -0,0 +1,505 @@
+// MessagePack, CBOR and BSON, decoded to JSON and encoded back. Types JSON
+// lacks are kept as single-key wrapper objects, so they survive a round trip
+// and the tree can label them:
+//   binary data       {"$binary": {"base64": "AAEC", "subType": "00"}}
+//   MessagePack ext   {"$ext": {"type": 5, "base64": "AAEC"}}
+//   CBOR tag          {"$tag": {"tag": 1, "value": 1700000000}}
+// BSON goes through MongoDB relaxed Extended JSON, where `$binary` comes
+// from, so ObjectIds, dates and decimals are `$oid`, `$date`, ... wrappers.
+// A file holding several concatenated values opens as an array of them.
+use std::io::Cursor;
+
+use base64::Engine;
+use base64::alphabet;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use serde_json::{Map, Value, json};
+
+use crate::convert::{Warnings, float_value};
+use crate::json_path::JsonPath;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinaryFormat {
+    MessagePack,
+    Cbor,
+    Bson,
+}
+
+impl BinaryFormat {
+    pub const ALL: [BinaryFormat; 3] = [BinaryFormat::MessagePack, BinaryFormat::Cbor, BinaryFormat::Bson];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            BinaryFormat::MessagePack => "MessagePack",
+            BinaryFormat::Cbor => "CBOR",
+            BinaryFormat::Bson => "BSON",
+        }
+    }
+
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            BinaryFormat::MessagePack => &["msgpack", "mpk"],
+            BinaryFormat::Cbor => &["cbor"],
+            BinaryFormat::Bson => &["bson"],
+        }
+    }
+
+    pub fn decode(self, bytes: &[u8]) -> Result<(Value, Vec<String>), String> {
+        if bytes.is_empty() {
+            return Err(format!("There is no {} data to decode.", self.label()));
+        }
+        let mut warnings = Warnings::default();
+        let mut cursor = Cursor::new(bytes);
+        let mut values = Vec::new();
+        while (cursor.position() as usize) < bytes.len() {
+            let offset = cursor.position();
+            let path = if values.is_empty() { JsonPath::root() } else { JsonPath::root().child_index(values.len()) };
+            let value = match self {
+                BinaryFormat::MessagePack => rmpv::decode::read_value(&mut cursor)
+                    .map_err(|e| e.to_string())
+                    .map(|v| msgpack_to_json(v, &path, &mut warnings)),
+                BinaryFormat::Cbor => ciborium::from_reader::<ciborium::Value, _>(&mut cursor)
+                    .map_err(|e| e.to_string())
+                    .map(|v| cbor_to_json(v, &path, &mut warnings)),
+                BinaryFormat::Bson => bson::Document::from_reader(&mut cursor).map_err(|e| e.to_string()).map(|doc| {
+                    let doc = bson::Bson::Document(doc);
+                    note_bson_types(&doc, &mut warnings);
+                    doc.into_relaxed_extjson()
+                }),
+            }
+            .map_err(|e| format!("Invalid {} at byte {}: {}", self.label(), offset, e))?;
+            values.push(value);
+        }
+        let value = if values.len() == 1 { values.remove(0) } else { Value::Array(values) };
+        Ok((value, warnings.0))
+    }
+
+    pub fn encode(self, value: &Value) -> Result<(Vec<u8>, Vec<String>), String> {
+        let mut warnings = Warnings::default();
+        let mut bytes = Vec::new();
+        match self {
+            BinaryFormat::MessagePack => {
+                let value = json_to_msgpack(value, &JsonPath::root(), &mut warnings)?;
+                rmpv::encode::write_value(&mut bytes, &value).map_err(|e| e.to_string())?;
+            }
+            BinaryFormat::Cbor => {
+                let value = json_to_cbor(value, &JsonPath::root(), &mut warnings)?;
+                ciborium::into_writer(&value, &mut bytes).map_err(|e| e.to_string())?;
+            }
+            BinaryFormat::Bson => bson_export(value, &mut bytes, &mut warnings)?,
+        }
+        Ok((bytes, warnings.0))
+    }
+}
+
+// Pasted binary data: hex (whitespace, `:` and a `0x` prefix allowed) or
+// base64 in either alphabet, padded or not. Text that is valid hex is hex.
+pub fn decode_blob_text(text: &str) -> Result<Vec<u8>, String> {
+    let compact: String = text.chars().filter(|c| !c.is_whitespace() && *c != ':').collect();
+    let hex = compact.strip_prefix("0x").unwrap_or(&compact);
+    if hex.is_empty() {
+        return Err("Paste hex or base64 data into the editor first.".to_string());
+    }
+    if hex.len().is_multiple_of(2) && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Ok((0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or_default())
+            .collect());
+    }
+    const CONFIG: GeneralPurposeConfig =
+        GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent);
+    GeneralPurpose::new(&alphabet::STANDARD, CONFIG)
+        .decode(&compact)
+        .or_else(|_| GeneralPurpose::new(&alphabet::URL_SAFE, CONFIG).decode(&compact))
+        .map_err(|e| format!("The text is neither hex nor base64: {}", e))
+}
+
+// e.g. "MessagePack: 120 bytes, 64% of compact JSON (188 bytes; 310 pretty)"
+pub fn size_comparison(format_label: &str, size: usize, value: &Value) -> String {
+    let compact = serde_json::to_string(value).map_or(0, |text| text.len());
+    let pretty = serde_json::to_string_pretty(value).map_or(0, |text| text.len());
+    let percent = (size * 100).checked_div(compact).unwrap_or_default();
+    format!(
+        "{}: {} bytes, {}% of compact JSON ({} bytes; {} pretty)",
+        format_label,
+        size,
+        percent,
+        compact,
+        pretty
+    )
+}
+
+// A short description of a wrapper object for a type JSON lacks, shown next
+// to it in the tree; `None` for ordinary objects.
+pub fn type_annotation(map: &Map<String, Value>) -> Option<String> {
+    let (key, inner) = map.iter().next()?;
+    if map.len() == 2 && key == "$code" && map.contains_key("$scope") {
+        return Some("JavaScript code with scope".to_string());
+    }
+    if map.len() != 1 {
+        return None;
+    }
+    let annotation = match key.as_str() {
+        "$binary" => {
+            let size = inner.get("base64").and_then(Value::as_str).map_or(0, base64_len);
+            match inner.get("subType").and_then(Value::as_str) {
+                Some(sub_type) if sub_type != "00" => format!("binary, {} bytes, subtype {}", size, sub_type),
+                _ => format!("binary, {} bytes", size),
+            }
+        }
+        "$ext" => {
+            let size = inner.get("base64").and_then(Value::as_str).map_or(0, base64_len);
+            match inner.get("type").and_then(Value::as_i64) {
+                Some(-1) => "MessagePack timestamp".to_string(),
+                Some(ext_type) => format!("MessagePack ext type {}, {} bytes", ext_type, size),
+                None => return None,
+            }
+        }
+        "$tag" => {
+            let tag = inner.get("tag").and_then(Value::as_u64)?;
+            match cbor_tag_name(tag) {
+                Some(name) => format!("CBOR tag {} ({})", tag, name),
+                None => format!("CBOR tag {}", tag),
+            }
+        }
+        "$oid" => "ObjectId".to_string(),
+        "$date" => "datetime".to_string(),
+        "$numberInt" => "int32".to_string(),
+        "$numberLong" => "int64".to_string(),
+        "$numberDouble" => "double".to_string(),
+        "$numberDecimal" => "decimal128".to_string(),
+        "$timestamp" => "BSON timestamp".to_string(),
+        "$regularExpression" => "regular expression".to_string(),
+        "$code" => "JavaScript code".to_string(),
+        "$symbol" => "symbol".to_string(),
+        "$dbPointer" => "DBPointer".to_string(),
+        "$minKey" => "min key".to_string(),
+        "$maxKey" => "max key".to_string(),
+        "$undefined" => "undefined".to_string(),
+        _ => return None,
+    };
+    Some(annotation)
+}
+
+fn cbor_tag_name(tag: u64) -> Option<&'static str> {
+    Some(match tag {
+        0 => "date/time string",
+        1 => "epoch date/time",
+        2 => "positive bignum",
+        3 => "negative bignum",
+        4 => "decimal fraction",
+        5 => "bigfloat",
+        21 => "expected base64url",
+        22 => "expected base64",
+        23 => "expected hex",
+        24 => "embedded CBOR",
+        32 => "URI",
+        37 => "UUID",
+        55799 => "self-described CBOR",
+        _ => return None,
+    })
+}
+
+fn base64_len(text: &str) -> usize {
+    text.trim_end_matches('=').len() * 3 / 4
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+pub fn binary_json(bytes: &[u8]) -> Value {
+    json!({ "$binary": { "base64": encode_base64(bytes), "subType": "00" } })
+}
+
+// The wrapper objects described at the top of the file
+enum Wrapped<'a> {
+    Binary(Vec<u8>, String),
+    Ext(i8, Vec<u8>),
+    Tag(u64, &'a Value),
+}
+
+fn unwrap_special<'a>(value: &'a Value, path: &JsonPath) -> Result<Option<Wrapped<'a>>, String> {
+    let Some(map) = value.as_object().filter(|map| map.len() == 1) else {
+        return Ok(None);
+    };
+    let invalid = |key: &str, expected: &str| format!("{}: invalid {} wrapper, expected {}", path.to_jsonpath(), key, expected);
+    let decode = |inner: &Value, key: &str| {
+        inner
+            .get("base64")
+            .and_then(Value::as_str)
+            .and_then(|text| base64::engine::general_purpose::STANDARD.decode(text).ok())
+            .ok_or_else(|| invalid(key, "a \"base64\" string"))
+    };
+    let wrapped = if let Some(inner) = map.get("$binary") {
+        let sub_type = inner.get("subType").and_then(Value::as_str).unwrap_or("00");
+        Wrapped::Binary(decode(inner, "$binary")?, sub_type.to_string())
+    } else if let Some(inner) = map.get("$ext") {
+        let ext_type = inner
+            .get("type")
+            .and_then(Value::as_i64)
+            .and_then(|t| i8::try_from(t).ok())
+            .ok_or_else(|| invalid("$ext", "a \"type\" from -128 to 127"))?;
+        Wrapped::Ext(ext_type, decode(inner, "$ext")?)
+    } else if let Some(inner) = map.get("$tag") {
+        let tag = inner
+            .get("tag")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| invalid("$tag", "a numeric \"tag\""))?;
+        Wrapped::Tag(tag, inner.get("value").ok_or_else(|| invalid("$tag", "a \"value\""))?)
+    } else {
+        return Ok(None);
+    };
+    Ok(Some(wrapped))
+}
+
+// Map keys that aren't strings are written out as JSON text
+fn object_key(key: Value, path: &JsonPath, warnings: &mut Warnings) -> String {
+    match key {
+        Value::String(key) => key,
+        other => {
+            let text = other.to_string();
+            warnings.at(path, &format!("non-string key {} became a string", text));
+            text
+        }
+    }
+}
+
+fn insert_member(map: &mut Map<String, Value>, key: String, value: Value, path: &JsonPath, warnings: &mut Warnings) {
+    if map.insert(key.clone(), value).is_some() {
+        warnings.at(path, &format!("duplicate key \"{}\", only the last value was kept", key));
+    }
+}
+
+// MessagePack
+
+fn msgpack_to_json(value: rmpv::Value, path: &JsonPath, warnings: &mut Warnings) -> Value {
+    match value {
+        rmpv::Value::Nil => Value::Null,
+        rmpv::Value::Boolean(b) => Value::Bool(b),
+        rmpv::Value::Integer(n) => match n.as_i64() {
+            Some(n) => Value::from(n),
+            None => Value::from(n.as_u64().unwrap_or_default()),
+        },
+        rmpv::Value::F32(f) => {
+            warnings.once("32-bit floats are shown as JSON numbers and encoded back as 64-bit floats");
+            float_value(f as f64, path, warnings)
+        }
+        rmpv::Value::F64(f) => float_value(f, path, warnings),
+        rmpv::Value::String(s) => match s.as_str() {
+            Some(text) => Value::String(text.to_string()),
+            None => {
+                warnings.at(path, "string is not valid UTF-8 and was kept as binary");
+                binary_json(s.as_bytes())
+            }
+        },
+        rmpv::Value::Binary(bytes) => binary_json(&bytes),
+        rmpv::Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .enumerate()
+                .map(|(index, item)| msgpack_to_json(item, &path.child_index(index), warnings))
+                .collect(),
+        ),
+        rmpv::Value::Map(entries) => {
+            let mut map = Map::new();
+            for (key, value) in entries {
+                let key = object_key(msgpack_to_json(key, path, warnings), path, warnings);
+                let value = msgpack_to_json(value, &path.child_key(&key), warnings);
+                insert_member(&mut map, key, value, path, warnings);
+            }
+            Value::Object(map)
+        }
+        rmpv::Value::Ext(ext_type, bytes) => json!({ "$ext": { "type": ext_type, "base64": encode_base64(&bytes) } }),
+    }
+}
+
+fn json_to_msgpack(value: &Value, path: &JsonPath, warnings: &mut Warnings) -> Result<rmpv::Value, String> {
+    match unwrap_special(value, path)? {
+        Some(Wrapped::Binary(bytes, sub_type)) => {
+            if sub_type != "00" {
+                warnings.at(path, &format!("binary subtype {} has no MessagePack equivalent and was dropped", sub_type));
+            }
+            return Ok(rmpv::Value::Binary(bytes));
+        }
+        Some(Wrapped::Ext(ext_type, bytes)) => return Ok(rmpv::Value::Ext(ext_type, bytes)),
+        Some(Wrapped::Tag(tag, inner)) => {
+            warnings.at(path, &format!("CBOR tag {} has no MessagePack equivalent and was dropped", tag));
+            return json_to_msgpack(inner, path, warnings);
+        }
+        None => {}
+    }
+    Ok(match value {
+        Value::Null => rmpv::Value::Nil,
+        Value::Bool(b) => rmpv::Value::Boolean(*b),
+        Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(n), _) => rmpv::Value::from(n),
+            (None, Some(n)) => rmpv::Value::from(n),
+            _ => rmpv::Value::F64(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => rmpv::Value::from(s.as_str()),
+        Value::Array(items) => rmpv::Value::Array(
+            items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| json_to_msgpack(item, &path.child_index(index), warnings))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Object(map) => rmpv::Value::Map(
+            map.iter()
+                .map(|(key, value)| {
+                    Ok((rmpv::Value::from(key.as_str()), json_to_msgpack(value, &path.child_key(key), warnings)?))
+                })
+                .collect::<Result<_, String>>()?,
+        ),
+    })
+}
+
+// CBOR
+
+fn cbor_to_json(value: ciborium::Value, path: &JsonPath, warnings: &mut Warnings) -> Value {
+    match value {
+        ciborium::Value::Null => Value::Null,
+        ciborium::Value::Bool(b) => Value::Bool(b),
+        ciborium::Value::Integer(n) => {
+            let n = i128::from(n);
+            if let Ok(n) = i64::try_from(n) {
+                Value::from(n)
+            } else if let Ok(n) = u64::try_from(n) {
+                Value::from(n)
+            } else {
+                warnings.at(path, &format!("integer {} is outside the 64-bit range and became a float", n));
+                float_value(n as f64, path, warnings)
+            }
+        }
+        ciborium::Value::Float(f) => float_value(f, path, warnings),
+        ciborium::Value::Text(text) => Value::String(text),
+        ciborium::Value::Bytes(bytes) => binary_json(&bytes),
+        ciborium::Value::Tag(tag, inner) => json!({ "$tag": { "tag": tag, "value": cbor_to_json(*inner, path, warnings) } }),
+        ciborium::Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .enumerate()
+                .map(|(index, item)| cbor_to_json(item, &path.child_index(index), warnings))
+                .collect(),
+        ),
+        ciborium::Value::Map(entries) => {
+            let mut map = Map::new();
+            for (key, value) in entries {
+                let key = object_key(cbor_to_json(key, path, warnings), path, warnings);
+                let value = cbor_to_json(value, &path.child_key(&key), warnings);
+                insert_member(&mut map, key, value, path, warnings);
+            }
+            Value::Object(map)
+        }
+        // `ciborium::Value` is non-exhaustive
+        _ => {
+            warnings.at(path, "unsupported CBOR value became null");
+            Value::Null
+        }
+    }
+}
+
+fn json_to_cbor(value: &Value, path: &JsonPath, warnings: &mut Warnings) -> Result<ciborium::Value, String> {
+    match unwrap_special(value, path)? {
+        Some(Wrapped::Binary(bytes, sub_type)) => {
+            if sub_type != "00" {
+                warnings.at(path, &format!("binary subtype {} has no CBOR equivalent and was dropped", sub_type));
+            }
+            return Ok(ciborium::Value::Bytes(bytes));
+        }
+        Some(Wrapped::Ext(ext_type, bytes)) => {
+            warnings.at(path, &format!("MessagePack ext type {} has no CBOR equivalent and was written as bytes", ext_type));
+            return Ok(ciborium::Value::Bytes(bytes));
+        }
+        Some(Wrapped::Tag(tag, inner)) => {
+            return Ok(ciborium::Value::Tag(tag, Box::new(json_to_cbor(inner, path, warnings)?)));
+        }
+        None => {}
+    }
+    Ok(match value {
+        Value::Null => ciborium::Value::Null,
+        Value::Bool(b) => ciborium::Value::Bool(*b),
+        Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(n), _) => ciborium::Value::Integer(n.into()),
+            (None, Some(n)) => ciborium::Value::Integer(n.into()),
+            _ => ciborium::Value::Float(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => ciborium::Value::Text(s.clone()),
+        Value::Array(items) => ciborium::Value::Array(
+            items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| json_to_cbor(item, &path.child_index(index), warnings))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Object(map) => ciborium::Value::Map(
+            map.iter()
+                .map(|(key, value)| {
+                    Ok((ciborium::Value::Text(key.clone()), json_to_cbor(value, &path.child_key(key), warnings)?))
+                })
+                .collect::<Result<_, String>>()?,
+        ),
+    })
+}
+
+// BSON
+
+// Relaxed Extended JSON writes small int64s as plain numbers, which are
+// encoded back as int32
+fn note_bson_types(value: &bson::Bson, warnings: &mut Warnings) {
+    match value {
+        bson::Bson::Int64(n) if i32::try_from(*n).is_ok() => {
+            warnings.once("int64 values that fit in 32 bits are shown as plain numbers and encoded back as int32");
+        }
+        bson::Bson::Document(doc) => doc.values().for_each(|value| note_bson_types(value, warnings)),
+        bson::Bson::Array(items) => items.iter().for_each(|value| note_bson_types(value, warnings)),
+        _ => {}
+    }
+}
+
+// An object becomes one document, an array of objects a sequence of them
+fn bson_export(value: &Value, bytes: &mut Vec<u8>, warnings: &mut Warnings) -> Result<(), String> {
+    let documents: Vec<(&Map<String, Value>, JsonPath)> = match value {
+        Value::Object(map) => vec![(map, JsonPath::root())],
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                item.as_object()
+                    .map(|map| (map, JsonPath::root().child_index(index)))
+                    .ok_or_else(|| format!("BSON can only hold objects, but element {} is not one.", index))
+            })
+            .collect::<Result<_, _>>()?,
+        _ => return Err("BSON needs an object, or an array of objects, at the top level.".to_string()),
+    };
+    for (map, path) in documents {
+        for (key, value) in map {
+            note_bson_loss(value, &path.child_key(key), warnings);
+        }
+        let document = bson::Document::try_from(map.clone()).map_err(|e| format!("{}: {}", path.to_jsonpath(), e))?;
+        let encoded = document.to_vec().map_err(|e| format!("{}: {}", path.to_jsonpath(), e))?;
+        bytes.extend_from_slice(&encoded);
+    }
+    Ok(())
+}
+
+fn note_bson_loss(value: &Value, path: &JsonPath, warnings: &mut Warnings) {
+    match value {
+        Value::Number(n) if n.as_i64().is_none() && n.as_u64().is_some() => {
+            warnings.at(path, &format!("{} is too large for int64 and was written as a double", n));
+        }
+        Value::Object(map) if map.len() == 1 && map.contains_key("$ext") => {
+            warnings.at(path, "MessagePack extension types have no BSON equivalent; written as a document");
+        }
+        Value::Object(map) if map.len() == 1 && map.contains_key("$tag") => {
+            warnings.at(path, "CBOR tags have no BSON equivalent; written as a document");
+        }
+        Value::Object(map) => map.iter().for_each(|(key, value)| note_bson_loss(value, &path.child_key(key), warnings)),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .for_each(|(index, value)| note_bson_loss(value, &path.child_index(index), warnings)),
+        _ => {}
+    }
+}
+
@@ -0,0 +1,186 @@
+// Go structs with `json` tags. Exported field names follow Go's initialism
+// style (`UserID`); fields that can be missing or null are pointers (slices,
+// maps and `any` are already nilable) and missing ones get `omitempty`.
+use std::collections::HashSet;
+
+use super::{References, TypeDef, TypeDefKind, TypeModel, TypeRef, quoted, unique_identifier, words};
+
+const INITIALISMS: &[&str] = &[
+    "api", "ascii", "cpu", "css", "dns", "html", "http", "https", "id", "ip", "json", "sql", "ssh", "tcp", "tls",
+    "ttl", "udp", "ui", "uri", "url", "utf8", "uuid", "xml",
+];
+
+pub fn generate(model: &TypeModel) -> String {
+    let go = Go {
+        interfaces: model
+            .defs
+            .iter()
+            .filter(|def| matches!(def.kind, TypeDefKind::Union(_)))
+            .map(|def| def.name.as_str())
+            .collect(),
+        reach: model.references(false),
+    };
+    let mut out = String::new();
+    if !matches!(model.root, TypeRef::Named(_)) {
+        out.push_str(&format!("type {} {}\n\n", model.root_name, go.go_type(&model.root, "")));
+    }
+    for def in &model.defs {
+        go.write_def(&mut out, def);
+        out.push('\n');
+    }
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+struct Go<'a> {
+    // Unions, which are spelled as interfaces and so are nilable
+    interfaces: HashSet<&'a str>,
+    // The structs each type holds by value, so cycles can be broken
+    reach: References,
+}
+
+impl Go<'_> {
+    fn write_def(&self, out: &mut String, def: &TypeDef) {
+        match &def.kind {
+            TypeDefKind::Struct(fields) => {
+                out.push_str(&format!("type {} struct {{\n", def.name));
+                let mut taken = HashSet::new();
+                // A struct tag is a raw string, which can't hold a backtick
+                let (fields, skipped): (Vec<_>, Vec<_>) = fields.iter().partition(|field| !field.key.contains('`'));
+                let lines: Vec<(String, String, String)> = fields
+                    .iter()
+                    .map(|field| {
+                        let name = unique_identifier(go_name(&field.key), "Field", &mut taken);
+                        let (inner, nullable) = field.ty.strip_nullable();
+                        let mut ty = self.go_type(inner, &def.name);
+                        if (nullable || !field.required) && !self.is_nilable(inner) && !ty.starts_with('*') {
+                            ty.insert(0, '*');
+                        }
+                        let omit = match (field.required, field.key.as_str()) {
+                            (false, _) => ",omitempty",
+                            // A bare `-` tells encoding/json to skip the field
+                            (true, "-") => ",",
+                            (true, _) => "",
+                        };
+                        let tag = format!("`json:{}`", quoted(&format!("{}{}", field.key, omit)));
+                        (name, ty, tag)
+                    })
+                    .collect();
+                // Aligned like gofmt would
+                let name_width = lines.iter().map(|(n, _, _)| n.len()).max().unwrap_or(0);
+                let type_width = lines.iter().map(|(_, t, _)| t.len()).max().unwrap_or(0);
+                for (name, ty, tag) in lines {
+                    out.push_str(&format!("\t{:name_width$} {:type_width$} {}\n", name, ty, tag));
+                }
+                for field in skipped {
+                    let key = quoted(&field.key);
+                    out.push_str(&format!("\t// Skipped {}: a struct tag can't contain a backtick\n", key));
+                }
+                out.push_str("}\n");
+            }
+            TypeDefKind::StringEnum(values) => {
+                out.push_str(&format!("type {} string\n\nconst (\n", def.name));
+                let mut taken = HashSet::new();
+                for value in values {
+                    let constant = unique_identifier(go_name(value), "Value", &mut taken);
+                    out.push_str(&format!("\t{}{} {} = {}\n", def.name, constant, def.name, quoted(value)));
+                }
+                out.push_str(")\n");
+            }
+            TypeDefKind::Union(variants) => {
+                // Go has no unions; decoding gives whichever plain Go value the
+                // JSON held (objects as `map[string]any`)
+                let types: Vec<String> = variants.iter().map(|v| self.go_type(&v.ty, &def.name)).collect();
+                out.push_str(&format!("// {} is one of: {}\n", def.name, types.join(", ")));
+                out.push_str(&format!("type {} any\n", def.name));
+            }
+            TypeDefKind::Alias(ty) => {
+                out.push_str(&format!("type {} = {}\n", def.name, self.go_type(ty, "")));
+            }
+        }
+    }
+
+    // `owner` is the struct being defined; a reference that leads back to it
+    // by value, directly or through other structs, is a pointer so the type
+    // has a size.
+    fn go_type(&self, ty: &TypeRef, owner: &str) -> String {
+        match ty {
+            TypeRef::Any => "any".to_string(),
+            TypeRef::Bool => "bool".to_string(),
+            TypeRef::Integer => "int64".to_string(),
+            TypeRef::Float => "float64".to_string(),
+            TypeRef::String => "string".to_string(),
+            TypeRef::Array(inner) => format!("[]{}", self.go_type(inner, "")),
+            TypeRef::Map(inner) => format!("map[string]{}", self.go_type(inner, "")),
+            TypeRef::Named(name)
+                if !self.interfaces.contains(name.as_str())
+                    && (name == owner || self.reach.get(name).is_some_and(|names| names.contains(owner))) =>
+            {
+                format!("*{}", name)
+            }
+            TypeRef::Named(name) => name.clone(),
+            TypeRef::Nullable(inner) if self.is_nilable(inner) => self.go_type(inner, owner),
+            TypeRef::Nullable(inner) => format!("*{}", self.go_type(inner, "").trim_start_matches('*')),
+        }
+    }
+
+    // Types whose zero value is already nil
+    fn is_nilable(&self, ty: &TypeRef) -> bool {
+        match ty {
+            TypeRef::Any | TypeRef::Array(_) | TypeRef::Map(_) => true,
+            TypeRef::Named(name) => self.interfaces.contains(name.as_str()),
+            _ => false,
+        }
+    }
+}
+
+fn go_name(key: &str) -> String {
+    words(key)
+        .into_iter()
+        .map(|word| {
+            let lower = word.to_ascii_lowercase();
+            if INITIALISMS.contains(&lower.as_str()) {
+                return lower.to_ascii_uppercase();
+            }
+            let mut chars = lower.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn breaks_mutually_recursive_structs_with_pointers() {
+        let schema = json!({
+            "$ref": "#/$defs/A",
+            "$defs": {
+                "A": { "type": "object", "properties": { "b": { "$ref": "#/$defs/B" } }, "required": ["b"] },
+                "B": { "type": "object", "properties": { "a": { "$ref": "#/$defs/A" } }, "required": ["a"] }
+            }
+        });
+        let code = generate(&TypeModel::from_schema(&schema, "Root", &[]).unwrap());
+        assert!(code.contains("B *B `json:\"b\"`"), "{}", code);
+        assert!(code.contains("A *A `json:\"a\"`"), "{}", code);
+    }
+
+    #[test]
+    fn dash_key_is_not_skipped() {
+        let schema = json!({
+            "type": "object",
+            "properties": { "-": { "type": "string" }, "x": { "type": "string" } },
+            "required": ["-"]
+        });
+        let code = generate(&TypeModel::from_schema(&schema, "Root", &[]).unwrap());
+        assert!(code.contains("`json:\"-,\"`"), "{}", code);
+        assert!(code.contains("`json:\"x,omitempty\"`"), "{}", code);
+    }
+}
@@ -0,0 +1,780 @@
+// Code generation from sample JSON or a JSON Schema. Both sources are turned
+// into one language-neutral `TypeModel` (named structs, string enums and
+// unions plus references between them), and each language module only
+// decides how to spell that model.
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use eframe::egui;
+use egui::Color32;
+use serde_json::Value;
+
+use crate::infer::Shape;
+use crate::{parse_json_to_value, parse_ndjson};
+
+mod go;
+mod python;
+mod rust;
+mod typescript;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypeRef {
+    // Anything; nothing was known about the value
+    Any,
+    Bool,
+    Integer,
+    Float,
+    String,
+    Array(Box<TypeRef>),
+    // An object used as a dictionary with string keys
+    Map(Box<TypeRef>),
+    // A `TypeDef` by name
+    Named(String),
+    Nullable(Box<TypeRef>),
+}
+
+impl TypeRef {
+    fn nullable(self) -> TypeRef {
+        match self {
+            TypeRef::Nullable(_) => self,
+            other => TypeRef::Nullable(Box::new(other)),
+        }
+    }
+
+    // The type without its outer `Nullable`, and whether there was one
+    pub fn strip_nullable(&self) -> (&TypeRef, bool) {
+        match self {
+            TypeRef::Nullable(inner) => (inner, true),
+            other => (other, false),
+        }
+    }
+}
+
+pub struct Field {
+    // The JSON key, exactly as it appears in the data
+    pub key: String,
+    pub ty: TypeRef,
+    // Present in every sample (or listed in the schema's `required`)
+    pub required: bool,
+}
+
+pub struct Variant {
+    // A name for the alternative, e.g. `String` or `Object`
+    pub name: String,
+    pub ty: TypeRef,
+}
+
+pub enum TypeDefKind {
+    Struct(Vec<Field>),
+    // A string restricted to a few known values
+    StringEnum(Vec<String>),
+    // A value that can be one of several types
+    Union(Vec<Variant>),
+    // Another name for a type defined elsewhere
+    Alias(TypeRef),
+}
+
+pub struct TypeDef {
+    pub name: String,
+    pub kind: TypeDefKind,
+}
+
+// For each definition, the names of the definitions it reaches
+pub type References = HashMap<String, HashSet<String>>;
+
+pub struct TypeModel {
+    pub root_name: String,
+    pub root: TypeRef,
+    // Definitions, parents before the types they use
+    pub defs: Vec<TypeDef>,
+}
+
+impl TypeModel {
+    // `reserved` are names the target language's output relies on, which a
+    // type of the same name would shadow
+    pub fn from_shape(shape: &Shape, root_name: &str, reserved: &[&str]) -> TypeModel {
+        let mut builder = ModelBuilder::new(reserved);
+        let root = builder.shape_type(shape, root_name);
+        builder.finish(root_name, root)
+    }
+
+    pub fn from_schema(schema: &Value, root_name: &str, reserved: &[&str]) -> Result<TypeModel, String> {
+        if !schema.is_object() && !schema.is_boolean() {
+            return Err("A JSON Schema must be an object or a boolean.".to_string());
+        }
+        let mut builder = ModelBuilder::new(reserved);
+        let root = builder.schema_type(schema, schema, root_name)?;
+        Ok(builder.finish(root_name, root))
+    }
+
+    // Whether any type anywhere in the model matches `predicate`
+    pub fn uses(&self, predicate: impl Fn(&TypeRef) -> bool + Copy) -> bool {
+        fn visit(ty: &TypeRef, predicate: impl Fn(&TypeRef) -> bool + Copy) -> bool {
+            predicate(ty)
+                || match ty {
+                    TypeRef::Array(inner) | TypeRef::Map(inner) | TypeRef::Nullable(inner) => {
+                        visit(inner, predicate)
+                    }
+                    _ => false,
+                }
+        }
+        visit(&self.root, predicate)
+            || self.defs.iter().any(|def| match &def.kind {
+                TypeDefKind::Struct(fields) => fields.iter().any(|f| visit(&f.ty, predicate)),
+                TypeDefKind::StringEnum(_) => false,
+                TypeDefKind::Union(variants) => variants.iter().any(|v| visit(&v.ty, predicate)),
+                TypeDefKind::Alias(ty) => visit(ty, predicate),
+            })
+    }
+
+    // For each definition, the definitions it refers to, directly or through
+    // others. Without `through_collections` only references held by value
+    // count, i.e. not those inside arrays or maps.
+    pub fn references(&self, through_collections: bool) -> References {
+        fn names<'a>(ty: &'a TypeRef, through_collections: bool, out: &mut Vec<&'a str>) {
+            match ty {
+                TypeRef::Named(name) => out.push(name),
+                TypeRef::Nullable(inner) => names(inner, through_collections, out),
+                TypeRef::Array(inner) | TypeRef::Map(inner) if through_collections => {
+                    names(inner, through_collections, out)
+                }
+                _ => {}
+            }
+        }
+        let direct: HashMap<&str, Vec<&str>> = self
+            .defs
+            .iter()
+            .map(|def| {
+                let mut out = Vec::new();
+                match &def.kind {
+                    TypeDefKind::Struct(fields) => {
+                        fields.iter().for_each(|f| names(&f.ty, through_collections, &mut out))
+                    }
+                    TypeDefKind::StringEnum(_) => {}
+                    TypeDefKind::Union(variants) => {
+                        variants.iter().for_each(|v| names(&v.ty, through_collections, &mut out))
+                    }
+                    TypeDefKind::Alias(ty) => names(ty, through_collections, &mut out),
+                }
+                (def.name.as_str(), out)
+            })
+            .collect();
+        direct
+            .keys()
+            .map(|&start| {
+                let mut seen = HashSet::new();
+                let mut stack = direct[start].clone();
+                while let Some(name) = stack.pop() {
+                    if seen.insert(name.to_string()) {
+                        stack.extend(direct.get(name).into_iter().flatten());
+                    }
+                }
+                (start.to_string(), seen)
+            })
+            .collect()
+    }
+}
+
+#[derive(Default)]
+struct ModelBuilder {
+    defs: Vec<Option<TypeDef>>,
+    used_names: HashSet<String>,
+    // `$ref` targets already given a type, so recursive schemas terminate
+    refs: HashMap<String, TypeRef>,
+}
+
+impl ModelBuilder {
+    fn new(reserved: &[&str]) -> Self {
+        ModelBuilder {
+            used_names: reserved.iter().map(|name| name.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    // The root is named after its definition, or gets an alias name when it
+    // isn't a definition of its own (e.g. an array of structs).
+    fn finish(mut self, root_name: &str, root: TypeRef) -> TypeModel {
+        let root_name = match &root {
+            TypeRef::Named(name) => name.clone(),
+            _ => self.unique_name(root_name),
+        };
+        TypeModel {
+            root_name,
+            root,
+            defs: self.defs.into_iter().flatten().collect(),
+        }
+    }
+
+    fn unique_name(&mut self, hint: &str) -> String {
+        let mut base = pascal_case(hint);
+        if base.is_empty() {
+            base = "Value".to_string();
+        } else if base.starts_with(|c: char| c.is_ascii_digit()) {
+            base.insert(0, 'T');
+        }
+        let mut name = base.clone();
+        let mut n = 2;
+        while !self.used_names.insert(name.clone()) {
+            name = format!("{}{}", base, n);
+            n += 1;
+        }
+        name
+    }
+
+    // Reserves a definition's place before its children are visited, so
+    // parents come out ahead of the types they contain.
+    fn reserve(&mut self) -> usize {
+        self.defs.push(None);
+        self.defs.len() - 1
+    }
+
+    fn define(&mut self, slot: usize, name: &str, kind: TypeDefKind) -> TypeRef {
+        self.defs[slot] = Some(TypeDef {
+            name: name.to_string(),
+            kind,
+        });
+        TypeRef::Named(name.to_string())
+    }
+
+    fn shape_type(&mut self, shape: &Shape, name: &str) -> TypeRef {
+        let kinds = [
+            shape.booleans > 0,
+            shape.integers + shape.floats > 0,
+            shape.strings.is_some(),
+            shape.array.is_some(),
+            shape.object.is_some(),
+        ];
+        let union = self.begin_union(name, kinds.iter().filter(|&&k| k).count());
+        let mut variants = Vec::new();
+        if shape.booleans > 0 {
+            variants.push(("Bool".to_string(), TypeRef::Bool));
+        }
+        if shape.floats > 0 {
+            variants.push(("Float".to_string(), TypeRef::Float));
+        } else if shape.integers > 0 {
+            variants.push(("Integer".to_string(), TypeRef::Integer));
+        }
+        if let Some(strings) = &shape.strings {
+            let ty = match strings.enum_values() {
+                Some(values) => {
+                    let enum_name = self.unique_name(&member_name(&union, name, "Kind"));
+                    let slot = self.reserve();
+                    let values = values.into_iter().map(str::to_string).collect();
+                    self.define(slot, &enum_name, TypeDefKind::StringEnum(values))
+                }
+                None => TypeRef::String,
+            };
+            variants.push(("String".to_string(), ty));
+        }
+        if let Some(array) = &shape.array {
+            let items = self.shape_type(&array.items, &item_name(name));
+            variants.push(("Array".to_string(), TypeRef::Array(Box::new(items))));
+        }
+        if let Some(object) = &shape.object {
+            let struct_name = self.unique_name(&member_name(&union, name, "Object"));
+            let slot = self.reserve();
+            let fields = object
+                .fields
+                .iter()
+                .map(|(key, field)| Field {
+                    key: key.clone(),
+                    ty: self.shape_type(field, key),
+                    required: object.is_required(field),
+                })
+                .collect();
+            let ty = self.define(slot, &struct_name, TypeDefKind::Struct(fields));
+            variants.push(("Object".to_string(), ty));
+        }
+        let ty = self.end_union(union, variants);
+        if shape.nulls > 0 { ty.nullable() } else { ty }
+    }
+
+    // A union claims its name and slot before its alternatives are visited,
+    // so it gets the plain name and they are named after it (`ValueObject`).
+    fn begin_union(&mut self, name: &str, alternatives: usize) -> Option<(String, usize)> {
+        (alternatives > 1).then(|| (self.unique_name(name), self.reserve()))
+    }
+
+    // `Any` for no alternatives, the type itself for one, and the union
+    // started by `begin_union` otherwise.
+    fn end_union(&mut self, union: Option<(String, usize)>, mut variants: Vec<(String, TypeRef)>) -> TypeRef {
+        match union {
+            Some((union_name, slot)) => {
+                let variants = variants
+                    .into_iter()
+                    .map(|(name, ty)| Variant { name, ty })
+                    .collect();
+                self.define(slot, &union_name, TypeDefKind::Union(variants))
+            }
+            None if variants.is_empty() => TypeRef::Any,
+            None => variants.remove(0).1,
+        }
+    }
+
+    fn schema_type(&mut self, schema: &Value, root: &Value, name: &str) -> Result<TypeRef, String> {
+        let Value::Object(map) = schema else {
+            // `true` accepts anything; `false` accepts nothing, which no
+            // language can spell either
+            return Ok(TypeRef::Any);
+        };
+        if let Some(reference) = map.get("$ref").and_then(Value::as_str) {
+            return self.schema_ref(reference, root);
+        }
+        for keyword in ["anyOf", "oneOf"] {
+            if let Some(alternatives) = map.get(keyword).and_then(Value::as_array) {
+                return self.schema_alternatives(alternatives, root, name);
+            }
+        }
+        if let Some(values) = map.get("enum").and_then(Value::as_array) {
+            let nullable = values.iter().any(Value::is_null);
+            let strings: Option<Vec<String>> = values
+                .iter()
+                .filter(|v| !v.is_null())
+                .map(|v| v.as_str().map(str::to_string))
+                .collect();
+            if let Some(strings) = strings.filter(|s| !s.is_empty()) {
+                let enum_name = self.unique_name(name);
+                let slot = self.reserve();
+                let ty = self.define(slot, &enum_name, TypeDefKind::StringEnum(strings));
+                return Ok(if nullable { ty.nullable() } else { ty });
+            }
+        }
+
+        let types: Vec<&str> = match map.get("type") {
+            Some(Value::String(t)) => vec![t.as_str()],
+            Some(Value::Array(ts)) => ts.iter().filter_map(Value::as_str).collect(),
+            // Guess from the keywords present
+            _ if map.contains_key("properties") || map.contains_key("additionalProperties") => {
+                vec!["object"]
+            }
+            _ if map.contains_key("items") => vec!["array"],
+            _ => Vec::new(),
+        };
+        let non_null: Vec<&str> = types.iter().copied().filter(|&t| t != "null").collect();
+        let union = self.begin_union(name, non_null.len());
+        let mut variants = Vec::new();
+        for ty in non_null {
+            let variant = match ty {
+                "boolean" => ("Bool", TypeRef::Bool),
+                "integer" => ("Integer", TypeRef::Integer),
+                "number" => ("Float", TypeRef::Float),
+                "string" => ("String", TypeRef::String),
+                "array" => {
+                    let items = match map.get("items") {
+                        Some(items) => self.schema_type(items, root, &item_name(name))?,
+                        None => TypeRef::Any,
+                    };
+                    ("Array", TypeRef::Array(Box::new(items)))
+                }
+                "object" => ("Object", self.schema_object(map, root, &member_name(&union, name, "Object"))?),
+                other => return Err(format!("Unknown type \"{}\"", other)),
+            };
+            variants.push((variant.0.to_string(), variant.1));
+        }
+        let ty = self.end_union(union, variants);
+        Ok(if types.contains(&"null") { ty.nullable() } else { ty })
+    }
+
+    fn schema_object(
+        &mut self,
+        map: &serde_json::Map<String, Value>,
+        root: &Value,
+        name: &str,
+    ) -> Result<TypeRef, String> {
+        let properties = map.get("properties").and_then(Value::as_object);
+        let additional = map.get("additionalProperties").filter(|v| v.is_object());
+        if let (None, Some(values)) = (properties, additional) {
+            let values = self.schema_type(values, root, &item_name(name))?;
+            return Ok(TypeRef::Map(Box::new(values)));
+        }
+        let required: Vec<&str> = map
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|r| r.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        let struct_name = self.unique_name(name);
+        let slot = self.reserve();
+        let mut fields = Vec::new();
+        for (key, property) in properties.into_iter().flatten() {
+            fields.push(Field {
+                key: key.clone(),
+                ty: self.schema_type(property, root, key)?,
+                required: required.contains(&key.as_str()),
+            });
+        }
+        Ok(self.define(slot, &struct_name, TypeDefKind::Struct(fields)))
+    }
+
+    fn schema_alternatives(&mut self, alternatives: &[Value], root: &Value, name: &str) -> Result<TypeRef, String> {
+        let is_null = |s: &Value| s.get("type").and_then(Value::as_str) == Some("null");
+        let nullable = alternatives.iter().any(is_null);
+        let non_null: Vec<&Value> = alternatives.iter().filter(|s| !is_null(s)).collect();
+        let union = self.begin_union(name, non_null.len());
+        let mut types = Vec::new();
+        for (index, alternative) in non_null.into_iter().enumerate() {
+            let hint = member_name(&union, name, &format!("Variant{}", index + 1));
+            types.push(self.schema_type(alternative, root, &hint)?);
+        }
+        // Name each alternative after its type where that's unambiguous
+        let names: Vec<String> = types.iter().map(variant_name).collect();
+        let distinct: HashSet<&String> = names.iter().collect();
+        let variants = names
+            .iter()
+            .zip(types)
+            .enumerate()
+            .map(|(index, (variant, ty))| {
+                if distinct.len() == names.len() {
+                    (variant.clone(), ty)
+                } else {
+                    (format!("Variant{}", index + 1), ty)
+                }
+            })
+            .collect();
+        let ty = self.end_union(union, variants);
+        Ok(if nullable { ty.nullable() } else { ty })
+    }
+
+    // Only local references (`#/$defs/Name`, `#/definitions/Name`, ...) are
+    // followed; the type is named after the last pointer segment.
+    fn schema_ref(&mut self, reference: &str, root: &Value) -> Result<TypeRef, String> {
+        if let Some(ty) = self.refs.get(reference) {
+            return Ok(ty.clone());
+        }
+        let pointer = reference
+            .strip_prefix('#')
+            .ok_or_else(|| format!("Only local references are supported, not \"{}\"", reference))?;
+        let target = root
+            .pointer(pointer)
+            .ok_or_else(|| format!("Reference \"{}\" does not resolve", reference))?;
+        let hint = match pointer.rsplit('/').next() {
+            Some(last) if !last.is_empty() => last.replace("~1", "/").replace("~0", "~"),
+            _ => "Root".to_string(),
+        };
+        // Register the name first so a self-reference resolves to it
+        let name = self.unique_name(&hint);
+        self.refs.insert(reference.to_string(), TypeRef::Named(name.clone()));
+        // Used if the target turns out to be another reference's definition
+        let alias_slot = self.reserve();
+        let ty = self.schema_type(target, root, &name)?;
+        let (inner, nullable) = ty.strip_nullable();
+        let named = match inner {
+            // A reference to itself and nothing else
+            TypeRef::Named(defined) if *defined == name => TypeRef::Any,
+            // That definition keeps its name, which other references use
+            TypeRef::Named(defined) if self.is_ref_name(defined) => {
+                self.define(alias_slot, &name, TypeDefKind::Alias(inner.clone()))
+            }
+            // The definition made for this reference was given a fresh name;
+            // the reserved one is the one self-references used, so it takes that
+            TypeRef::Named(defined) => {
+                if let Some(def) = self.defs.iter_mut().flatten().find(|def| def.name == *defined) {
+                    def.name = name.clone();
+                }
+                TypeRef::Named(name)
+            }
+            // Not a definition of its own (e.g. a plain string); use it directly
+            _ => {
+                self.refs.remove(reference);
+                return Ok(ty);
+            }
+        };
+        let ty = if nullable { named.nullable() } else { named };
+        self.refs.insert(reference.to_string(), ty.clone());
+        Ok(ty)
+    }
+
+    fn is_ref_name(&self, name: &str) -> bool {
+        self.refs
+            .values()
+            .any(|ty| matches!(ty.strip_nullable().0, TypeRef::Named(named) if named == name))
+    }
+}
+
+fn variant_name(ty: &TypeRef) -> String {
+    match ty.strip_nullable().0 {
+        TypeRef::Any => "Any".to_string(),
+        TypeRef::Bool => "Bool".to_string(),
+        TypeRef::Integer => "Integer".to_string(),
+        TypeRef::Float => "Float".to_string(),
+        TypeRef::String => "String".to_string(),
+        TypeRef::Array(_) => "Array".to_string(),
+        TypeRef::Map(_) => "Map".to_string(),
+        TypeRef::Named(name) => name.clone(),
+        TypeRef::Nullable(_) => "Nullable".to_string(),
+    }
+}
+
+// The name hint for one alternative of a union named `name`
+fn member_name(union: &Option<(String, usize)>, name: &str, suffix: &str) -> String {
+    match union {
+        Some((union_name, _)) => format!("{}{}", union_name, suffix),
+        None => name.to_string(),
+    }
+}
+
+// The name for elements of an array called `name`: `users` gives `User`,
+// anything that doesn't look plural gets an `Item` suffix.
+fn item_name(name: &str) -> String {
+    let singular = name
+        .strip_suffix("ies")
+        .map(|stem| format!("{}y", stem))
+        .or_else(|| {
+            name.strip_suffix('s')
+                .filter(|stem| stem.len() > 1 && !stem.ends_with(['s', 'u', 'i']))
+                .map(str::to_string)
+        });
+    match singular {
+        Some(singular) => singular,
+        None => format!("{}Item", pascal_case(name)),
+    }
+}
+
+// Splits a key into words at separators and case changes, so `userID`,
+// `user_id` and `user-id` all give ["user", "ID"] or ["user", "id"].
+fn words(text: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, &(offset, c)) in chars.iter().enumerate() {
+        if !c.is_ascii_alphanumeric() {
+            if let Some(s) = start.take() {
+                words.push(&text[s..offset]);
+            }
+            continue;
+        }
+        let prev = i.checked_sub(1).map(|p| chars[p].1);
+        let next = chars.get(i + 1).map(|&(_, n)| n);
+        let boundary = match prev {
+            Some(p) if c.is_ascii_uppercase() => {
+                p.is_ascii_lowercase()
+                    || p.is_ascii_digit()
+                    || (p.is_ascii_uppercase() && next.is_some_and(|n| n.is_ascii_lowercase()))
+            }
+            _ => false,
+        };
+        match start {
+            Some(s) if boundary => {
+                words.push(&text[s..offset]);
+                start = Some(offset);
+            }
+            Some(_) => {}
+            None => start = Some(offset),
+        }
+    }
+    if let Some(s) = start {
+        words.push(&text[s..]);
+    }
+    words
+}
+
+pub fn pascal_case(text: &str) -> String {
+    words(text)
+        .into_iter()
+        .map(|word| {
+            let lower = word.to_ascii_lowercase();
+            let mut chars = lower.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+pub fn snake_case(text: &str) -> String {
+    words(text)
+        .into_iter()
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+// Makes `name` a usable identifier: never empty, never starting with a
+// digit, and unique among `taken`.
+pub fn unique_identifier(name: String, fallback: &str, taken: &mut HashSet<String>) -> String {
+    let base = if name.is_empty() {
+        fallback.to_string()
+    } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("{}{}", fallback, name)
+    } else {
+        name
+    };
+    let mut unique = base.clone();
+    let mut n = 2;
+    while !taken.insert(unique.clone()) {
+        unique = format!("{}{}", base, n);
+        n += 1;
+    }
+    unique
+}
+
+// A string literal in the double-quoted syntax shared by most languages
+pub fn quoted(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or_default()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Language {
+    Rust,
+    TypeScript,
+    Zod,
+    Go,
+    PythonDataclass,
+    PythonTypedDict,
+}
+
+impl Language {
+    pub const ALL: [Language; 6] = [
+        Language::Rust,
+        Language::TypeScript,
+        Language::Zod,
+        Language::Go,
+        Language::PythonDataclass,
+        Language::PythonTypedDict,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Language::Rust => "Rust (serde)",
+            Language::TypeScript => "TypeScript interfaces",
+            Language::Zod => "TypeScript (Zod)",
+            Language::Go => "Go structs",
+            Language::PythonDataclass => "Python dataclasses",
+            Language::PythonTypedDict => "Python TypedDicts",
+        }
+    }
+
+    pub fn reserved_type_names(self) -> &'static [&'static str] {
+        match self {
+            Language::Rust => rust::RESERVED_TYPE_NAMES,
+            Language::TypeScript | Language::Zod => typescript::RESERVED_TYPE_NAMES,
+            Language::Go => &[],
+            Language::PythonDataclass | Language::PythonTypedDict => python::RESERVED_TYPE_NAMES,
+        }
+    }
+
+    pub fn generate(self, model: &TypeModel) -> String {
+        match self {
+            Language::Rust => rust::generate(model),
+            Language::TypeScript => typescript::generate(model),
+            Language::Zod => typescript::generate_zod(model),
+            Language::Go => go::generate(model),
+            Language::PythonDataclass => python::generate(model, python::Style::Dataclass),
+            Language::PythonTypedDict => python::generate(model, python::Style::TypedDict),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CodegenSource {
+    // The document is one sample; arrays merge their elements
+    Document,
+    NdjsonLines,
+    // The document is a JSON Schema describing the payload
+    JsonSchema,
+}
+
+impl CodegenSource {
+    const ALL: [CodegenSource; 3] = [
+        CodegenSource::Document,
+        CodegenSource::NdjsonLines,
+        CodegenSource::JsonSchema,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            CodegenSource::Document => "Sample document",
+            CodegenSource::NdjsonLines => "NDJSON lines as samples",
+            CodegenSource::JsonSchema => "Document is a JSON Schema",
+        }
+    }
+}
+
+pub fn generate_code(text: &str, source: CodegenSource, language: Language, root_name: &str) -> Result<String, String> {
+    let reserved = language.reserved_type_names();
+    let model = match source {
+        CodegenSource::Document => {
+            TypeModel::from_shape(&Shape::from_samples([&parse_json_to_value(text)?]), root_name, reserved)
+        }
+        CodegenSource::NdjsonLines => TypeModel::from_shape(&Shape::from_samples(&parse_ndjson(text)?), root_name, reserved),
+        CodegenSource::JsonSchema => TypeModel::from_schema(&parse_json_to_value(text)?, root_name, reserved)?,
+    };
+    Ok(language.generate(&model))
+}
+
+// Settings and output of the code generation window. The code is
+// regenerated whenever the document or a setting changes.
+pub struct CodegenPanel {
+    source: CodegenSource,
+    language: Language,
+    root_name: String,
+    output: Result<String, String>,
+    generated_for: Option<u64>, // Hash of the input and settings behind `output`
+}
+
+impl Default for CodegenPanel {
+    fn default() -> Self {
+        CodegenPanel {
+            source: CodegenSource::Document,
+            language: Language::Rust,
+            root_name: "Root".to_string(),
+            output: Ok(String::new()),
+            generated_for: None,
+        }
+    }
+}
+
+impl CodegenPanel {
+    pub fn show(&mut self, ui: &mut egui::Ui, text: &str) {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("codegen_language")
+                .selected_text(self.language.label())
+                .show_ui(ui, |ui| {
+                    for language in Language::ALL {
+                        ui.selectable_value(&mut self.language, language, language.label());
+                    }
+                });
+            egui::ComboBox::from_id_salt("codegen_source")
+                .selected_text(self.source.label())
+                .show_ui(ui, |ui| {
+                    for source in CodegenSource::ALL {
+                        ui.selectable_value(&mut self.source, source, source.label());
+                    }
+                });
+            ui.label("Root type:");
+            ui.add(egui::TextEdit::singleline(&mut self.root_name).desired_width(100.0));
+        });
+
+        let mut hasher = DefaultHasher::new();
+        (text, self.source, self.language, &self.root_name).hash(&mut hasher);
+        let key = hasher.finish();
+        if self.generated_for != Some(key) {
+            self.output = if text.trim().is_empty() {
+                Ok(String::new())
+            } else {
+                generate_code(text, self.source, self.language, &self.root_name)
+            };
+            self.generated_for = Some(key);
+        }
+
+        match &self.output {
+            Ok(code) => {
+                if ui.add_enabled(!code.is_empty(), egui::Button::new("Copy")).clicked() {
+                    ui.ctx().copy_text(code.clone());
+                }
+                egui::ScrollArea::both().id_salt("codegen_output_scroll").show(ui, |ui| {
+                    let mut preview = code.as_str();
+                    ui.add(
+                        egui::TextEdit::multiline(&mut preview)
+                            .code_editor()
+                            .desired_width(f32::INFINITY),
+                    );
+                });
+            }
+            Err(e) => {
+                ui.colored_label(Color32::RED, e);
+            }
+        }
+    }
+}
@@ -0,0 +1,222 @@
+// Python dataclasses or TypedDicts. Both are written children first because
+// aliases and functional TypedDicts are evaluated at import time; a name used
+// before its definition (recursion) is written as a string forward reference.
+use std::collections::HashSet;
+
+use super::{Field, TypeDef, TypeDefKind, TypeModel, TypeRef, quoted, snake_case, unique_identifier};
+
+const KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del",
+    "elif", "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "nonlocal",
+    "not", "or", "pass", "raise", "return", "try", "while", "with", "yield",
+];
+
+const TYPING_NAMES: &[&str] = &["Any", "Dict", "List", "Literal", "NotRequired", "Optional", "TypedDict", "Union"];
+
+// The `typing` imports, and keywords a class name could collide with
+pub const RESERVED_TYPE_NAMES: &[&str] = &[
+    "Any", "Dict", "List", "Literal", "NotRequired", "Optional", "TypedDict", "Union", "False", "None", "True",
+];
+
+#[derive(Clone, Copy)]
+pub enum Style {
+    Dataclass,
+    TypedDict,
+}
+
+pub fn generate(model: &TypeModel, style: Style) -> String {
+    let mut body = String::new();
+    let mut defined = HashSet::new();
+    for def in model.defs.iter().rev() {
+        body.push_str("\n\n");
+        write_def(&mut body, def, style, &defined);
+        defined.insert(def.name.clone());
+    }
+    if !matches!(model.root, TypeRef::Named(_)) {
+        body.push_str(&format!("\n\n{} = {}\n", model.root_name, py_type(&model.root, &defined)));
+    }
+
+    // Import only what the definitions ended up using
+    let mut out = String::new();
+    if matches!(style, Style::Dataclass) {
+        out.push_str("from dataclasses import dataclass\n");
+    }
+    let used = code_words(&body);
+    let typing: Vec<&str> = TYPING_NAMES.iter().copied().filter(|name| used.contains(name)).collect();
+    if !typing.is_empty() {
+        out.push_str(&format!("from typing import {}\n", typing.join(", ")));
+    }
+    out.push_str(&body);
+    out
+}
+
+// The identifiers in `body`, skipping string literals and comments
+fn code_words(body: &str) -> HashSet<&str> {
+    let mut words = HashSet::new();
+    for line in body.lines() {
+        let mut in_string = false;
+        let mut escaped = false;
+        let mut word_start = None;
+        for (i, c) in line.char_indices() {
+            if in_string {
+                match c {
+                    _ if escaped => escaped = false,
+                    '\\' => escaped = true,
+                    '"' => in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+            let is_word = c.is_ascii_alphanumeric() || c == '_';
+            match (word_start, is_word) {
+                (None, true) => word_start = Some(i),
+                (Some(start), false) => {
+                    words.insert(&line[start..i]);
+                    word_start = None;
+                }
+                _ => {}
+            }
+            match c {
+                '"' => in_string = true,
+                '#' => break,
+                _ => {}
+            }
+        }
+        if let Some(start) = word_start {
+            words.insert(&line[start..]);
+        }
+    }
+    words
+}
+
+fn write_def(out: &mut String, def: &TypeDef, style: Style, defined: &HashSet<String>) {
+    match &def.kind {
+        TypeDefKind::Struct(fields) => match style {
+            Style::Dataclass => write_dataclass(out, &def.name, fields, defined),
+            Style::TypedDict => write_typed_dict(out, &def.name, fields, defined),
+        },
+        TypeDefKind::StringEnum(values) => {
+            let values: Vec<String> = values.iter().map(|v| quoted(v)).collect();
+            out.push_str(&format!("{} = Literal[{}]\n", def.name, values.join(", ")));
+        }
+        TypeDefKind::Union(variants) => {
+            let types: Vec<String> = variants.iter().map(|v| py_type(&v.ty, defined)).collect();
+            out.push_str(&format!("{} = Union[{}]\n", def.name, types.join(", ")));
+        }
+        TypeDefKind::Alias(ty) => {
+            out.push_str(&format!("{} = {}\n", def.name, py_type(ty, defined)));
+        }
+    }
+}
+
+// Dataclass fields are snake_case, with the JSON key noted where it differs.
+// Fields without a default have to come first, so missing-able ones move to
+// the end with `= None`.
+fn write_dataclass(out: &mut String, name: &str, fields: &[Field], defined: &HashSet<String>) {
+    out.push_str(&format!("@dataclass\nclass {}:\n", name));
+    let mut taken = HashSet::new();
+    let mut lines: Vec<(bool, String)> = fields
+        .iter()
+        .map(|field| {
+            let mut attribute = unique_identifier(snake_case(&field.key), "field_", &mut taken);
+            if KEYWORDS.contains(&attribute.as_str()) {
+                attribute.push('_');
+            }
+            let ty = dataclass_field_type(field, defined);
+            let default = if field.required { "" } else { " = None" };
+            let note = if attribute == field.key { String::new() } else { format!("  # {}", quoted(&field.key)) };
+            (field.required, format!("    {}: {}{}{}\n", attribute, ty, default, note))
+        })
+        .collect();
+    lines.sort_by_key(|(required, _)| !required);
+    for (_, line) in &lines {
+        out.push_str(line);
+    }
+    if lines.is_empty() {
+        out.push_str("    pass\n");
+    }
+}
+
+// TypedDict keys must match the JSON exactly; the class syntax only works
+// when every key is a valid identifier, so otherwise the functional form is
+// used.
+fn write_typed_dict(out: &mut String, name: &str, fields: &[Field], defined: &HashSet<String>) {
+    let members: Vec<(&str, String)> = fields
+        .iter()
+        .map(|field| {
+            // Missing and null are different things to a TypedDict
+            let ty = py_type(&field.ty, defined);
+            let ty = if field.required { ty } else { format!("NotRequired[{}]", ty) };
+            (field.key.as_str(), ty)
+        })
+        .collect();
+    if members.iter().all(|(key, _)| is_identifier(key)) {
+        out.push_str(&format!("class {}(TypedDict):\n", name));
+        for (key, ty) in &members {
+            out.push_str(&format!("    {}: {}\n", key, ty));
+        }
+        if members.is_empty() {
+            out.push_str("    pass\n");
+        }
+    } else {
+        out.push_str(&format!("{} = TypedDict({}, {{\n", name, quoted(name)));
+        for (key, ty) in &members {
+            out.push_str(&format!("    {}: {},\n", quoted(key), ty));
+        }
+        out.push_str("})\n");
+    }
+}
+
+// A dataclass field defaulting to None must accept None
+fn dataclass_field_type(field: &Field, defined: &HashSet<String>) -> String {
+    let (inner, nullable) = field.ty.strip_nullable();
+    if nullable || !field.required {
+        format!("Optional[{}]", py_type(inner, defined))
+    } else {
+        py_type(inner, defined)
+    }
+}
+
+fn py_type(ty: &TypeRef, defined: &HashSet<String>) -> String {
+    match ty {
+        TypeRef::Any => "Any".to_string(),
+        TypeRef::Bool => "bool".to_string(),
+        TypeRef::Integer => "int".to_string(),
+        TypeRef::Float => "float".to_string(),
+        TypeRef::String => "str".to_string(),
+        TypeRef::Array(inner) => format!("List[{}]", py_type(inner, defined)),
+        TypeRef::Map(inner) => format!("Dict[str, {}]", py_type(inner, defined)),
+        TypeRef::Named(name) if defined.contains(name) => name.clone(),
+        TypeRef::Named(name) => quoted(name),
+        TypeRef::Nullable(inner) => format!("Optional[{}]", py_type(inner, defined)),
+    }
+}
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&key)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::infer::Shape;
+
+    #[test]
+    fn classes_never_take_keyword_names() {
+        let document = json!({ "none": { "a": 1 }, "true": { "a": 1 }, "false": { "a": 1 }, "list": { "a": 1 } });
+        let model = TypeModel::from_shape(&Shape::from_samples([&document]), "Root", RESERVED_TYPE_NAMES);
+        for style in [Style::Dataclass, Style::TypedDict] {
+            let code = generate(&model, style);
+            for name in ["None", "True", "False", "List"] {
+                assert!(!code.contains(&format!("class {}:", name)), "{}", code);
+                assert!(!code.contains(&format!("class {}(", name)), "{}", code);
+            }
+            assert!(code.contains("class None2"), "{}", code);
+        }
+    }
+}
@@ -0,0 +1,205 @@
+// Rust types with serde derives. Fields are snake_case with
+// `#[serde(rename)]` where that differs from the key, values that can be
+// missing or null are `Option`, and unions are untagged enums.
+use std::collections::{HashMap, HashSet};
+
+use super::{References, TypeDef, TypeDefKind, TypeModel, TypeRef, pascal_case, quoted, snake_case, unique_identifier};
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false", "fn", "for",
+    "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "static", "struct",
+    "trait", "true", "type", "unsafe", "use", "where", "while", "abstract", "become", "box", "do", "final", "gen",
+    "macro", "override", "priv", "try", "typeof", "unsized", "virtual", "yield",
+];
+
+// Keywords that can't be raw identifiers
+const RESERVED: &[&str] = &["self", "Self", "super", "crate", "_"];
+
+// Type names the generated code uses or that can't be declared
+pub const RESERVED_TYPE_NAMES: &[&str] =
+    &["Box", "Deserialize", "HashMap", "Option", "Result", "Self", "Serialize", "String", "Value", "Vec"];
+
+pub fn generate(model: &TypeModel) -> String {
+    let mut out = String::from("use serde::{Deserialize, Serialize};\n");
+    if model.uses(|ty| matches!(ty, TypeRef::Map(_))) {
+        out.push_str("use std::collections::HashMap;\n");
+    }
+    if !matches!(model.root, TypeRef::Named(_)) {
+        out.push_str(&format!("\npub type {} = {};\n", model.root_name, rust_type(&model.root, "", &HashMap::new())));
+    }
+    let reach = model.references(false);
+    for def in &model.defs {
+        out.push('\n');
+        write_def(&mut out, def, &reach);
+    }
+    out
+}
+
+fn write_def(out: &mut String, def: &TypeDef, reach: &References) {
+    match &def.kind {
+        TypeDefKind::Struct(fields) => {
+            out.push_str("#[derive(Debug, Clone, Serialize, Deserialize)]\n");
+            out.push_str(&format!("pub struct {} {{\n", def.name));
+            let mut taken = HashSet::new();
+            for field in fields {
+                let name = identifier(unique_identifier(snake_case(&field.key), "field_", &mut taken));
+                let mut attributes = Vec::new();
+                // serde strips the `r#` of raw identifiers itself
+                if name.trim_start_matches("r#") != field.key {
+                    attributes.push(format!("rename = {}", quoted(&field.key)));
+                }
+                let (inner, nullable) = field.ty.strip_nullable();
+                let ty = if nullable || !field.required {
+                    format!("Option<{}>", rust_type(inner, &def.name, reach))
+                } else {
+                    rust_type(inner, &def.name, reach)
+                };
+                if !field.required {
+                    attributes.push("skip_serializing_if = \"Option::is_none\"".to_string());
+                }
+                if !attributes.is_empty() {
+                    out.push_str(&format!("    #[serde({})]\n", attributes.join(", ")));
+                }
+                out.push_str(&format!("    pub {}: {},\n", name, ty));
+            }
+            out.push_str("}\n");
+        }
+        TypeDefKind::StringEnum(values) => {
+            out.push_str("#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]\n");
+            out.push_str(&format!("pub enum {} {{\n", def.name));
+            let mut taken = HashSet::new();
+            for value in values {
+                let variant = unique_identifier(pascal_case(value), "Value", &mut taken);
+                if variant != *value {
+                    out.push_str(&format!("    #[serde(rename = {})]\n", quoted(value)));
+                }
+                out.push_str(&format!("    {},\n", variant));
+            }
+            out.push_str("}\n");
+        }
+        TypeDefKind::Union(variants) => {
+            out.push_str("#[derive(Debug, Clone, Serialize, Deserialize)]\n");
+            out.push_str("#[serde(untagged)]\n");
+            out.push_str(&format!("pub enum {} {{\n", def.name));
+            let mut taken = HashSet::new();
+            for variant in variants {
+                let name = unique_identifier(pascal_case(&variant.name), "Variant", &mut taken);
+                out.push_str(&format!("    {}({}),\n", name, rust_type(&variant.ty, &def.name, reach)));
+            }
+            out.push_str("}\n");
+        }
+        TypeDefKind::Alias(ty) => {
+            out.push_str(&format!("pub type {} = {};\n", def.name, rust_type(ty, "", reach)));
+        }
+    }
+}
+
+// `owner` is the type being defined; a reference that leads back to it by
+// value, directly or through other types, is boxed so recursive types have a
+// size. `Vec` and `HashMap` already add indirection.
+fn rust_type(ty: &TypeRef, owner: &str, reach: &References) -> String {
+    match ty {
+        TypeRef::Any => "serde_json::Value".to_string(),
+        TypeRef::Bool => "bool".to_string(),
+        TypeRef::Integer => "i64".to_string(),
+        TypeRef::Float => "f64".to_string(),
+        TypeRef::String => "String".to_string(),
+        TypeRef::Array(inner) => format!("Vec<{}>", rust_type(inner, "", reach)),
+        TypeRef::Map(inner) => format!("HashMap<String, {}>", rust_type(inner, "", reach)),
+        TypeRef::Named(name) if name == owner || reach.get(name).is_some_and(|names| names.contains(owner)) => {
+            format!("Box<{}>", name)
+        }
+        TypeRef::Named(name) => name.clone(),
+        TypeRef::Nullable(inner) => format!("Option<{}>", rust_type(inner, owner, reach)),
+    }
+}
+
+fn identifier(name: String) -> String {
+    if RESERVED.contains(&name.as_str()) {
+        format!("{}_", name)
+    } else if KEYWORDS.contains(&name.as_str()) {
+        format!("r#{}", name)
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn boxes_mutually_recursive_references() {
+        let schema = json!({
+            "$ref": "#/$defs/A",
+            "$defs": {
+                "A": { "type": "object", "properties": { "b": { "$ref": "#/$defs/B" } } },
+                "B": {
+                    "type": "object",
+                    "properties": {
+                        "a": { "$ref": "#/$defs/A" },
+                        "list": { "type": "array", "items": { "$ref": "#/$defs/B" } }
+                    }
+                }
+            }
+        });
+        let code = generate(&TypeModel::from_schema(&schema, "Root", RESERVED_TYPE_NAMES).unwrap());
+        assert!(code.contains("pub b: Option<Box<B>>"), "{}", code);
+        assert!(code.contains("pub a: Option<Box<A>>"), "{}", code);
+        assert!(code.contains("pub list: Option<Vec<B>>"), "{}", code);
+    }
+
+    #[test]
+    fn nullable_recursive_reference_keeps_its_name() {
+        let schema = json!({
+            "$ref": "#/$defs/Node",
+            "$defs": {
+                "Node": { "type": ["object", "null"], "properties": { "next": { "$ref": "#/$defs/Node" } } }
+            }
+        });
+        let code = generate(&TypeModel::from_schema(&schema, "Root", RESERVED_TYPE_NAMES).unwrap());
+        assert!(code.contains("pub type Root = Option<Node>;"), "{}", code);
+        assert!(code.contains("pub struct Node {"), "{}", code);
+        assert!(code.contains("pub next: Option<Box<Node>>"), "{}", code);
+        assert!(!code.contains("Node2"), "{}", code);
+    }
+
+    #[test]
+    fn reference_to_a_reference_is_an_alias() {
+        let schema = json!({
+            "type": "object",
+            "properties": { "a": { "$ref": "#/$defs/A" }, "b": { "$ref": "#/$defs/B" } },
+            "$defs": {
+                "A": { "$ref": "#/$defs/B" },
+                "B": { "type": "object", "properties": { "id": { "type": "string" } } }
+            }
+        });
+        let code = generate(&TypeModel::from_schema(&schema, "Root", RESERVED_TYPE_NAMES).unwrap());
+        assert!(code.contains("pub type A = B;"), "{}", code);
+        assert!(code.contains("pub struct B {"), "{}", code);
+        assert!(code.contains("pub a: Option<A>"), "{}", code);
+        assert!(code.contains("pub b: Option<B>"), "{}", code);
+    }
+
+    #[test]
+    fn types_never_take_names_the_code_uses() {
+        let document = json!({
+            "_links": { "self": { "href": "/" } },
+            "serialize": { "a": 1 },
+            "deserialize": { "a": 1 },
+            "value": { "a": 1 },
+            "result": { "a": 1 },
+            "option": { "a": 1 },
+            "vec": { "a": 1 },
+            "box": { "a": 1 }
+        });
+        let shape = crate::infer::Shape::from_samples([&document]);
+        let code = generate(&TypeModel::from_shape(&shape, "Root", RESERVED_TYPE_NAMES));
+        for name in ["Self", "Serialize", "Deserialize", "Value", "Result", "Option", "Vec", "Box"] {
+            assert!(!code.contains(&format!("pub struct {} {{", name)), "{}", code);
+            assert!(code.contains(&format!("pub struct {}2 {{", name)), "{}", code);
+        }
+    }
+}
@@ -0,0 +1,185 @@
+// TypeScript interfaces, and Zod schemas with their inferred types. Property
+// names stay exactly as the JSON keys; missing keys are optional (`?`) and
+// null is spelled `| null`.
+use std::collections::HashSet;
+
+use super::{TypeDef, TypeDefKind, TypeModel, TypeRef, quoted};
+
+// Global types a declaration would shadow, including `Array` and `Record`
+// which the generated code uses itself
+pub const RESERVED_TYPE_NAMES: &[&str] = &[
+    "Array", "Boolean", "Date", "Error", "Function", "Map", "Number", "Object", "Promise", "Record", "Set", "String",
+    "Symbol",
+];
+
+pub fn generate(model: &TypeModel) -> String {
+    let mut out = String::new();
+    if !matches!(model.root, TypeRef::Named(_)) {
+        out.push_str(&format!("export type {} = {};\n\n", model.root_name, ts_type(&model.root)));
+    }
+    for def in &model.defs {
+        write_def(&mut out, def);
+        out.push('\n');
+    }
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+fn write_def(out: &mut String, def: &TypeDef) {
+    match &def.kind {
+        TypeDefKind::Struct(fields) => {
+            out.push_str(&format!("export interface {} {{\n", def.name));
+            for field in fields {
+                let optional = if field.required { "" } else { "?" };
+                out.push_str(&format!("  {}{}: {};\n", property_name(&field.key), optional, ts_type(&field.ty)));
+            }
+            out.push_str("}\n");
+        }
+        TypeDefKind::StringEnum(values) => {
+            let values: Vec<String> = values.iter().map(|v| quoted(v)).collect();
+            out.push_str(&format!("export type {} = {};\n", def.name, values.join(" | ")));
+        }
+        TypeDefKind::Union(variants) => {
+            let types: Vec<String> = variants.iter().map(|v| ts_type(&v.ty)).collect();
+            out.push_str(&format!("export type {} = {};\n", def.name, types.join(" | ")));
+        }
+        TypeDefKind::Alias(ty) => {
+            out.push_str(&format!("export type {} = {};\n", def.name, ts_type(ty)));
+        }
+    }
+}
+
+fn ts_type(ty: &TypeRef) -> String {
+    match ty {
+        TypeRef::Any => "unknown".to_string(),
+        TypeRef::Bool => "boolean".to_string(),
+        TypeRef::Integer | TypeRef::Float => "number".to_string(),
+        TypeRef::String => "string".to_string(),
+        // `Array<..>` rather than `..[]` so `string | null` needs no parentheses
+        TypeRef::Array(inner) if matches!(**inner, TypeRef::Nullable(_)) => format!("Array<{}>", ts_type(inner)),
+        TypeRef::Array(inner) => format!("{}[]", ts_type(inner)),
+        TypeRef::Map(inner) => format!("Record<string, {}>", ts_type(inner)),
+        TypeRef::Named(name) => name.clone(),
+        TypeRef::Nullable(inner) => format!("{} | null", ts_type(inner)),
+    }
+}
+
+fn property_name(key: &str) -> String {
+    let mut chars = key.chars();
+    let is_identifier = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if is_identifier { key.to_string() } else { quoted(key) }
+}
+
+// Zod schemas are consts, so they are written children first; anything
+// referenced before it is defined (i.e. recursion) goes through `z.lazy`.
+// TypeScript can't infer the type of a schema that refers to itself, so a
+// recursive type is declared up front and its schema annotated with it.
+pub fn generate_zod(model: &TypeModel) -> String {
+    let mut out = String::from("import { z } from \"zod\";\n");
+    let references = model.references(true);
+    let mut defined = HashSet::new();
+    for def in model.defs.iter().rev() {
+        out.push('\n');
+        let schema = match &def.kind {
+            TypeDefKind::Struct(fields) => {
+                let mut schema = String::from("z.object({\n");
+                for field in fields {
+                    let optional = if field.required { "" } else { ".optional()" };
+                    schema.push_str(&format!(
+                        "  {}: {}{},\n",
+                        property_name(&field.key),
+                        zod_type(&field.ty, &defined),
+                        optional
+                    ));
+                }
+                schema.push_str("})");
+                schema
+            }
+            TypeDefKind::StringEnum(values) => {
+                let values: Vec<String> = values.iter().map(|v| quoted(v)).collect();
+                format!("z.enum([{}])", values.join(", "))
+            }
+            TypeDefKind::Union(variants) => {
+                let types: Vec<String> = variants.iter().map(|v| zod_type(&v.ty, &defined)).collect();
+                format!("z.union([{}])", types.join(", "))
+            }
+            TypeDefKind::Alias(ty) => zod_type(ty, &defined),
+        };
+        if references.get(&def.name).is_some_and(|names| names.contains(&def.name)) {
+            write_def(&mut out, def);
+            out.push_str(&format!("export const {}Schema: z.ZodType<{}> = {};\n", def.name, def.name, schema));
+        } else {
+            write_zod_const(&mut out, &def.name, &schema);
+        }
+        defined.insert(def.name.clone());
+    }
+    if !matches!(model.root, TypeRef::Named(_)) {
+        out.push('\n');
+        write_zod_const(&mut out, &model.root_name, &zod_type(&model.root, &defined));
+    }
+    out
+}
+
+fn write_zod_const(out: &mut String, name: &str, schema: &str) {
+    out.push_str(&format!("export const {}Schema = {};\n", name, schema));
+    out.push_str(&format!("export type {} = z.infer<typeof {}Schema>;\n", name, name));
+}
+
+fn zod_type(ty: &TypeRef, defined: &HashSet<String>) -> String {
+    match ty {
+        TypeRef::Any => "z.unknown()".to_string(),
+        TypeRef::Bool => "z.boolean()".to_string(),
+        TypeRef::Integer => "z.number().int()".to_string(),
+        TypeRef::Float => "z.number()".to_string(),
+        TypeRef::String => "z.string()".to_string(),
+        TypeRef::Array(inner) => format!("z.array({})", zod_type(inner, defined)),
+        TypeRef::Map(inner) => format!("z.record(z.string(), {})", zod_type(inner, defined)),
+        TypeRef::Named(name) if defined.contains(name) => format!("{}Schema", name),
+        TypeRef::Named(name) => format!("z.lazy(() => {}Schema)", name),
+        TypeRef::Nullable(inner) => format!("{}.nullable()", zod_type(inner, defined)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::infer::Shape;
+
+    #[test]
+    fn recursive_zod_schemas_are_annotated() {
+        let schema = json!({
+            "$ref": "#/$defs/Node",
+            "$defs": {
+                "Node": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string" },
+                        "children": { "type": "array", "items": { "$ref": "#/$defs/Node" } }
+                    }
+                }
+            }
+        });
+        let code = generate_zod(&TypeModel::from_schema(&schema, "Root", RESERVED_TYPE_NAMES).unwrap());
+        assert!(code.contains("export interface Node {"), "{}", code);
+        assert!(code.contains("export const NodeSchema: z.ZodType<Node> = z.object({"), "{}", code);
+        assert!(code.contains("children: z.array(z.lazy(() => NodeSchema)).optional(),"), "{}", code);
+        assert!(!code.contains("z.infer<typeof NodeSchema>"), "{}", code);
+    }
+
+    #[test]
+    fn types_never_shadow_globals() {
+        let document = json!({ "record": { "a": 1 }, "array": { "a": 1 }, "date": { "a": 1 }, "object": { "a": 1 } });
+        let model = TypeModel::from_shape(&Shape::from_samples([&document]), "Root", RESERVED_TYPE_NAMES);
+        let code = generate(&model);
+        for name in ["Record", "Array", "Date", "Object"] {
+            assert!(!code.contains(&format!("interface {} {{", name)), "{}", code);
+            assert!(code.contains(&format!("interface {}2 {{", name)), "{}", code);
+        }
+    }
+}
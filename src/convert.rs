@@ -0,0 +1,516 @@
+// Conversion between JSON and other text formats, with the parsed `Value` as
+// the model in the middle. Nothing is dropped silently: whatever a format
+// can't represent is listed as a warning with the JSON path it happened at.
+//
+// XML mapping, both ways:
+//   <root a="1"><b>x</b><b>y</b><c/>text</root>
+//   {"root": {"@a": "1", "b": ["x", "y"], "c": "", "#text": "text"}}
+// - an element with neither attributes nor child elements is its text;
+// - attributes are `@name` keys and text next to them or to children is `#text`;
+// - repeated child elements become an array, a single one stays a value.
+// Every XML value is a string, so numbers, booleans and null don't survive a
+// round trip; on export they are written as text and reported.
+use std::io::Cursor;
+
+use quick_xml::Writer;
+use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+use serde_json::{Map, Number, Value};
+
+use crate::json_path::JsonPath;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataFormat {
+    Yaml,
+    Toml,
+    Xml,
+    Csv,
+}
+
+impl DataFormat {
+    pub const ALL: [DataFormat; 4] = [DataFormat::Yaml, DataFormat::Toml, DataFormat::Xml, DataFormat::Csv];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DataFormat::Yaml => "YAML",
+            DataFormat::Toml => "TOML",
+            DataFormat::Xml => "XML",
+            DataFormat::Csv => "CSV",
+        }
+    }
+
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            DataFormat::Yaml => &["yaml", "yml"],
+            DataFormat::Toml => &["toml"],
+            DataFormat::Xml => &["xml"],
+            DataFormat::Csv => &["csv"],
+        }
+    }
+
+    pub fn import(self, text: &str) -> Result<(Value, Vec<String>), String> {
+        let mut warnings = Warnings::default();
+        let value = match self {
+            DataFormat::Yaml => yaml_import(text, &mut warnings)?,
+            DataFormat::Toml => toml_import(text, &mut warnings)?,
+            DataFormat::Xml => xml_import(text, &mut warnings)?,
+            DataFormat::Csv => csv_import(text)?,
+        };
+        Ok((value, warnings.0))
+    }
+
+    pub fn export(self, value: &Value) -> Result<(String, Vec<String>), String> {
+        let mut warnings = Warnings::default();
+        let text = match self {
+            DataFormat::Yaml => serde_yaml::to_string(value).map_err(|e| e.to_string())?,
+            DataFormat::Toml => toml_export(value, &mut warnings)?,
+            DataFormat::Xml => xml_export(value, &mut warnings)?,
+            DataFormat::Csv => csv_export(value, &mut warnings)?,
+        };
+        Ok((text, warnings.0))
+    }
+}
+
+// Lossy-conversion messages, shared with the binary formats
+#[derive(Default)]
+pub struct Warnings(pub Vec<String>);
+
+impl Warnings {
+    pub fn at(&mut self, path: &JsonPath, message: &str) {
+        self.0.push(format!("{}: {}", path.to_jsonpath(), message));
+    }
+
+    // A warning that applies to the whole document, reported once
+    pub fn once(&mut self, message: &str) {
+        if !self.0.iter().any(|w| w == message) {
+            self.0.push(message.to_string());
+        }
+    }
+}
+
+pub fn float_value(f: f64, path: &JsonPath, warnings: &mut Warnings) -> Value {
+    Number::from_f64(f).map(Value::Number).unwrap_or_else(|| {
+        warnings.at(path, &format!("{} has no JSON equivalent and became null", f));
+        Value::Null
+    })
+}
+
+// YAML
+
+fn yaml_import(text: &str, warnings: &mut Warnings) -> Result<Value, String> {
+    use serde::Deserialize;
+    let mut documents = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(text) {
+        let yaml = serde_yaml::Value::deserialize(document).map_err(|e| format!("Invalid YAML: {}", e))?;
+        documents.push(yaml);
+    }
+    match documents.len() {
+        0 => Ok(Value::Null),
+        1 => Ok(yaml_to_json(&documents[0], &JsonPath::root(), warnings)),
+        n => {
+            warnings.once(&format!("The YAML stream has {} documents; they were put in an array.", n));
+            Ok(Value::Array(
+                documents
+                    .iter()
+                    .enumerate()
+                    .map(|(i, d)| yaml_to_json(d, &JsonPath::root().child_index(i), warnings))
+                    .collect(),
+            ))
+        }
+    }
+}
+
+fn yaml_to_json(yaml: &serde_yaml::Value, path: &JsonPath, warnings: &mut Warnings) -> Value {
+    use serde_yaml::Value as Yaml;
+    match yaml {
+        Yaml::Null => Value::Null,
+        Yaml::Bool(b) => Value::Bool(*b),
+        Yaml::Number(n) => match (n.as_i64(), n.as_u64(), n.as_f64()) {
+            (Some(i), _, _) => Value::from(i),
+            (_, Some(u), _) => Value::from(u),
+            (_, _, Some(f)) => float_value(f, path, warnings),
+            _ => Value::Null,
+        },
+        Yaml::String(s) => Value::String(s.clone()),
+        Yaml::Sequence(items) => Value::Array(
+            items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| yaml_to_json(item, &path.child_index(i), warnings))
+                .collect(),
+        ),
+        Yaml::Mapping(mapping) => {
+            let mut map = Map::new();
+            for (key, value) in mapping {
+                let key = match key {
+                    Yaml::String(s) => s.clone(),
+                    other => {
+                        let text = yaml_to_json(other, path, warnings).to_string();
+                        warnings.at(path, &format!("non-string key {} became a string", text));
+                        text
+                    }
+                };
+                let value = yaml_to_json(value, &path.child_key(&key), warnings);
+                map.insert(key, value);
+            }
+            Value::Object(map)
+        }
+        Yaml::Tagged(tagged) => {
+            warnings.at(path, &format!("tag {} was dropped", tagged.tag));
+            yaml_to_json(&tagged.value, path, warnings)
+        }
+    }
+}
+
+// TOML
+
+fn toml_import(text: &str, warnings: &mut Warnings) -> Result<Value, String> {
+    let table: toml::Table = text.parse().map_err(|e| format!("Invalid TOML: {}", e))?;
+    Ok(toml_to_json(&toml::Value::Table(table), &JsonPath::root(), warnings))
+}
+
+fn toml_to_json(toml: &toml::Value, path: &JsonPath, warnings: &mut Warnings) -> Value {
+    match toml {
+        toml::Value::String(s) => Value::String(s.clone()),
+        toml::Value::Integer(i) => Value::from(*i),
+        toml::Value::Float(f) => float_value(*f, path, warnings),
+        toml::Value::Boolean(b) => Value::Bool(*b),
+        toml::Value::Datetime(datetime) => {
+            warnings.at(path, "datetime became a string");
+            Value::String(datetime.to_string())
+        }
+        toml::Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| toml_to_json(item, &path.child_index(i), warnings))
+                .collect(),
+        ),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .iter()
+                .map(|(key, value)| (key.clone(), toml_to_json(value, &path.child_key(key), warnings)))
+                .collect(),
+        ),
+    }
+}
+
+fn toml_export(value: &Value, warnings: &mut Warnings) -> Result<String, String> {
+    let Some(toml::Value::Table(table)) = json_to_toml(value, &JsonPath::root(), warnings) else {
+        return Err("A TOML document has to be a table; the top-level value is not an object.".to_string());
+    };
+    toml::to_string_pretty(&table).map_err(|e| e.to_string())
+}
+
+// `None` for null, which TOML doesn't have
+fn json_to_toml(value: &Value, path: &JsonPath, warnings: &mut Warnings) -> Option<toml::Value> {
+    Some(match value {
+        Value::Null => {
+            warnings.at(path, "TOML has no null; dropped");
+            return None;
+        }
+        Value::Bool(b) => toml::Value::Boolean(*b),
+        Value::Number(n) => match (n.as_i64(), n.as_f64()) {
+            (Some(i), _) => toml::Value::Integer(i),
+            (None, Some(f)) if n.is_u64() => {
+                warnings.at(path, "integer too large for TOML; written as a float");
+                toml::Value::Float(f)
+            }
+            (None, Some(f)) => toml::Value::Float(f),
+            (None, None) => return None,
+        },
+        Value::String(s) => toml::Value::String(s.clone()),
+        Value::Array(items) => toml::Value::Array(
+            items
+                .iter()
+                .enumerate()
+                .filter_map(|(i, item)| json_to_toml(item, &path.child_index(i), warnings))
+                .collect(),
+        ),
+        Value::Object(map) => toml::Value::Table(
+            map.iter()
+                .filter_map(|(key, value)| Some((key.clone(), json_to_toml(value, &path.child_key(key), warnings)?)))
+                .collect(),
+        ),
+    })
+}
+
+// XML
+
+const TEXT_KEY: &str = "#text";
+const ATTRIBUTE_PREFIX: char = '@';
+
+fn xml_import(text: &str, warnings: &mut Warnings) -> Result<Value, String> {
+    let mut reader = quick_xml::Reader::from_str(text);
+    // Open elements: name, attributes/children so far, text so far
+    let mut stack: Vec<(String, Map<String, Value>, String)> = Vec::new();
+    let mut root: Option<Value> = None;
+    let error = |reader: &quick_xml::Reader<&[u8]>, e: &dyn std::fmt::Display| {
+        format!("Invalid XML at byte {}: {}", reader.buffer_position(), e)
+    };
+    loop {
+        let event = reader.read_event().map_err(|e| error(&reader, &e))?;
+        match event {
+            Event::Start(start) | Event::Empty(start) if root.is_some() && stack.is_empty() => {
+                let name = String::from_utf8_lossy(start.name().as_ref()).into_owned();
+                return Err(format!("Invalid XML: more than one root element (<{}>)", name));
+            }
+            Event::Start(start) => stack.push(open_element(&start, &reader)?),
+            Event::Empty(start) => {
+                let element = open_element(&start, &reader)?;
+                close_element(element, &mut stack, &mut root, warnings);
+            }
+            Event::End(_) => {
+                let element = stack.pop().ok_or_else(|| error(&reader, &"unexpected end tag"))?;
+                close_element(element, &mut stack, &mut root, warnings);
+            }
+            Event::Text(text) => {
+                let text = text.unescape().map_err(|e| error(&reader, &e))?;
+                if let Some((_, _, content)) = stack.last_mut() {
+                    content.push_str(&text);
+                }
+            }
+            Event::CData(data) => {
+                if let Some((_, _, content)) = stack.last_mut() {
+                    content.push_str(&String::from_utf8_lossy(&data));
+                }
+            }
+            Event::Comment(_) => warnings.once("XML comments were dropped."),
+            Event::PI(_) | Event::DocType(_) => warnings.once("XML processing instructions and DOCTYPE were dropped."),
+            Event::Decl(_) => {}
+            Event::Eof => break,
+        }
+    }
+    if !stack.is_empty() {
+        return Err("Invalid XML: unclosed elements at the end".to_string());
+    }
+    root.ok_or_else(|| "Invalid XML: no root element".to_string())
+}
+
+fn open_element(
+    start: &BytesStart,
+    reader: &quick_xml::Reader<&[u8]>,
+) -> Result<(String, Map<String, Value>, String), String> {
+    let name = String::from_utf8_lossy(start.name().as_ref()).into_owned();
+    let mut fields = Map::new();
+    for attribute in start.attributes() {
+        let attribute = attribute.map_err(|e| format!("Invalid XML attribute in <{}>: {}", name, e))?;
+        let key = format!("{}{}", ATTRIBUTE_PREFIX, String::from_utf8_lossy(attribute.key.as_ref()));
+        let value = attribute
+            .decode_and_unescape_value(reader.decoder())
+            .map_err(|e| format!("Invalid XML attribute in <{}>: {}", name, e))?;
+        fields.insert(key, Value::String(value.into_owned()));
+    }
+    Ok((name, fields, String::new()))
+}
+
+fn close_element(
+    (name, mut fields, text): (String, Map<String, Value>, String),
+    stack: &mut [(String, Map<String, Value>, String)],
+    root: &mut Option<Value>,
+    warnings: &mut Warnings,
+) {
+    let text = text.trim();
+    let value = if fields.is_empty() {
+        Value::String(text.to_string())
+    } else {
+        if !text.is_empty() {
+            if fields.keys().any(|k| !k.starts_with(ATTRIBUTE_PREFIX)) {
+                warnings.once("Text mixed with child elements was joined into one #text; its position is lost.");
+            }
+            fields.insert(TEXT_KEY.to_string(), Value::String(text.to_string()));
+        }
+        Value::Object(fields)
+    };
+    match stack.last_mut() {
+        Some((_, parent, _)) => match parent.get_mut(&name) {
+            Some(Value::Array(siblings)) => siblings.push(value),
+            Some(existing) => {
+                let first = existing.take();
+                *existing = Value::Array(vec![first, value]);
+            }
+            None => {
+                parent.insert(name, value);
+            }
+        },
+        None => *root = Some(Value::Object(Map::from_iter([(name, value)]))),
+    }
+}
+
+fn xml_export(value: &Value, warnings: &mut Warnings) -> Result<String, String> {
+    let mut writer = Writer::new_with_indent(Cursor::new(Vec::new()), b' ', 2);
+    let root = JsonPath::root();
+    match value {
+        Value::Object(map) if map.len() == 1 && map.values().all(|v| !v.is_array()) => {
+            let (name, content) = map.iter().next().expect("one entry");
+            write_element(&mut writer, name, content, &root.child_key(name), warnings)?;
+        }
+        _ => {
+            warnings.once("XML needs a single root element; the document was wrapped in <root>.");
+            write_element(&mut writer, "root", value, &root, warnings)?;
+        }
+    }
+    String::from_utf8(writer.into_inner().into_inner()).map_err(|e| e.to_string())
+}
+
+type XmlWriter = Writer<Cursor<Vec<u8>>>;
+
+fn write_element(
+    writer: &mut XmlWriter,
+    name: &str,
+    value: &Value,
+    path: &JsonPath,
+    warnings: &mut Warnings,
+) -> Result<(), String> {
+    let xml_error = |e: std::io::Error| e.to_string();
+    let name = xml_name(name, path, warnings);
+    let mut start = BytesStart::new(name.as_str());
+    match value {
+        Value::Object(map) => {
+            let mut text = None;
+            let mut children = Vec::new();
+            for (key, child) in map {
+                let child_path = path.child_key(key);
+                if key == TEXT_KEY {
+                    text = Some(scalar_text(child, &child_path, warnings));
+                } else if let Some(attribute) = key.strip_prefix(ATTRIBUTE_PREFIX) {
+                    if child.is_object() || child.is_array() {
+                        warnings.at(&child_path, "attributes can't hold objects or arrays; dropped");
+                        continue;
+                    }
+                    let attribute = xml_name(attribute, &child_path, warnings);
+                    start.push_attribute((attribute.as_str(), scalar_text(child, &child_path, warnings).as_str()));
+                } else {
+                    children.push((key, child, child_path));
+                }
+            }
+            if text.is_none() && children.is_empty() {
+                return writer.write_event(Event::Empty(start)).map_err(xml_error);
+            }
+            writer.write_event(Event::Start(start)).map_err(xml_error)?;
+            if let Some(text) = text {
+                writer.write_event(Event::Text(BytesText::new(&text))).map_err(xml_error)?;
+            }
+            for (key, child, child_path) in children {
+                match child {
+                    Value::Array(items) => {
+                        for (i, item) in items.iter().enumerate() {
+                            write_element(writer, key, item, &child_path.child_index(i), warnings)?;
+                        }
+                    }
+                    _ => write_element(writer, key, child, &child_path, warnings)?,
+                }
+            }
+        }
+        Value::Array(items) => {
+            warnings.at(path, "nested arrays have no XML form; elements were written as <item>");
+            writer.write_event(Event::Start(start)).map_err(xml_error)?;
+            for (i, item) in items.iter().enumerate() {
+                write_element(writer, "item", item, &path.child_index(i), warnings)?;
+            }
+        }
+        _ => {
+            let text = scalar_text(value, path, warnings);
+            writer.write_event(Event::Start(start)).map_err(xml_error)?;
+            writer.write_event(Event::Text(BytesText::new(&text))).map_err(xml_error)?;
+        }
+    }
+    writer.write_event(Event::End(BytesEnd::new(name.as_str()))).map_err(xml_error)
+}
+
+fn scalar_text(value: &Value, path: &JsonPath, warnings: &mut Warnings) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => {
+            warnings.at(path, "null written as empty text");
+            String::new()
+        }
+        other => {
+            warnings.once("XML has no types: numbers and booleans were written as text.");
+            other.to_string()
+        }
+    }
+}
+
+// Replaces characters XML names can't contain
+fn xml_name(name: &str, path: &JsonPath, warnings: &mut Warnings) -> String {
+    let valid: String = name
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let allowed = c.is_alphanumeric() || matches!(c, '_' | ':' | '-' | '.');
+            let allowed_first = c.is_alphabetic() || c == '_' || c == ':';
+            if (i == 0 && allowed_first) || (i > 0 && allowed) { c } else { '_' }
+        })
+        .collect();
+    let valid = if valid.is_empty() { "_".to_string() } else { valid };
+    if valid != name {
+        warnings.at(path, &format!("\"{}\" is not a valid XML name; written as <{}>", name, valid));
+    }
+    valid
+}
+
+// CSV
+
+// Rows become objects keyed by the header row. Every field is a string.
+fn csv_import(text: &str) -> Result<Value, String> {
+    let mut reader = csv::Reader::from_reader(text.as_bytes());
+    let headers = reader.headers().map_err(|e| format!("Invalid CSV: {}", e))?.clone();
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| format!("Invalid CSV: {}", e))?;
+        let row: Map<String, Value> = headers
+            .iter()
+            .zip(record.iter())
+            .map(|(header, field)| (header.to_string(), Value::String(field.to_string())))
+            .collect();
+        rows.push(Value::Object(row));
+    }
+    Ok(Value::Array(rows))
+}
+
+fn csv_export(value: &Value, warnings: &mut Warnings) -> Result<String, String> {
+    let Value::Array(rows) = value else {
+        return Err("CSV export needs an array of objects.".to_string());
+    };
+    let mut columns: Vec<&str> = Vec::new();
+    for (i, row) in rows.iter().enumerate() {
+        let Value::Object(map) = row else {
+            return Err(format!("CSV export needs an array of objects; element {} is not an object.", i));
+        };
+        for key in map.keys() {
+            if !columns.contains(&key.as_str()) {
+                columns.push(key);
+            }
+        }
+    }
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(&columns).map_err(|e| e.to_string())?;
+    let root = JsonPath::root();
+    for (i, row) in rows.iter().enumerate() {
+        let record: Vec<String> = columns
+            .iter()
+            .map(|column| {
+                let path = root.child_index(i).child_key(column);
+                match row.get(*column) {
+                    None => String::new(),
+                    Some(Value::Null) => {
+                        warnings.once("CSV has no null: nulls and missing fields are both empty.");
+                        String::new()
+                    }
+                    Some(Value::String(s)) => s.clone(),
+                    Some(nested @ (Value::Object(_) | Value::Array(_))) => {
+                        warnings.at(&path, "nested value written as JSON text");
+                        nested.to_string()
+                    }
+                    Some(other) => {
+                        warnings.once("CSV has no types: numbers and booleans were written as text.");
+                        other.to_string()
+                    }
+                }
+            })
+            .collect();
+        writer.write_record(&record).map_err(|e| e.to_string())?;
+    }
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
@@ -0,0 +1,110 @@
+// The window reporting the outcome of an import or export: the converted
+// text or bytes (for exports) and every warning about data that didn't
+// carry over.
+use base64::Engine;
+use eframe::egui;
+use egui::Color32;
+
+pub enum ConvertedOutput {
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+pub struct ConversionReport {
+    pub title: String,
+    // Exported data, with the file extension to save it under
+    pub output: Option<(ConvertedOutput, &'static str)>,
+    // e.g. the size comparison for binary exports
+    pub summary: Option<String>,
+    pub warnings: Vec<String>,
+    pub save_error: Option<String>,
+}
+
+impl ConversionReport {
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        if let Some(summary) = &self.summary {
+            ui.label(summary);
+        }
+        if self.warnings.is_empty() {
+            ui.colored_label(Color32::GREEN, "Converted without losing anything.");
+        } else {
+            ui.colored_label(Color32::YELLOW, format!("{} lossy conversions:", self.warnings.len()));
+            egui::ScrollArea::vertical()
+                .id_salt("conversion_warnings_scroll")
+                .max_height(150.0)
+                .show(ui, |ui| {
+                    for warning in &self.warnings {
+                        ui.label(warning);
+                    }
+                });
+        }
+        let Some((output, extension)) = &self.output else {
+            return;
+        };
+        ui.separator();
+        ui.horizontal(|ui| {
+            match output {
+                ConvertedOutput::Text(text) => {
+                    if ui.button("Copy").clicked() {
+                        ui.ctx().copy_text(text.clone());
+                    }
+                }
+                ConvertedOutput::Bytes(bytes) => {
+                    if ui.button("Copy as Base64").clicked() {
+                        ui.ctx().copy_text(base64::engine::general_purpose::STANDARD.encode(bytes));
+                    }
+                    if ui.button("Copy as Hex").clicked() {
+                        ui.ctx().copy_text(bytes.iter().map(|b| format!("{:02x}", b)).collect());
+                    }
+                }
+            }
+            if ui.button("Save As…").clicked()
+                && let Some(path) = rfd::FileDialog::new().add_filter(*extension, &[*extension]).save_file()
+            {
+                let contents = match output {
+                    ConvertedOutput::Text(text) => text.as_bytes(),
+                    ConvertedOutput::Bytes(bytes) => bytes.as_slice(),
+                };
+                self.save_error = std::fs::write(&path, contents)
+                    .err()
+                    .map(|e| format!("Failed to save {}: {}", path.display(), e));
+            }
+        });
+        if let Some(error) = &self.save_error {
+            ui.colored_label(Color32::RED, error);
+        }
+        let dump;
+        let mut preview = match output {
+            ConvertedOutput::Text(text) => text.as_str(),
+            ConvertedOutput::Bytes(bytes) => {
+                dump = hex_dump(bytes);
+                dump.as_str()
+            }
+        };
+        egui::ScrollArea::both().id_salt("conversion_output_scroll").show(ui, |ui| {
+            ui.add(
+                egui::TextEdit::multiline(&mut preview)
+                    .code_editor()
+                    .desired_width(f32::INFINITY),
+            );
+        });
+    }
+}
+
+// `00000010  82 a1 61 01 a1 62 c3 ...  |..a..b.|`, up to 4 KiB
+fn hex_dump(bytes: &[u8]) -> String {
+    const MAX_BYTES: usize = 4096;
+    let mut dump = String::new();
+    for (line, chunk) in bytes[..bytes.len().min(MAX_BYTES)].chunks(16).enumerate() {
+        let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+        let ascii: String = chunk
+            .iter()
+            .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+            .collect();
+        dump.push_str(&format!("{:08x}  {:<47}  |{}|\n", line * 16, hex.join(" "), ascii));
+    }
+    if bytes.len() > MAX_BYTES {
+        dump.push_str(&format!("… {} more bytes\n", bytes.len() - MAX_BYTES));
+    }
+    dump
+}
@@ -0,0 +1,336 @@
+// Structural comparison of two documents. Objects are compared key by key
+// (so key order and formatting don't matter), arrays by position or by an
+// identity key, and anything under an ignored path is skipped. Each
+// difference carries the node's path on both sides, since matching by
+// identity can put the same element at different indices.
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::json_path::{JsonPath, PathSegment};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl DiffKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            DiffKind::Added => "Added",
+            DiffKind::Removed => "Removed",
+            DiffKind::Changed => "Changed",
+        }
+    }
+}
+
+pub struct Difference {
+    pub kind: DiffKind,
+    // Where the node is in the left document (`None` when added)
+    pub left: Option<JsonPath>,
+    // Where the node is in the right document (`None` when removed)
+    pub right: Option<JsonPath>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum PatternSegment {
+    Key(String),
+    Index(usize),
+    // A JSON Pointer token, which can name a key or an index
+    Token(String),
+    // `*`: any key or index
+    Any,
+}
+
+impl PatternSegment {
+    fn matches(&self, segment: &PathSegment) -> bool {
+        match (self, segment) {
+            (PatternSegment::Any, _) => true,
+            (PatternSegment::Key(k), PathSegment::Key(key)) => k == key,
+            (PatternSegment::Index(i), PathSegment::Index(index)) => i == index,
+            (PatternSegment::Token(t), PathSegment::Key(key)) => t == key,
+            (PatternSegment::Token(t), PathSegment::Index(index)) => *t == index.to_string(),
+            _ => false,
+        }
+    }
+}
+
+// A path to ignore, with `*` standing for any key or index. Written as a
+// JSONPath (`$.items[*].updatedAt`, `$['a b']`) or a JSON Pointer
+// (`/items/*/updatedAt`). Everything below a matching node is ignored too.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PathPattern {
+    segments: Vec<PatternSegment>,
+}
+
+impl PathPattern {
+    pub fn parse(text: &str) -> Result<PathPattern, String> {
+        let text = text.trim();
+        if let Some(pointer) = text.strip_prefix('/') {
+            let segments = pointer
+                .split('/')
+                .map(|token| match token {
+                    "*" => PatternSegment::Any,
+                    _ => PatternSegment::Token(token.replace("~1", "/").replace("~0", "~")),
+                })
+                .collect();
+            return Ok(PathPattern { segments });
+        }
+        let error = |message: &str| format!("Invalid path \"{}\": {}", text, message);
+        let rest = text.strip_prefix('$').unwrap_or(text);
+        let chars: Vec<char> = rest.chars().collect();
+        let mut segments = Vec::new();
+        let mut pos = 0;
+        // `a.b` is accepted as shorthand for `$.a.b`
+        let mut expect_name = !text.starts_with('$') && !rest.starts_with('[');
+        while pos < chars.len() || expect_name {
+            if expect_name || chars[pos] == '.' {
+                if !expect_name {
+                    pos += 1;
+                }
+                expect_name = false;
+                let start = pos;
+                while pos < chars.len() && chars[pos] != '.' && chars[pos] != '[' {
+                    pos += 1;
+                }
+                let name: String = chars[start..pos].iter().collect();
+                segments.push(match name.as_str() {
+                    "" => return Err(error("empty key")),
+                    "*" => PatternSegment::Any,
+                    _ => PatternSegment::Key(name),
+                });
+            } else if chars[pos] == '[' {
+                let close = chars[pos..]
+                    .iter()
+                    .position(|&c| c == ']')
+                    .map(|offset| pos + offset)
+                    .ok_or_else(|| error("missing ']'"))?;
+                let inner: String = chars[pos + 1..close].iter().collect();
+                let inner = inner.trim();
+                segments.push(if inner == "*" {
+                    PatternSegment::Any
+                } else if let Ok(index) = inner.parse::<usize>() {
+                    PatternSegment::Index(index)
+                } else if inner.len() >= 2
+                    && ((inner.starts_with('\'') && inner.ends_with('\''))
+                        || (inner.starts_with('"') && inner.ends_with('"')))
+                {
+                    PatternSegment::Key(inner[1..inner.len() - 1].replace("\\'", "'").replace("\\\"", "\""))
+                } else {
+                    return Err(error("expected an index, '*' or a quoted key in brackets"));
+                });
+                pos = close + 1;
+            } else {
+                return Err(error(&format!("unexpected '{}'", chars[pos])));
+            }
+        }
+        Ok(PathPattern { segments })
+    }
+
+    // Whether `path` is the matching node or inside it
+    fn covers(&self, path: &JsonPath) -> bool {
+        let segments = path.segments();
+        self.segments.len() <= segments.len()
+            && self.segments.iter().zip(segments).all(|(p, s)| p.matches(s))
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DiffOptions {
+    // Object key that identifies array elements, e.g. `id`; empty compares
+    // arrays by position
+    pub identity_key: String,
+    pub ignore: Vec<PathPattern>,
+}
+
+impl DiffOptions {
+    fn is_ignored(&self, left: &JsonPath, right: &JsonPath) -> bool {
+        self.ignore.iter().any(|p| p.covers(left) || p.covers(right))
+    }
+}
+
+pub fn diff(left: &Value, right: &Value, options: &DiffOptions) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    diff_at(left, right, &JsonPath::root(), &JsonPath::root(), options, &mut differences);
+    differences
+}
+
+fn diff_at(
+    left: &Value,
+    right: &Value,
+    left_path: &JsonPath,
+    right_path: &JsonPath,
+    options: &DiffOptions,
+    out: &mut Vec<Difference>,
+) {
+    if options.is_ignored(left_path, right_path) {
+        return;
+    }
+    match (left, right) {
+        (Value::Object(l), Value::Object(r)) => {
+            for (key, l_child) in l {
+                let l_child_path = left_path.child_key(key);
+                let r_child_path = right_path.child_key(key);
+                match r.get(key) {
+                    Some(r_child) => diff_at(l_child, r_child, &l_child_path, &r_child_path, options, out),
+                    None => removed(l_child_path, &r_child_path, options, out),
+                }
+            }
+            for key in r.keys().filter(|key| !l.contains_key(*key)) {
+                added(&left_path.child_key(key), right_path.child_key(key), options, out);
+            }
+        }
+        (Value::Array(l), Value::Array(r)) => {
+            let (pairs, left_only, right_only) = match_elements(l, r, &options.identity_key);
+            for (i, j) in pairs {
+                let (l_child_path, r_child_path) = (left_path.child_index(i), right_path.child_index(j));
+                diff_at(&l[i], &r[j], &l_child_path, &r_child_path, options, out);
+            }
+            for i in left_only {
+                removed(left_path.child_index(i), right_path, options, out);
+            }
+            for j in right_only {
+                added(left_path, right_path.child_index(j), options, out);
+            }
+        }
+        _ if left == right => {}
+        _ => out.push(Difference {
+            kind: DiffKind::Changed,
+            left: Some(left_path.clone()),
+            right: Some(right_path.clone()),
+        }),
+    }
+}
+
+// `counterpart` is the closest path on the other side, used for ignore rules
+fn removed(path: JsonPath, counterpart: &JsonPath, options: &DiffOptions, out: &mut Vec<Difference>) {
+    if !options.is_ignored(&path, counterpart) {
+        out.push(Difference {
+            kind: DiffKind::Removed,
+            left: Some(path),
+            right: None,
+        });
+    }
+}
+
+fn added(counterpart: &JsonPath, path: JsonPath, options: &DiffOptions, out: &mut Vec<Difference>) {
+    if !options.is_ignored(counterpart, &path) {
+        out.push(Difference {
+            kind: DiffKind::Added,
+            left: None,
+            right: Some(path),
+        });
+    }
+}
+
+type ElementMatching = (Vec<(usize, usize)>, Vec<usize>, Vec<usize>);
+
+// Pairs up array elements: elements carrying the identity key are matched
+// by its value, the rest by their order among themselves. Returns the pairs
+// plus the unmatched indices on each side.
+fn match_elements(left: &[Value], right: &[Value], identity_key: &str) -> ElementMatching {
+    let identity = |element: &Value| -> Option<String> {
+        if identity_key.is_empty() {
+            return None;
+        }
+        element.get(identity_key).map(Value::to_string)
+    };
+    let mut by_identity: HashMap<String, usize> = HashMap::new();
+    let mut right_positional = Vec::new();
+    // Elements repeating an identity can't be matched
+    let mut right_only = Vec::new();
+    for (j, element) in right.iter().enumerate() {
+        match identity(element) {
+            Some(id) if by_identity.contains_key(&id) => right_only.push(j),
+            Some(id) => {
+                by_identity.insert(id, j);
+            }
+            None => right_positional.push(j),
+        }
+    }
+    let mut pairs = Vec::new();
+    let mut left_only = Vec::new();
+    let mut left_positional = Vec::new();
+    for (i, element) in left.iter().enumerate() {
+        match identity(element) {
+            Some(id) => match by_identity.remove(&id) {
+                Some(j) => pairs.push((i, j)),
+                None => left_only.push(i),
+            },
+            None => left_positional.push(i),
+        }
+    }
+    let common = left_positional.len().min(right_positional.len());
+    pairs.extend(left_positional[..common].iter().copied().zip(right_positional[..common].iter().copied()));
+    left_only.extend_from_slice(&left_positional[common..]);
+    left_only.sort_unstable();
+    right_only.extend(by_identity.into_values().chain(right_positional[common..].iter().copied()));
+    right_only.sort_unstable();
+    (pairs, left_only, right_only)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    // Each difference as "<kind> <left path> <right path>", with `-` for a
+    // side that doesn't have the node
+    fn summary(left: Value, right: Value, identity_key: &str, ignore: &[&str]) -> Vec<String> {
+        let options = DiffOptions {
+            identity_key: identity_key.to_string(),
+            ignore: ignore.iter().map(|text| PathPattern::parse(text).unwrap()).collect(),
+        };
+        let side = |path: &Option<JsonPath>| path.as_ref().map_or("-".to_string(), JsonPath::to_jsonpath);
+        diff(&left, &right, &options)
+            .iter()
+            .map(|d| format!("{} {} {}", d.kind.label(), side(&d.left), side(&d.right)))
+            .collect()
+    }
+
+    #[test]
+    fn compares_objects_by_key_and_arrays_by_position() {
+        let left = json!({ "a": 1, "b": { "c": [1, 2, 3] }, "gone": null });
+        let right = json!({ "b": { "c": [1, 5] }, "a": 1, "new": true });
+        assert_eq!(
+            summary(left, right, "", &[]),
+            ["Changed $.b.c[1] $.b.c[1]", "Removed $.b.c[2] -", "Removed $.gone -", "Added - $.new"]
+        );
+    }
+
+    #[test]
+    fn matches_elements_by_identity_key() {
+        let left = json!([{ "id": 1, "v": "a" }, { "id": 2, "v": "b" }, { "id": 3 }]);
+        let right = json!([{ "id": 2, "v": "B" }, { "id": 1, "v": "a" }, { "id": 4 }]);
+        assert_eq!(
+            summary(left.clone(), right.clone(), "id", &[]),
+            ["Changed $[1].v $[0].v", "Removed $[2] -", "Added - $[2]"]
+        );
+        // Without the key the same arrays differ element by element
+        assert_eq!(summary(left, right, "", &[]).len(), 5);
+    }
+
+    #[test]
+    fn skips_ignored_paths() {
+        let left = json!({ "items": [{ "id": 1, "updatedAt": "x", "a b": 1 }], "meta": { "t": 1 } });
+        let right = json!({ "items": [{ "id": 1, "updatedAt": "y", "a b": 2 }], "meta": { "t": 2 } });
+        assert_eq!(summary(left.clone(), right.clone(), "", &[]).len(), 3);
+        for ignore in [
+            ["$.items[*].updatedAt", "$.items[0]['a b']", "$.meta"],
+            ["/items/*/updatedAt", "/items/0/a b", "/meta"],
+            ["items[*].updatedAt", "$['items'][*][\"a b\"]", "meta.t"],
+        ] {
+            assert!(summary(left.clone(), right.clone(), "", &ignore).is_empty(), "{:?}", ignore);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_patterns() {
+        for text in ["$.a..b", "$.a[", "$.a[b]", "$a"] {
+            assert!(PathPattern::parse(text).is_err(), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,284 @@
+// The "Compare Documents" window: two open documents side by side as trees,
+// with added, removed and changed nodes outlined and a clickable list of
+// every difference.
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use eframe::egui;
+use egui::Color32;
+use serde_json::Value;
+
+use crate::diff::{DiffKind, DiffOptions, Difference, PathPattern, diff};
+use crate::document::{Document, preview};
+use crate::json_path::JsonPath;
+use crate::parse_json_to_value;
+use crate::table_view::TableState;
+use crate::tree_view::{ExpansionState, NodeEditor, NodeMark, TreeAction, TreeContext, render_json_value};
+
+fn kind_color(kind: DiffKind) -> Color32 {
+    match kind {
+        DiffKind::Added => Color32::GREEN,
+        DiffKind::Removed => Color32::RED,
+        DiffKind::Changed => Color32::YELLOW,
+    }
+}
+
+// View state of one of the two trees
+#[derive(Default)]
+struct DiffSide {
+    document_id: Option<u64>,
+    value: Option<Value>,
+    expansion: ExpansionState,
+    tables: HashMap<JsonPath, TableState>,
+    // Never used since the trees aren't editable, but the tree wants one
+    editor: Option<NodeEditor>,
+    selected: Option<JsonPath>,
+    reveal_selected: bool,
+    marks: HashMap<JsonPath, NodeMark>,
+}
+
+impl DiffSide {
+    fn select(&mut self, path: Option<&JsonPath>) {
+        if let Some(path) = path {
+            self.expansion.reveal(path);
+            self.reveal_selected = true;
+        }
+        self.selected = path.cloned();
+    }
+
+    fn apply_tree_action(&mut self, action: TreeAction) {
+        let Some(value) = &self.value else {
+            return;
+        };
+        match action {
+            TreeAction::Select(path) => self.selected = Some(path),
+            TreeAction::SetExpanded(path, open) => self.expansion.set_open(&path, open),
+            TreeAction::SetSubtreeExpanded(path, open) => self.expansion.set_subtree_open(value, &path, open),
+            TreeAction::SetSiblingsExpanded(path, open) => self.expansion.set_siblings_open(value, &path, open),
+            TreeAction::ToggleTableView(path) => {
+                if self.tables.remove(&path).is_none() {
+                    self.tables.insert(path.clone(), TableState::default());
+                    self.expansion.set_open(&path, true);
+                }
+            }
+            // Read-only trees have nothing to apply these to
+            TreeAction::UseAsJqFilter(_) | TreeAction::Edit(_) | TreeAction::Error(_) => {}
+        }
+    }
+
+    fn show(&mut self, ui: &mut egui::Ui, id_salt: &str) {
+        let Some(value) = &self.value else {
+            return;
+        };
+        let mut actions = Vec::new();
+        let mut tree_ctx = TreeContext {
+            search_query: "",
+            editable: false,
+            editor: &mut self.editor,
+            expansion: &self.expansion,
+            tables: &mut self.tables,
+            marks: &self.marks,
+            selected: self.selected.as_ref(),
+            reveal_selected: self.reveal_selected,
+            actions: &mut actions,
+        };
+        egui::ScrollArea::both().id_salt(id_salt).show(ui, |ui| {
+            render_json_value(ui, None, value, &JsonPath::root(), &mut tree_ctx);
+        });
+        self.reveal_selected = false;
+        for action in actions {
+            self.apply_tree_action(action);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct DiffView {
+    left: DiffSide,
+    right: DiffSide,
+    identity_key: String,
+    ignore_text: String, // One path per line
+    differences: Vec<Difference>,
+    error: Option<String>,
+    compared: Option<u64>, // Hash of the inputs behind `differences`
+}
+
+impl DiffView {
+    // Compares the active document with the next one when first opened
+    pub fn pick_defaults(&mut self, documents: &[Document], active: usize) {
+        if self.left.document_id.is_none() {
+            self.left.document_id = documents.get(active).map(|d| d.id);
+            self.right.document_id = documents.get((active + 1) % documents.len()).map(|d| d.id);
+        }
+    }
+
+    fn options(&self) -> Result<DiffOptions, String> {
+        let ignore = self
+            .ignore_text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(PathPattern::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(DiffOptions {
+            identity_key: self.identity_key.trim().to_string(),
+            ignore,
+        })
+    }
+
+    // Re-runs the comparison when either document or an option changed
+    fn refresh(&mut self, documents: &[Document]) {
+        let find = |id: Option<u64>| documents.iter().find(|d| Some(d.id) == id);
+        let (Some(left), Some(right)) = (find(self.left.document_id), find(self.right.document_id)) else {
+            self.error = Some("Pick two documents to compare.".to_string());
+            self.compared = None;
+            return;
+        };
+        let mut hasher = DefaultHasher::new();
+        (&left.input_json, &right.input_json, &self.identity_key, &self.ignore_text).hash(&mut hasher);
+        let key = hasher.finish();
+        if self.compared == Some(key) {
+            return;
+        }
+        self.compared = Some(key);
+        self.differences.clear();
+        self.left.marks.clear();
+        self.right.marks.clear();
+
+        let parse = |document: &Document| {
+            parse_json_to_value(&document.input_json).map_err(|e| format!("{}: {}", document.title, e))
+        };
+        let result = self
+            .options()
+            .and_then(|options| Ok((parse(left)?, parse(right)?, options)));
+        let (left_value, right_value, options) = match result {
+            Ok(inputs) => inputs,
+            Err(e) => {
+                self.error = Some(e);
+                self.left.value = None;
+                self.right.value = None;
+                return;
+            }
+        };
+        self.error = None;
+        self.differences = diff(&left_value, &right_value, &options);
+
+        // Start collapsed with every difference revealed
+        self.left.expansion.collapse_all();
+        self.right.expansion.collapse_all();
+        for difference in &self.differences {
+            let color = kind_color(difference.kind);
+            let tooltip = match (&difference.left, &difference.right) {
+                (Some(l), Some(r)) => format!(
+                    "Changed: {} → {}",
+                    left_value.pointer(&l.to_json_pointer()).map_or_else(|| "(missing)".to_string(), preview),
+                    right_value.pointer(&r.to_json_pointer()).map_or_else(|| "(missing)".to_string(), preview)
+                ),
+                _ => difference.kind.label().to_string(),
+            };
+            for (side, path) in [(&mut self.left, &difference.left), (&mut self.right, &difference.right)] {
+                if let Some(path) = path {
+                    side.expansion.reveal(path);
+                    side.marks.insert(
+                        path.clone(),
+                        NodeMark {
+                            color,
+                            tooltip: tooltip.clone(),
+                        },
+                    );
+                }
+            }
+        }
+        self.left.value = Some(left_value);
+        self.right.value = Some(right_value);
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, documents: &[Document]) {
+        ui.horizontal(|ui| {
+            for (label, side) in [("Left:", &mut self.left), ("Right:", &mut self.right)] {
+                ui.label(label);
+                let selected = documents
+                    .iter()
+                    .find(|d| Some(d.id) == side.document_id)
+                    .map_or("(none)", |d| d.title.as_str());
+                egui::ComboBox::from_id_salt(("diff_document", label))
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        for document in documents {
+                            ui.selectable_value(&mut side.document_id, Some(document.id), &document.title);
+                        }
+                    });
+            }
+            if ui.button("Swap").clicked() {
+                std::mem::swap(&mut self.left, &mut self.right);
+                self.compared = None;
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Match array elements by key:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.identity_key)
+                    .hint_text("e.g. id")
+                    .desired_width(100.0),
+            );
+        });
+        ui.label("Ignore paths (one per line, `*` matches any key or index):");
+        ui.add(
+            egui::TextEdit::multiline(&mut self.ignore_text)
+                .code_editor()
+                .hint_text("$.meta.requestId\n$.items[*].updatedAt")
+                .desired_rows(2)
+                .desired_width(f32::INFINITY),
+        );
+
+        self.refresh(documents);
+        if let Some(error) = &self.error {
+            ui.colored_label(Color32::RED, error);
+            return;
+        }
+
+        let count = |kind| self.differences.iter().filter(|d| d.kind == kind).count();
+        let summary = format!(
+            "{} added, {} removed, {} changed",
+            count(DiffKind::Added),
+            count(DiffKind::Removed),
+            count(DiffKind::Changed)
+        );
+        if self.differences.is_empty() {
+            ui.colored_label(Color32::GREEN, "The documents are equal.");
+        } else {
+            let mut clicked = None;
+            egui::CollapsingHeader::new(format!("Differences: {}", summary))
+                .id_salt("diff_list")
+                .show(ui, |ui| {
+                    egui::ScrollArea::vertical()
+                        .id_salt("diff_list_scroll")
+                        .max_height(150.0)
+                        .show(ui, |ui| {
+                            for (index, difference) in self.differences.iter().enumerate() {
+                                let path = difference.left.as_ref().or(difference.right.as_ref());
+                                let text = format!(
+                                    "{} {}",
+                                    difference.kind.label(),
+                                    path.map(JsonPath::to_jsonpath).unwrap_or_default()
+                                );
+                                let label = egui::RichText::new(text).color(kind_color(difference.kind));
+                                if ui.selectable_label(false, label).clicked() {
+                                    clicked = Some(index);
+                                }
+                            }
+                        });
+                });
+            if let Some(index) = clicked {
+                let difference = &self.differences[index];
+                self.left.select(difference.left.as_ref());
+                self.right.select(difference.right.as_ref());
+            }
+        }
+
+        ui.columns(2, |columns| {
+            self.left.show(&mut columns[0], "diff_left_tree");
+            self.right.show(&mut columns[1], "diff_right_tree");
+        });
+    }
+}
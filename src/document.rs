@@ -0,0 +1,713 @@
+// Everything that belongs to one open document (one tab): its text, the
+// parsed tree and all the view state hanging off it.
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use eframe::egui;
+use egui::Color32;
+use serde_json::Value;
+
+use crate::jq_options::{JqLimits, JqOptions};
+use crate::json_path::JsonPath;
+use crate::live_preview::LivePreview;
+use crate::query::{self, QueryLanguage, QueryMatch, QueryRun};
+use crate::query_editor::QueryEditor;
+use crate::schema::{self, SchemaDraft, SchemaError};
+use crate::span_parser::SpanIndex;
+use crate::sql::{self, SqlTable};
+use crate::table_view::TableState;
+use crate::tree_edit::{EditOp, apply_edit};
+use crate::tree_nav::{TypeAhead, find_by_prefix, visible_nodes};
+use crate::tree_view::{ExpansionState, NodeEditor, NodeMark, TreeAction};
+use crate::{char_to_byte_index, parse_json_to_value};
+
+static NEXT_DOCUMENT_ID: AtomicU64 = AtomicU64::new(0);
+
+// Older queries are dropped from a document's history
+const MAX_QUERY_HISTORY: usize = 100;
+// An SQL query run from the query field blocks the window, so one that
+// never ends (e.g. a recursive CTE without a LIMIT) is given up on
+const MAX_SQL_RUN_TIME: Duration = Duration::from_secs(10);
+const MAX_SQL_RESULT_BYTES: usize = 256 * 1024 * 1024;
+
+#[derive(Default)]
+pub struct Document {
+    // Stable across tab reordering and closing, for widget ids
+    pub id: u64,
+    pub title: String,
+    pub input_json: String,
+    // Hash of the text the document was opened with, to tell if it's edited
+    opened_hash: u64,
+    // Store the parsed JSON Value directly for structured display
+    pub parsed_json_value: Option<Value>,
+    pub error_message: Option<String>,
+    pub jq_query_input: String, // The text field for user's JQ query
+    pub query_language: QueryLanguage,
+    pub query_editor: QueryEditor,
+    pub jq_options: JqOptions,
+    pub live_preview: LivePreview,
+    // Nodes matched by the last in-process query, outlined in the tree
+    pub query_matches: Vec<QueryMatch>,
+    // Shown above the tree while it holds the result of the last query
+    pub query_note: Option<String>,
+    // Queries run on this document, most recent last
+    query_history: Vec<QueryRun>,
+    // Tables SQL queries can use, and the hash of the text they came from
+    sql_tables: Vec<SqlTable>,
+    sql_tables_hash: Option<u64>,
+
+    // Tree editing
+    pub tree_editor: Option<NodeEditor>,
+    pub tree_is_query_result: bool, // The tree shows a jq result, so edits can't be written back
+    undo_stack: Vec<String>,        // Previous `input_json` contents, most recent last
+    redo_stack: Vec<String>,
+
+    // Cursor sync between the editor and the tree
+    span_index: Option<SpanIndex>, // Byte spans of the nodes in `span_index_text`
+    span_index_text: String,
+    pub selected_path: Option<JsonPath>,
+    pub reveal_selected: bool,
+    pub pending_editor_selection: Option<Range<usize>>, // Byte range to select in the editor next frame
+    last_editor_cursor: Option<usize>,                 // Char index, to notice when the cursor moves
+
+    // Open/closed containers, kept across re-formatting and jq runs
+    pub expansion: ExpansionState,
+    type_ahead: TypeAhead,
+    // Where the tree was drawn last frame, and whether the last click was in
+    // it; the tree only takes keys while hovered or clicked last
+    pub tree_rect: Option<egui::Rect>,
+    tree_has_keyboard: bool,
+
+    // Arrays shown as tables, keyed by path like the expansion state
+    pub table_views: HashMap<JsonPath, TableState>,
+
+    // JSON Schema validation of `input_json`
+    schema_text: String,
+    schema_draft: SchemaDraft,
+    schema_errors: Vec<SchemaError>,
+    schema_status: Option<String>,
+    // Hash of the text that was validated; the errors point at its nodes
+    schema_validated_hash: Option<u64>,
+}
+
+impl Document {
+    // A document holding `text`, with its tree already built if it parses
+    pub fn new(title: String, text: String) -> Self {
+        let mut document = Document {
+            id: NEXT_DOCUMENT_ID.fetch_add(1, Ordering::Relaxed),
+            title,
+            input_json: text,
+            ..Default::default()
+        };
+        document.opened_hash = document.input_hash();
+        if !document.input_json.is_empty() {
+            document.reload_tree_from_input();
+        }
+        document
+    }
+
+    // Whether closing the document would lose text typed or edited in it
+    pub fn is_edited(&self) -> bool {
+        self.input_hash() != self.opened_hash
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    // Records a cursor position we set ourselves, so it isn't mistaken for
+    // the user moving the cursor.
+    pub fn note_editor_cursor(&mut self, cursor_char_index: usize) {
+        self.last_editor_cursor = Some(cursor_char_index);
+    }
+
+    // Applies an edit made in the tree view to the document and writes the
+    // pretty-printed result back into `input_json`.
+    pub fn apply_tree_edit(&mut self, op: EditOp) {
+        let Some(value) = &self.parsed_json_value else {
+            return;
+        };
+        // Don't clobber text typed into the editor since the tree was built
+        if parse_json_to_value(&self.input_json).ok().as_ref() != Some(value) {
+            self.error_message = Some(
+                "The JSON text has changed since the tree was built. Click 'Format JSON' before editing the tree."
+                    .to_string(),
+            );
+            return;
+        }
+        let mut edited = value.clone();
+        if let Err(e) = apply_edit(&mut edited, op) {
+            self.error_message = Some(e);
+            return;
+        }
+        match serde_json::to_string_pretty(&edited) {
+            Ok(pretty_json_string) => {
+                self.undo_stack.push(std::mem::replace(&mut self.input_json, pretty_json_string));
+                self.redo_stack.clear();
+                self.parsed_json_value = Some(edited);
+                self.query_matches.clear();
+                self.error_message = None;
+            }
+            Err(e) => {
+                self.error_message = Some(format!("Error pretty-printing JSON: {}", e));
+            }
+        }
+    }
+
+    // Replaces the whole text, e.g. with a patched version, as an undoable edit
+    pub fn replace_input(&mut self, text: String) {
+        self.undo_stack.push(std::mem::replace(&mut self.input_json, text));
+        self.redo_stack.clear();
+        self.reload_tree_from_input();
+    }
+
+    pub fn undo_tree_edit(&mut self) {
+        if let Some(previous) = self.undo_stack.pop() {
+            let current = std::mem::replace(&mut self.input_json, previous);
+            self.redo_stack.push(current);
+            self.reload_tree_from_input();
+        }
+    }
+
+    pub fn redo_tree_edit(&mut self) {
+        if let Some(next) = self.redo_stack.pop() {
+            let current = std::mem::replace(&mut self.input_json, next);
+            self.undo_stack.push(current);
+            self.reload_tree_from_input();
+        }
+    }
+
+    fn span_index(&mut self) -> Option<&SpanIndex> {
+        if self.span_index.is_none() || self.span_index_text != self.input_json {
+            self.span_index = SpanIndex::parse(&self.input_json).ok();
+            self.span_index_text = self.input_json.clone();
+        }
+        self.span_index.as_ref()
+    }
+
+    // Tree -> editor: select the node's text in the editor.
+    pub fn select_tree_node(&mut self, path: JsonPath) {
+        if !self.tree_is_query_result
+            && let Some(node) = self.span_index().and_then(|index| index.find(&path))
+        {
+            self.pending_editor_selection = Some(node.span.clone());
+        }
+        self.selected_path = Some(path);
+    }
+
+    // Editor -> tree: select the node under the cursor and scroll it into view.
+    pub fn sync_tree_to_editor_cursor(&mut self, cursor_char_index: usize) {
+        if self.last_editor_cursor == Some(cursor_char_index) || self.tree_is_query_result {
+            return;
+        }
+        self.last_editor_cursor = Some(cursor_char_index);
+        let offset = char_to_byte_index(&self.input_json, cursor_char_index);
+        if let Some(path) = self.span_index().and_then(|index| index.node_at(offset)).map(|node| node.path.clone()) {
+            self.expansion.reveal(&path);
+            self.selected_path = Some(path);
+            self.reveal_selected = true;
+        }
+    }
+
+    pub fn apply_tree_action(&mut self, action: TreeAction) {
+        match action {
+            TreeAction::UseAsJqFilter(filter) => {
+                self.jq_query_input = filter;
+                self.query_language = QueryLanguage::Jq;
+            }
+            TreeAction::Select(path) => self.select_tree_node(path),
+            TreeAction::SetExpanded(path, open) => self.expansion.set_open(&path, open),
+            TreeAction::SetSubtreeExpanded(path, open) => {
+                if let Some(value) = &self.parsed_json_value {
+                    self.expansion.set_subtree_open(value, &path, open);
+                }
+            }
+            TreeAction::SetSiblingsExpanded(path, open) => {
+                if let Some(value) = &self.parsed_json_value {
+                    self.expansion.set_siblings_open(value, &path, open);
+                }
+            }
+            TreeAction::ToggleTableView(path) => {
+                if self.table_views.remove(&path).is_none() {
+                    self.table_views.insert(path.clone(), TableState::default());
+                    self.expansion.set_open(&path, true);
+                }
+            }
+            TreeAction::Edit(op) => self.apply_tree_edit(op),
+            TreeAction::Error(e) => self.error_message = Some(e),
+        }
+    }
+
+    // Keyboard navigation of the tree, driven by the selected node:
+    // arrows move between visible rows, Left/Right close/open or go to the
+    // parent/first child, Home/End jump to the ends, Enter edits or copies,
+    // `+` / `-` / `*` expand, collapse or expand everything below, and typing
+    // jumps to the next key starting with the typed text.
+    pub fn handle_tree_keyboard(&mut self, ctx: &egui::Context) {
+        let Some(root) = &self.parsed_json_value else {
+            return;
+        };
+        let Some(tree_rect) = self.tree_rect else {
+            return;
+        };
+        let (hover, press) = ctx.input(|i| (i.pointer.hover_pos(), i.pointer.press_origin()));
+        if ctx.input(|i| i.pointer.any_pressed())
+            && let Some(pos) = press
+        {
+            self.tree_has_keyboard = tree_rect.contains(pos);
+        }
+        if !self.tree_has_keyboard && !hover.is_some_and(|pos| tree_rect.contains(pos)) {
+            return;
+        }
+        let nodes = visible_nodes(root, &self.expansion);
+        let current = self
+            .selected_path
+            .as_ref()
+            .and_then(|path| nodes.iter().position(|node| &node.path == path));
+
+        let none = egui::Modifiers::NONE;
+        let (up, down, left, right, home, end, enter, typed, now) = ctx.input_mut(|i| {
+            let typed: Vec<String> = i
+                .events
+                .iter()
+                .filter_map(|e| match e {
+                    egui::Event::Text(t) => Some(t.clone()),
+                    _ => None,
+                })
+                .collect();
+            (
+                i.consume_key(none, egui::Key::ArrowUp),
+                i.consume_key(none, egui::Key::ArrowDown),
+                i.consume_key(none, egui::Key::ArrowLeft),
+                i.consume_key(none, egui::Key::ArrowRight),
+                i.consume_key(none, egui::Key::Home),
+                i.consume_key(none, egui::Key::End),
+                i.consume_key(none, egui::Key::Enter),
+                typed,
+                i.time,
+            )
+        });
+
+        let mut target = None;
+        if down {
+            target = current.map_or(nodes.first(), |i| nodes.get(i + 1));
+        } else if up {
+            target = current.map_or(nodes.first(), |i| nodes.get(i.saturating_sub(1)));
+        } else if home {
+            target = nodes.first();
+        } else if end {
+            target = nodes.last();
+        }
+
+        if let Some(index) = current {
+            let node = &nodes[index];
+            let is_open = node.is_container && self.expansion.is_open(&node.path);
+            if right && node.is_container {
+                if !is_open {
+                    self.expansion.set_open(&node.path, true);
+                } else if let Some(child) = nodes.get(index + 1)
+                    && child.path.parent().as_ref() == Some(&node.path)
+                {
+                    target = Some(child);
+                }
+            } else if left {
+                if is_open {
+                    self.expansion.set_open(&node.path, false);
+                } else if let Some(parent) = node.path.parent() {
+                    target = nodes.iter().find(|n| n.path == parent);
+                }
+            } else if enter
+                && let Some(value) = root.pointer(&node.path.to_json_pointer())
+            {
+                if !self.tree_is_query_result && !matches!(value, Value::Object(_) | Value::Array(_)) {
+                    self.tree_editor = Some(NodeEditor::value(&node.path, value));
+                } else if let Ok(text) = serde_json::to_string_pretty(value) {
+                    ctx.copy_text(text);
+                }
+            }
+        }
+
+        let mut pending_action = None;
+        for text in &typed {
+            match text.as_str() {
+                "+" | "=" | "-" | "*" => {
+                    if let Some(path) = self.selected_path.clone() {
+                        pending_action = Some(match text.as_str() {
+                            "-" => TreeAction::SetExpanded(path, false),
+                            "*" => TreeAction::SetSubtreeExpanded(path, true),
+                            _ => TreeAction::SetExpanded(path, true),
+                        });
+                    }
+                }
+                t if t.chars().all(|c| !c.is_whitespace() && !c.is_control()) => {
+                    let prefix = self.type_ahead.push(t, now);
+                    if let Some(node) = find_by_prefix(&nodes, current, prefix) {
+                        target = Some(node);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(node) = target {
+            self.selected_path = Some(node.path.clone());
+            self.reveal_selected = true;
+        }
+        if let Some(action) = pending_action {
+            self.apply_tree_action(action);
+        }
+    }
+
+    fn input_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.input_json.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    pub fn validate_against_schema(&mut self) {
+        self.schema_errors.clear();
+        self.schema_validated_hash = Some(self.input_hash());
+        let schema = match serde_json::from_str::<Value>(&self.schema_text) {
+            Ok(schema) => schema,
+            Err(e) => {
+                self.schema_status = Some(format!("Invalid schema JSON: {}", e));
+                return;
+            }
+        };
+        let instance = match parse_json_to_value(&self.input_json) {
+            Ok(instance) => instance,
+            Err(e) => {
+                self.schema_status = Some(e);
+                return;
+            }
+        };
+        match schema::validate(&schema, &instance, self.schema_draft) {
+            Ok(errors) if errors.is_empty() => {
+                self.schema_status = Some("The document is valid.".to_string());
+            }
+            Ok(errors) => {
+                self.schema_status = Some(format!("{} validation error(s)", errors.len()));
+                self.schema_errors = errors;
+            }
+            Err(e) => self.schema_status = Some(e),
+        }
+    }
+
+    // Drops validation results once the text they were for has changed, so
+    // they don't point at the wrong nodes
+    pub fn drop_stale_schema_errors(&mut self) {
+        if self.schema_validated_hash.is_none_or(|hash| hash == self.input_hash()) {
+            return;
+        }
+        self.schema_errors.clear();
+        self.schema_status = None;
+        self.schema_validated_hash = None;
+    }
+
+    // Outlines for nodes that need attention in the tree
+    pub fn tree_marks(&self) -> HashMap<JsonPath, NodeMark> {
+        let mut marks: HashMap<JsonPath, NodeMark> = HashMap::new();
+        if self.tree_is_query_result {
+            return marks;
+        }
+        for error in &self.schema_errors {
+            let mark = marks.entry(error.instance_path.clone()).or_insert_with(|| NodeMark {
+                color: Color32::RED,
+                tooltip: String::new(),
+            });
+            if !mark.tooltip.is_empty() {
+                mark.tooltip.push('\n');
+            }
+            mark.tooltip.push_str(&error.message);
+        }
+        for (number, found) in self.query_matches.iter().enumerate() {
+            marks.entry(found.path.clone()).or_insert_with(|| NodeMark {
+                color: Color32::from_rgb(80, 200, 255),
+                tooltip: format!("Match {} of {}", number + 1, self.query_matches.len()),
+            });
+        }
+        marks
+    }
+
+    // Runs the query with one of the in-process languages. For JSONPath the
+    // tree shows the whole document, with every match outlined and revealed;
+    // a JMESPath result replaces the tree the way a jq result does.
+    pub fn run_native_query(&mut self) {
+        self.reload_tree_from_input();
+        let Some(root) = &self.parsed_json_value else {
+            return;
+        };
+        if self.jq_query_input.trim().is_empty() {
+            self.error_message = Some("The query field cannot be empty.".to_string());
+            return;
+        }
+        match self.query_language {
+            QueryLanguage::JsonPath => match query::run_jsonpath(root, &self.jq_query_input) {
+                Ok(matches) => {
+                    for found in &matches {
+                        self.expansion.reveal(&found.path);
+                    }
+                    self.query_matches = matches;
+                }
+                Err(e) => self.error_message = Some(e),
+            },
+            QueryLanguage::JmesPath => match query::run_jmespath(root, &self.jq_query_input) {
+                Ok(value) => {
+                    self.parsed_json_value = Some(value);
+                    self.tree_is_query_result = true;
+                }
+                Err(e) => self.error_message = Some(format!("Error : {}", e)),
+            },
+            QueryLanguage::Sql => {
+                let limits = JqLimits {
+                    timeout: Some(MAX_SQL_RUN_TIME),
+                    max_output_bytes: Some(MAX_SQL_RESULT_BYTES),
+                    ..Default::default()
+                };
+                match sql::run_sql(root, &self.jq_query_input, &limits) {
+                    Ok((columns, rows)) => {
+                        // Shown as a table with the columns in SELECT order,
+                        // which the rows' objects don't keep
+                        self.table_views.insert(JsonPath::root(), TableState::with_columns(&columns));
+                        self.expansion.set_open(&JsonPath::root(), true);
+                        self.parsed_json_value = Some(rows);
+                        self.tree_is_query_result = true;
+                    }
+                    Err(e) => self.error_message = Some(format!("Error : {}", e)),
+                }
+            }
+            QueryLanguage::Jq => {}
+        }
+    }
+
+    // Adds the query that was just run to the history, judging its outcome
+    // by the error message and the result it left behind
+    pub fn record_query_run(&mut self) {
+        let outcome = match (&self.error_message, &self.parsed_json_value) {
+            (Some(e), _) => Err(e.clone()),
+            _ if self.query_language == QueryLanguage::JsonPath => Ok(format!("{} matches", self.query_matches.len())),
+            (None, Some(value)) if self.tree_is_query_result => Ok(query::result_size(value)),
+            (None, _) => Ok(String::new()),
+        };
+        self.query_history.push(QueryRun {
+            language: self.query_language,
+            query: self.jq_query_input.clone(),
+            at: std::time::SystemTime::now(),
+            outcome,
+        });
+        if self.query_history.len() > MAX_QUERY_HISTORY {
+            self.query_history.remove(0);
+        }
+    }
+
+    // Menu of the queries run on this document; picking one puts it back in
+    // the query field
+    pub fn query_history_menu(&mut self, ui: &mut egui::Ui) {
+        let mut picked = None;
+        ui.add_enabled_ui(!self.query_history.is_empty(), |ui| {
+            ui.menu_button("History", |ui| {
+                egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    for run in self.query_history.iter().rev() {
+                        let (mark, color, detail) = match &run.outcome {
+                            Ok(size) => ("✔", Color32::LIGHT_GREEN, size.as_str()),
+                            Err(e) => ("✖", Color32::LIGHT_RED, e.as_str()),
+                        };
+                        let text = format!("{} [{}] {}", mark, run.language.label(), run.query);
+                        let response = ui
+                            .button(egui::RichText::new(text).monospace().color(color))
+                            .on_hover_text(format!("{}\n{}", run.age(), detail));
+                        if response.clicked() {
+                            picked = Some((run.language, run.query.clone()));
+                            ui.close_menu();
+                        }
+                    }
+                });
+            });
+        });
+        if let Some((language, query)) = picked {
+            self.query_language = language;
+            self.jq_query_input = query;
+        }
+    }
+
+    // Clickable list of the nodes the last in-process query matched
+    pub fn query_matches_panel(&mut self, ui: &mut egui::Ui) {
+        if self.query_matches.is_empty() {
+            return;
+        }
+        let mut reveal = None;
+        egui::CollapsingHeader::new(format!("{} matches", self.query_matches.len()))
+            .id_salt(("query_matches", self.id))
+            .default_open(true)
+            .show(ui, |ui| {
+                egui::ScrollArea::vertical()
+                    .id_salt("query_matches_scroll")
+                    .max_height(150.0)
+                    .show(ui, |ui| {
+                        for found in &self.query_matches {
+                            let text = format!("{}  {}", found.path.to_normalized_path(), preview(&found.value));
+                            let selected = self.selected_path.as_ref() == Some(&found.path);
+                            if ui.selectable_label(selected, egui::RichText::new(text).monospace()).clicked() {
+                                reveal = Some(found.path.clone());
+                            }
+                        }
+                    });
+            });
+        if let Some(path) = reveal {
+            self.reveal_node(path);
+        }
+    }
+
+    // The document's arrays as SQL tables; clicking one queries all of it.
+    // Returns the rows of the last SQL result when they should be opened as
+    // a document of their own.
+    pub fn sql_tables_panel(&mut self, ui: &mut egui::Ui) -> Option<Value> {
+        if self.query_language != QueryLanguage::Sql {
+            return None;
+        }
+        let hash = self.input_hash();
+        if self.sql_tables_hash != Some(hash) {
+            self.sql_tables = parse_json_to_value(&self.input_json)
+                .map(|root| sql::find_tables(&root))
+                .unwrap_or_default();
+            self.sql_tables_hash = Some(hash);
+        }
+        let mut open_result = None;
+        egui::CollapsingHeader::new(format!("{} tables", self.sql_tables.len()))
+            .id_salt(("sql_tables", self.id))
+            .default_open(true)
+            .show(ui, |ui| {
+                if self.sql_tables.is_empty() {
+                    ui.label("The document has no arrays to query.");
+                }
+                egui::ScrollArea::vertical()
+                    .id_salt("sql_tables_scroll")
+                    .max_height(150.0)
+                    .show(ui, |ui| {
+                        for table in &self.sql_tables {
+                            let text = format!("{} ({} rows): {}", table.name, table.rows, table.columns.join(", "));
+                            if ui
+                                .selectable_label(false, egui::RichText::new(text).monospace())
+                                .on_hover_text(&table.path)
+                                .clicked()
+                            {
+                                self.jq_query_input = format!("SELECT * FROM {}", sql::quote_identifier(&table.name));
+                            }
+                        }
+                    });
+                if self.tree_is_query_result
+                    && let Some(rows) = &self.parsed_json_value
+                    && ui.button("Open Result as Document").clicked()
+                {
+                    open_result = Some(rows.clone());
+                }
+            });
+        open_result
+    }
+
+    // Selects a node in the tree and the editor, opening whatever hides it
+    pub fn reveal_node(&mut self, path: JsonPath) {
+        self.expansion.reveal(&path);
+        self.reveal_selected = true;
+        self.select_tree_node(path);
+    }
+
+    // Contents of the "JSON Schema" window for this document
+    pub fn schema_panel(&mut self, ui: &mut egui::Ui) {
+        self.drop_stale_schema_errors();
+        let mut reveal = None;
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("schema_draft")
+                .selected_text(self.schema_draft.label())
+                .show_ui(ui, |ui| {
+                    for draft in SchemaDraft::ALL {
+                        ui.selectable_value(&mut self.schema_draft, draft, draft.label());
+                    }
+                });
+            if ui.button("Load File…").clicked()
+                && let Some(path) = rfd::FileDialog::new()
+                    .add_filter("JSON Schema", &["json"])
+                    .pick_file()
+            {
+                match std::fs::read_to_string(&path) {
+                    Ok(text) => self.schema_text = text,
+                    Err(e) => self.schema_status = Some(format!("Failed to read {}: {}", path.display(), e)),
+                }
+            }
+            if ui.button("Validate").clicked() {
+                self.validate_against_schema();
+            }
+            if ui.button("Clear").clicked() {
+                self.schema_text.clear();
+                self.schema_errors.clear();
+                self.schema_status = None;
+            }
+        });
+        ui.label("Paste or load a schema:");
+        egui::ScrollArea::vertical()
+            .id_salt("schema_text_scroll")
+            .max_height(200.0)
+            .show(ui, |ui| {
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.schema_text)
+                        .code_editor()
+                        .desired_width(f32::INFINITY)
+                        .desired_rows(10),
+                );
+            });
+        if let Some(status) = &self.schema_status {
+            let color = if self.schema_errors.is_empty() { Color32::GREEN } else { Color32::RED };
+            ui.colored_label(color, status);
+        }
+        egui::ScrollArea::vertical()
+            .id_salt("schema_errors_scroll")
+            .show(ui, |ui| {
+                for error in &self.schema_errors {
+                    let text = format!("{}: {}", error.instance_path.to_jsonpath(), error.message);
+                    let response = ui
+                        .selectable_label(false, text)
+                        .on_hover_text(format!("Schema path: {}", error.schema_path));
+                    if response.clicked() {
+                        reveal = Some(error.instance_path.clone());
+                    }
+                }
+            });
+        if let Some(path) = reveal {
+            self.reveal_node(path);
+        }
+    }
+
+    pub fn reload_tree_from_input(&mut self) {
+        self.tree_editor = None;
+        self.tree_is_query_result = false;
+        self.query_matches.clear();
+        match parse_json_to_value(&self.input_json) {
+            Ok(value) => {
+                self.parsed_json_value = Some(value);
+                self.error_message = None;
+            }
+            Err(e) => {
+                self.parsed_json_value = None;
+                self.error_message = Some(e);
+            }
+        }
+    }
+}
+
+// A short one-line rendering of a value for lists and tooltips
+pub fn preview(value: &Value) -> String {
+    const MAX_CHARS: usize = 60;
+    let text = value.to_string();
+    if text.chars().count() > MAX_CHARS {
+        format!("{}…", text.chars().take(MAX_CHARS).collect::<String>())
+    } else {
+        text
+    }
+}
@@ -0,0 +1,227 @@
+// Structural type inference over one or more sample values. Every sample is
+// merged into a `Shape` that records which JSON types were seen at each
+// position, how often object fields were present, and what strings looked
+// like. Schema and code generators all read from this model so they agree on
+// what is optional, nullable or an enum.
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+// Strings are considered for an enum only while they have at most this many
+// distinct values.
+const MAX_TRACKED_STRING_VALUES: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StringFormat {
+    DateTime,
+    Date,
+    Uuid,
+    Email,
+}
+
+impl StringFormat {
+    const ALL: [StringFormat; 4] = [
+        StringFormat::DateTime,
+        StringFormat::Date,
+        StringFormat::Uuid,
+        StringFormat::Email,
+    ];
+
+    // The JSON Schema `format` name
+    pub fn name(self) -> &'static str {
+        match self {
+            StringFormat::DateTime => "date-time",
+            StringFormat::Date => "date",
+            StringFormat::Uuid => "uuid",
+            StringFormat::Email => "email",
+        }
+    }
+
+    fn matches(self, s: &str) -> bool {
+        match self {
+            StringFormat::DateTime => is_date_time(s),
+            StringFormat::Date => is_date(s),
+            StringFormat::Uuid => is_uuid(s),
+            StringFormat::Email => is_email(s),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Shape {
+    // Number of values merged into this shape
+    pub occurrences: usize,
+    pub nulls: usize,
+    pub booleans: usize,
+    pub integers: usize,
+    pub floats: usize,
+    pub strings: Option<StringShape>,
+    pub array: Option<Box<ArrayShape>>,
+    pub object: Option<ObjectShape>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct StringShape {
+    pub count: usize,
+    // Distinct values and how often they occurred, until there are too many
+    values: BTreeMap<String, usize>,
+    too_many_values: bool,
+    // Formats every string so far has matched; `None` before the first string
+    formats: Option<Vec<StringFormat>>,
+}
+
+impl StringShape {
+    fn add(&mut self, s: &str) {
+        self.count += 1;
+        if !self.too_many_values {
+            *self.values.entry(s.to_string()).or_default() += 1;
+            if self.values.len() > MAX_TRACKED_STRING_VALUES {
+                self.too_many_values = true;
+                self.values.clear();
+            }
+        }
+        let formats = self.formats.get_or_insert_with(|| StringFormat::ALL.to_vec());
+        formats.retain(|format| format.matches(s));
+    }
+
+    // The most specific format every string matched (date-time before date)
+    pub fn format(&self) -> Option<StringFormat> {
+        self.formats.as_ref()?.first().copied()
+    }
+
+    // The distinct values, when there are few of them and they repeat enough
+    // to look like an enumeration rather than free text
+    pub fn enum_values(&self) -> Option<Vec<&str>> {
+        if self.too_many_values || self.format().is_some() || self.values.is_empty() {
+            return None;
+        }
+        let repeats = self.count >= 2 * self.values.len();
+        repeats.then(|| self.values.keys().map(String::as_str).collect())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ArrayShape {
+    pub count: usize,
+    pub items: Shape,
+    pub min_len: usize,
+    pub max_len: usize,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ObjectShape {
+    pub count: usize,
+    // Fields in the order they were first seen
+    pub fields: Vec<(String, Shape)>,
+}
+
+impl ObjectShape {
+    // Present in every object that was seen
+    pub fn is_required(&self, field: &Shape) -> bool {
+        field.occurrences == self.count
+    }
+}
+
+impl Shape {
+    pub fn from_samples<'a>(samples: impl IntoIterator<Item = &'a Value>) -> Shape {
+        let mut shape = Shape::default();
+        for sample in samples {
+            shape.add(sample);
+        }
+        shape
+    }
+
+    pub fn add(&mut self, value: &Value) {
+        self.occurrences += 1;
+        match value {
+            Value::Null => self.nulls += 1,
+            Value::Bool(_) => self.booleans += 1,
+            Value::Number(n) if n.is_f64() => self.floats += 1,
+            Value::Number(_) => self.integers += 1,
+            Value::String(s) => self.strings.get_or_insert_with(Default::default).add(s),
+            Value::Array(arr) => {
+                let array = self.array.get_or_insert_with(|| {
+                    Box::new(ArrayShape {
+                        min_len: usize::MAX,
+                        ..Default::default()
+                    })
+                });
+                array.count += 1;
+                array.min_len = array.min_len.min(arr.len());
+                array.max_len = array.max_len.max(arr.len());
+                for item in arr {
+                    array.items.add(item);
+                }
+            }
+            Value::Object(map) => {
+                let object = self.object.get_or_insert_with(Default::default);
+                object.count += 1;
+                for (key, child) in map {
+                    match object.fields.iter_mut().find(|(k, _)| k == key) {
+                        Some((_, field)) => field.add(child),
+                        None => {
+                            let mut field = Shape::default();
+                            field.add(child);
+                            object.fields.push((key.clone(), field));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn all_digits(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn is_date(s: &str) -> bool {
+    let parts: Vec<&str> = s.split('-').collect();
+    matches!(parts.as_slice(), [y, m, d]
+        if y.len() == 4 && m.len() == 2 && d.len() == 2
+            && all_digits(y) && all_digits(m) && all_digits(d))
+}
+
+// RFC 3339, e.g. `2024-05-01T12:30:00Z` or `2024-05-01T12:30:00.5+02:00`
+fn is_date_time(s: &str) -> bool {
+    let Some((date, time)) = s.split_once(['T', 't']) else {
+        return false;
+    };
+    if !is_date(date) {
+        return false;
+    }
+    let (clock, offset) = if let Some(clock) = time.strip_suffix(['Z', 'z']) {
+        (clock, None)
+    } else if let Some(pos) = time.rfind(['+', '-']) {
+        (&time[..pos], Some(&time[pos + 1..]))
+    } else {
+        return false;
+    };
+    let clock = clock.split('.').next().unwrap_or_default();
+    let hms: Vec<&str> = clock.split(':').collect();
+    let clock_ok = hms.len() == 3 && hms.iter().all(|p| p.len() == 2 && all_digits(p));
+    let offset_ok = offset.is_none_or(|o| {
+        matches!(o.split_once(':'), Some((h, m)) if h.len() == 2 && m.len() == 2 && all_digits(h) && all_digits(m))
+    });
+    clock_ok && offset_ok
+}
+
+fn is_uuid(s: &str) -> bool {
+    s.len() == 36
+        && s.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+fn is_email(s: &str) -> bool {
+    let Some((local, domain)) = s.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !domain.contains('@')
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !s.contains(char::is_whitespace)
+}
@@ -0,0 +1,182 @@
+// Command-line options for jq runs: `--arg`/`--argjson` variables and the
+// flags that change how input is read and output is printed. They describe
+// the run rather than a particular jq, and are turned into arguments for
+// the jq binary by `command_args`. Saved queries keep their options.
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+use eframe::egui;
+use egui::Color32;
+use serde_json::{Map, Value, json};
+
+#[derive(Clone, Default, PartialEq, Hash)]
+pub struct JqVariable {
+    pub name: String, // Without the `$`
+    pub value: String,
+    pub json: bool, // `--argjson` rather than `--arg`
+}
+
+#[derive(Clone, Default, PartialEq, Hash)]
+pub struct JqOptions {
+    pub variables: Vec<JqVariable>,
+    pub slurp: bool,
+    pub raw_input: bool,
+    pub null_input: bool,
+    pub sort_keys: bool,
+    pub compact_output: bool,
+    pub raw_output: bool,
+}
+
+// Bounds for a jq run (or an SQL query) that mustn't hang or flood its
+// caller, like the live preview. The default has none, and the run goes on
+// until it's done.
+#[derive(Clone, Default)]
+pub struct JqLimits {
+    pub timeout: Option<Duration>,
+    pub max_output_bytes: Option<usize>,
+    // Set from another thread to stop the run
+    pub cancel: Arc<AtomicBool>,
+}
+
+// Flag and what it does, in the order of `JqOptions::flags`
+const FLAGS: [(&str, &str); 6] = [
+    ("--slurp", "Read all inputs into one array"),
+    ("--raw-input", "Read lines of text instead of JSON"),
+    ("--null-input", "Run with null as the input, ignoring the document"),
+    ("--sort-keys", "Sort the keys of output objects"),
+    ("--compact-output", "Print each output on one line"),
+    ("--raw-output", "Print strings without quotes"),
+];
+
+impl JqOptions {
+    fn flags(&self) -> [bool; 6] {
+        [self.slurp, self.raw_input, self.null_input, self.sort_keys, self.compact_output, self.raw_output]
+    }
+
+    fn flags_mut(&mut self) -> [&mut bool; 6] {
+        [
+            &mut self.slurp,
+            &mut self.raw_input,
+            &mut self.null_input,
+            &mut self.sort_keys,
+            &mut self.compact_output,
+            &mut self.raw_output,
+        ]
+    }
+
+    // How many options differ from plain `jq <filter>`
+    pub fn active_count(&self) -> usize {
+        self.variables.len() + self.flags().iter().filter(|on| **on).count()
+    }
+
+    pub fn command_args(&self) -> Result<Vec<String>, String> {
+        let mut args: Vec<String> = FLAGS
+            .iter()
+            .zip(self.flags())
+            .filter(|(_, on)| *on)
+            .map(|((flag, _), _)| flag.to_string())
+            .collect();
+        for variable in &self.variables {
+            let name = variable.name.trim();
+            if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                return Err(format!("\"{}\" is not a valid jq variable name.", name));
+            }
+            if variable.json {
+                serde_json::from_str::<Value>(&variable.value)
+                    .map_err(|e| format!("The value of ${} is not valid JSON: {}", name, e))?;
+            }
+            let option = if variable.json { "--argjson" } else { "--arg" };
+            args.extend([option.to_string(), name.to_string(), variable.value.clone()]);
+        }
+        Ok(args)
+    }
+
+    // jq's output as one value, and how many values jq printed. Several
+    // outputs, e.g. one per line with --raw-input, are collected into an
+    // array. Raw output is whatever text jq printed, so it becomes a string.
+    pub fn parse_output(&self, output: &str) -> Result<(Value, usize), String> {
+        if self.raw_output {
+            return Ok((Value::String(output.to_string()), 1));
+        }
+        let mut values = serde_json::Deserializer::from_str(output)
+            .into_iter::<Value>()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("jq printed something that isn't JSON: {}", e))?;
+        match values.len() {
+            0 => Err("The query produced no output.".to_string()),
+            1 => Ok((values.remove(0), 1)),
+            count => Ok((Value::Array(values), count)),
+        }
+    }
+
+    // Only the options that are set, e.g. `{"slurp": true, "args": [...]}`
+    pub fn to_json(&self) -> Value {
+        let mut object = Map::new();
+        for ((flag, _), on) in FLAGS.iter().zip(self.flags()) {
+            if on {
+                object.insert(flag.trim_start_matches('-').to_string(), Value::Bool(true));
+            }
+        }
+        if !self.variables.is_empty() {
+            let variables = self
+                .variables
+                .iter()
+                .map(|variable| json!({ "name": variable.name, "value": variable.value, "json": variable.json }))
+                .collect();
+            object.insert("args".to_string(), Value::Array(variables));
+        }
+        Value::Object(object)
+    }
+
+    pub fn from_json(value: &Value) -> JqOptions {
+        let mut options = JqOptions::default();
+        for ((flag, _), on) in FLAGS.iter().zip(options.flags_mut()) {
+            *on = value.get(flag.trim_start_matches('-')).and_then(Value::as_bool).unwrap_or_default();
+        }
+        options.variables = value
+            .get("args")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .map(|variable| JqVariable {
+                name: variable.get("name").and_then(Value::as_str).unwrap_or_default().to_string(),
+                value: variable.get("value").and_then(Value::as_str).unwrap_or_default().to_string(),
+                json: variable.get("json").and_then(Value::as_bool).unwrap_or_default(),
+            })
+            .collect();
+        options
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        for ((flag, description), on) in FLAGS.iter().zip(self.flags_mut()) {
+            ui.checkbox(on, *flag).on_hover_text(*description);
+        }
+        ui.separator();
+        ui.label("Variables");
+        let mut remove = None;
+        egui::Grid::new("jq_variables").num_columns(4).show(ui, |ui| {
+            for (index, variable) in self.variables.iter_mut().enumerate() {
+                ui.add(egui::TextEdit::singleline(&mut variable.name).hint_text("name").desired_width(80.0));
+                ui.add(egui::TextEdit::singleline(&mut variable.value).hint_text("value").desired_width(160.0));
+                ui.checkbox(&mut variable.json, "JSON")
+                    .on_hover_text("Pass the value with --argjson instead of as a string");
+                if ui.small_button("✖").clicked() {
+                    remove = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(index) = remove {
+            self.variables.remove(index);
+        }
+        if ui.button("Add Variable").clicked() {
+            self.variables.push(JqVariable::default());
+        }
+        if let Err(e) = self.command_args() {
+            ui.colored_label(Color32::RED, e);
+        }
+    }
+}
@@ -0,0 +1,600 @@
+// A lexer for jq filters, good enough for highlighting, bracket matching and
+// completion in the query field. It never fails: anything it doesn't
+// recognise becomes an `Operator` token, and unterminated strings run to the
+// end of the text.
+use std::ops::Range;
+
+use serde_json::Value;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+    // `.`, `.foo`; `..` is an operator
+    Field,
+    Variable, // `$name`
+    Format,   // `@base64`
+    Keyword,
+    // Builtins and user-defined functions
+    Identifier,
+    String,
+    Number,
+    Open,
+    Close,
+    Operator,
+    Comment,
+}
+
+#[derive(Clone, Debug)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub range: Range<usize>, // Bytes
+}
+
+const KEYWORDS: &[&str] = &[
+    "def", "if", "then", "elif", "else", "end", "as", "reduce", "foreach", "try", "catch", "label", "import",
+    "include", "and", "or", "__loc__",
+];
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+pub fn tokenize(filter: &str) -> Vec<Token> {
+    let bytes = filter.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    // End of the identifier-like run starting at `from`, with `::` for
+    // module-qualified names if `qualified`
+    let ident_end = |from: usize, qualified: bool| {
+        let mut end = from;
+        loop {
+            while end < bytes.len() && is_ident_char(bytes[end] as char) {
+                end += 1;
+            }
+            if qualified && filter[end..].starts_with("::") && filter[end + 2..].starts_with(is_ident_start) {
+                end += 2;
+            } else {
+                return end;
+            }
+        }
+    };
+    while pos < bytes.len() {
+        let c = filter[pos..].chars().next().unwrap_or_default();
+        if c.is_whitespace() {
+            pos += c.len_utf8();
+            continue;
+        }
+        let start = pos;
+        let kind = match c {
+            '#' => {
+                pos = filter[pos..].find('\n').map_or(filter.len(), |n| pos + n);
+                TokenKind::Comment
+            }
+            '"' => {
+                pos = string_end(filter, pos);
+                TokenKind::String
+            }
+            '.' if filter[pos + 1..].starts_with('.') => {
+                pos += 2;
+                TokenKind::Operator
+            }
+            '.' => {
+                pos += 1;
+                if filter[pos..].starts_with(is_ident_start) {
+                    pos = ident_end(pos, false);
+                }
+                TokenKind::Field
+            }
+            '$' => {
+                pos = ident_end(pos + 1, true);
+                TokenKind::Variable
+            }
+            '@' => {
+                pos = ident_end(pos + 1, false);
+                TokenKind::Format
+            }
+            c if c.is_ascii_digit() => {
+                while pos < bytes.len() && (bytes[pos].is_ascii_digit() || bytes[pos] == b'.') {
+                    pos += 1;
+                }
+                if pos < bytes.len() && (bytes[pos] == b'e' || bytes[pos] == b'E') {
+                    pos += 1;
+                    if pos < bytes.len() && (bytes[pos] == b'+' || bytes[pos] == b'-') {
+                        pos += 1;
+                    }
+                    while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                        pos += 1;
+                    }
+                }
+                TokenKind::Number
+            }
+            c if is_ident_start(c) => {
+                pos = ident_end(pos, true);
+                if KEYWORDS.contains(&&filter[start..pos]) {
+                    TokenKind::Keyword
+                } else {
+                    TokenKind::Identifier
+                }
+            }
+            '(' | '[' | '{' => {
+                pos += 1;
+                TokenKind::Open
+            }
+            ')' | ']' | '}' => {
+                pos += 1;
+                TokenKind::Close
+            }
+            _ => {
+                const OPERATORS: &[&str] = &["?//=", "?//", "//=", "|=", "+=", "-=", "*=", "/=", "%=", "==", "!=", "<=", ">=", "//"];
+                pos += OPERATORS
+                    .iter()
+                    .find(|op| filter[pos..].starts_with(**op))
+                    .map_or(c.len_utf8(), |op| op.len());
+                TokenKind::Operator
+            }
+        };
+        tokens.push(Token { kind, range: start..pos });
+    }
+    tokens
+}
+
+// Byte just past the string starting at `start`, skipping escapes and
+// `\(...)` interpolations, which may hold strings of their own
+fn string_end(filter: &str, start: usize) -> usize {
+    let bytes = filter.as_bytes();
+    let mut pos = start + 1;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'"' => return pos + 1,
+            b'\\' if bytes.get(pos + 1) == Some(&b'(') => {
+                pos += 2;
+                let mut depth = 1;
+                while pos < bytes.len() && depth > 0 {
+                    match bytes[pos] {
+                        b'(' => depth += 1,
+                        b')' => depth -= 1,
+                        b'"' => {
+                            pos = string_end(filter, pos);
+                            continue;
+                        }
+                        _ => {}
+                    }
+                    pos += 1;
+                }
+            }
+            b'\\' => pos += 2,
+            _ => pos += 1,
+        }
+    }
+    bytes.len()
+}
+
+fn closer(open: &str) -> &'static str {
+    match open {
+        "(" => ")",
+        "[" => "]",
+        _ => "}",
+    }
+}
+
+// Index of the token that closes each opening bracket, and the other way
+// round; `None` for brackets without a partner
+pub fn bracket_partners(filter: &str, tokens: &[Token]) -> Vec<Option<usize>> {
+    let mut partners = vec![None; tokens.len()];
+    let mut open: Vec<usize> = Vec::new();
+    for (index, token) in tokens.iter().enumerate() {
+        match token.kind {
+            TokenKind::Open => open.push(index),
+            TokenKind::Close => {
+                if let Some(&top) = open.last()
+                    && closer(&filter[tokens[top].range.clone()]) == &filter[token.range.clone()]
+                {
+                    open.pop();
+                    partners[top] = Some(index);
+                    partners[index] = Some(top);
+                }
+            }
+            _ => {}
+        }
+    }
+    partners
+}
+
+// Whether every bracket and string is closed and the filter doesn't end in
+// a binary operator, as a quick check that it isn't still being typed
+pub fn is_complete(filter: &str) -> bool {
+    let tokens = tokenize(filter);
+    let partners = bracket_partners(filter, &tokens);
+    let dangling = tokens.iter().rev().find(|token| token.kind != TokenKind::Comment).is_some_and(|token| {
+        token.kind == TokenKind::Operator && !matches!(&filter[token.range.clone()], "?" | "..")
+    });
+    let closed = tokens.iter().zip(&partners).all(|(token, partner)| match token.kind {
+        TokenKind::Open | TokenKind::Close => partner.is_some(),
+        TokenKind::String => token.range.len() > 1 && filter[token.range.clone()].ends_with('"'),
+        _ => true,
+    });
+    closed && !dangling
+}
+
+// Byte ranges of the top-level stages of a pipeline, without the `|`s.
+// Pipes inside brackets, strings, `if ... end` and function definitions
+// don't split, and neither does the one after a variable binding
+// (`.[] as $x | ...`), since what follows it needs `$x`.
+pub fn split_pipeline(filter: &str) -> Vec<Range<usize>> {
+    let mut stages = Vec::new();
+    let mut start = 0;
+    let mut depth = 0usize;
+    // Keyword nesting at bracket depth 0: `if`/`end`, `def`/`;`
+    let mut blocks: Vec<&str> = Vec::new();
+    // After `as $x` or `label $x`, whose `|` is part of the binding
+    let mut binding = false;
+    // Inside `reduce`/`foreach` before its `as`, which takes no `|`
+    let mut folding = false;
+    for token in tokenize(filter) {
+        let text = &filter[token.range.clone()];
+        match token.kind {
+            TokenKind::Open => depth += 1,
+            TokenKind::Close => depth = depth.saturating_sub(1),
+            _ if depth > 0 => {}
+            TokenKind::Keyword => match text {
+                "if" | "def" => blocks.push(text),
+                "end" if blocks.last() == Some(&"if") => {
+                    blocks.pop();
+                }
+                // Bindings inside a block end with it, and its pipes aren't
+                // split anyway
+                _ if !blocks.is_empty() => {}
+                "reduce" | "foreach" => folding = true,
+                // `reduce ... as $x (...)` binds without a pipe
+                "as" if folding => folding = false,
+                "as" | "label" => binding = true,
+                _ => {}
+            },
+            TokenKind::Operator if text == ";" && blocks.last() == Some(&"def") => {
+                blocks.pop();
+            }
+            TokenKind::Operator if text == "|" && blocks.is_empty() => {
+                if binding {
+                    binding = false;
+                } else {
+                    stages.push(start..token.range.start);
+                    start = token.range.end;
+                }
+            }
+            _ => {}
+        }
+    }
+    stages.push(start..filter.len());
+    stages
+}
+
+// One step of a plain path expression like `.data[0].items[]`
+#[derive(Clone, Debug, PartialEq)]
+enum Step {
+    Key(String),
+    Index(i64),
+    Iterate,
+}
+
+pub struct Suggestion {
+    pub insert: String,
+    pub label: String,
+    pub detail: String,
+}
+
+pub struct Completion {
+    // Bytes of the partly typed word the chosen suggestion replaces
+    pub replace: Range<usize>,
+    pub suggestions: Vec<Suggestion>,
+}
+
+const MAX_SUGGESTIONS: usize = 50;
+
+// Suggestions for the word ending at `cursor` (a byte index): keys of the
+// document after `.`, builtins when a function name is being typed.
+pub fn complete(filter: &str, cursor: usize, source: Option<&Value>) -> Option<Completion> {
+    let before = &filter[..cursor];
+    let tokens = tokenize(before);
+    let last = tokens.last()?;
+    if last.range.end != cursor {
+        // After `|` with nothing typed yet, offer every builtin
+        return (last.kind == TokenKind::Operator && &before[last.range.clone()] == "|").then(|| Completion {
+            replace: cursor..cursor,
+            suggestions: builtin_suggestions(""),
+        });
+    }
+    match last.kind {
+        TokenKind::Field => {
+            let partial = &before[last.range.start + 1..];
+            let root = source?;
+            let steps = context_steps(before, &tokens[..tokens.len() - 1]);
+            let nodes = match &steps {
+                Some(steps) => resolve(vec![root], steps),
+                None => every_container(root),
+            };
+            let suggestions = key_suggestions(&nodes, partial);
+            (!suggestions.is_empty()).then_some(Completion {
+                replace: last.range.start + 1..cursor,
+                suggestions,
+            })
+        }
+        TokenKind::Identifier => {
+            let suggestions = builtin_suggestions(&before[last.range.clone()]);
+            (!suggestions.is_empty()).then_some(Completion {
+                replace: last.range.clone(),
+                suggestions,
+            })
+        }
+        _ => None,
+    }
+}
+
+// The path the input of the field being typed is at, when the filter up to
+// it is a pipeline of plain paths (`.data[] | .items[0] | .`), and `None`
+// when it can't be known without running the filter
+fn context_steps(filter: &str, tokens: &[Token]) -> Option<Vec<Step>> {
+    let mut stages: Vec<Vec<Step>> = vec![Vec::new()];
+    let mut index = 0;
+    while index < tokens.len() {
+        let token = &tokens[index];
+        let text = &filter[token.range.clone()];
+        let steps = stages.last_mut()?;
+        match token.kind {
+            TokenKind::Field if text.len() > 1 => steps.push(Step::Key(text[1..].to_string())),
+            TokenKind::Field => {
+                // `."key"`
+                if let Some(next) = tokens.get(index + 1)
+                    && next.kind == TokenKind::String
+                    && next.range.start == token.range.end
+                {
+                    steps.push(Step::Key(serde_json::from_str(&filter[next.range.clone()]).ok()?));
+                    index += 1;
+                }
+            }
+            TokenKind::Open if text == "[" => {
+                let inner = tokens.get(index + 1)?;
+                let inner_text = &filter[inner.range.clone()];
+                if inner_text == "]" {
+                    steps.push(Step::Iterate);
+                    index += 1;
+                } else if tokens.get(index + 2).is_some_and(|t| &filter[t.range.clone()] == "]") {
+                    steps.push(match inner.kind {
+                        TokenKind::Number => Step::Index(inner_text.parse().ok()?),
+                        TokenKind::String => Step::Key(serde_json::from_str(inner_text).ok()?),
+                        _ => return None,
+                    });
+                    index += 2;
+                } else {
+                    return None;
+                }
+            }
+            TokenKind::Operator if text == "?" => {}
+            TokenKind::Operator if text == "|" => stages.push(Vec::new()),
+            _ => return None,
+        }
+        index += 1;
+    }
+    Some(stages.concat())
+}
+
+fn resolve<'a>(mut nodes: Vec<&'a Value>, steps: &[Step]) -> Vec<&'a Value> {
+    for step in steps {
+        nodes = nodes
+            .into_iter()
+            .flat_map(|node| -> Vec<&Value> {
+                match (step, node) {
+                    (Step::Key(key), Value::Object(map)) => map.get(key).into_iter().collect(),
+                    (Step::Index(i), Value::Array(items)) => {
+                        let i = if *i < 0 { items.len() as i64 + i } else { *i };
+                        usize::try_from(i).ok().and_then(|i| items.get(i)).into_iter().collect()
+                    }
+                    (Step::Iterate, Value::Array(items)) => items.iter().collect(),
+                    (Step::Iterate, Value::Object(map)) => map.values().collect(),
+                    _ => Vec::new(),
+                }
+            })
+            .collect();
+    }
+    nodes
+}
+
+// Every object and array, for when the field's input is unknown; limited so
+// huge documents stay responsive
+fn every_container(root: &Value) -> Vec<&Value> {
+    const LIMIT: usize = 100_000;
+    let mut found = Vec::new();
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        if found.len() >= LIMIT {
+            break;
+        }
+        match node {
+            Value::Object(map) => stack.extend(map.values()),
+            Value::Array(items) => stack.extend(items.iter()),
+            _ => continue,
+        }
+        found.push(node);
+    }
+    found
+}
+
+fn key_suggestions(nodes: &[&Value], partial: &str) -> Vec<Suggestion> {
+    let mut keys: Vec<(&String, &Value)> = Vec::new();
+    for node in nodes {
+        if let Value::Object(map) = node {
+            for (key, value) in map {
+                if key.starts_with(partial) && key != partial && !keys.iter().any(|(k, _)| *k == key) {
+                    keys.push((key, value));
+                }
+            }
+        }
+    }
+    keys.sort_by_key(|(key, _)| key.to_lowercase());
+    keys.truncate(MAX_SUGGESTIONS);
+    keys.into_iter()
+        .map(|(key, value)| {
+            let plain = key.starts_with(is_ident_start) && key.chars().all(is_ident_char);
+            Suggestion {
+                insert: if plain { key.clone() } else { Value::String(key.clone()).to_string() },
+                label: key.clone(),
+                detail: match value {
+                    Value::Null => "null",
+                    Value::Bool(_) => "boolean",
+                    Value::Number(_) => "number",
+                    Value::String(_) => "string",
+                    Value::Array(_) => "array",
+                    Value::Object(_) => "object",
+                }
+                .to_string(),
+            }
+        })
+        .collect()
+}
+
+fn builtin_suggestions(partial: &str) -> Vec<Suggestion> {
+    let mut suggestions: Vec<Suggestion> = BUILTINS
+        .iter()
+        .filter(|(signature, _)| signature.starts_with(partial))
+        .map(|(signature, description)| Suggestion {
+            // Functions with arguments are inserted with their parentheses
+            insert: match signature.find('(') {
+                Some(paren) => format!("{}(", &signature[..paren]),
+                None => signature.to_string(),
+            },
+            label: signature.to_string(),
+            detail: description.to_string(),
+        })
+        .filter(|suggestion| suggestion.insert != partial)
+        .collect();
+    suggestions.sort_by(|a, b| a.label.cmp(&b.label));
+    suggestions.truncate(MAX_SUGGESTIONS);
+    suggestions
+}
+
+// jq 1.7 builtins: signature and what it does
+const BUILTINS: &[(&str, &str)] = &[
+    ("add", "Sums or concatenates the elements of an array"),
+    ("all", "Whether every element is true"),
+    ("all(f)", "Whether f is true for every element"),
+    ("any", "Whether any element is true"),
+    ("any(f)", "Whether f is true for any element"),
+    ("arrays", "Keeps inputs that are arrays"),
+    ("ascii_downcase", "Lowercases ASCII letters"),
+    ("ascii_upcase", "Uppercases ASCII letters"),
+    ("booleans", "Keeps inputs that are booleans"),
+    ("capture(re)", "Object of the named groups of a regex match"),
+    ("del(path)", "Removes the value at a path"),
+    ("empty", "Produces no output"),
+    ("endswith(s)", "Whether the string ends with s"),
+    ("env", "Environment variables as an object"),
+    ("error(msg)", "Raises an error"),
+    ("explode", "String to an array of codepoints"),
+    ("first", "First element of an array"),
+    ("first(f)", "First output of f"),
+    ("flatten", "Flattens nested arrays"),
+    ("flatten(depth)", "Flattens nested arrays up to a depth"),
+    ("from_entries", "Object from [{key, value}] pairs"),
+    ("fromjson", "Parses a JSON string"),
+    ("getpath(path)", "Value at a path array"),
+    ("group_by(f)", "Groups elements by f into arrays"),
+    ("gsub(re; s)", "Replaces every regex match"),
+    ("has(key)", "Whether the object or array has a key or index"),
+    ("implode", "Array of codepoints to a string"),
+    ("in(obj)", "Whether the input is a key of obj"),
+    ("index(s)", "Index of the first occurrence of s"),
+    ("indices(s)", "Indices of every occurrence of s"),
+    ("input", "Next input"),
+    ("inputs", "All remaining inputs"),
+    ("inside(b)", "Whether the input is contained in b"),
+    ("contains(b)", "Whether b is contained in the input"),
+    ("isempty(f)", "Whether f produces no output"),
+    ("iterables", "Keeps arrays and objects"),
+    ("join(sep)", "Joins an array of strings"),
+    ("keys", "Sorted keys of an object"),
+    ("keys_unsorted", "Keys of an object in their order"),
+    ("last", "Last element of an array"),
+    ("last(f)", "Last output of f"),
+    ("length", "Length of a string, array or object"),
+    ("limit(n; f)", "First n outputs of f"),
+    ("ltrimstr(s)", "Removes a prefix"),
+    ("map(f)", "Applies f to every element of an array"),
+    ("map_values(f)", "Applies f to every value of an object or array"),
+    ("match(re)", "Regex match objects"),
+    ("max", "Largest element"),
+    ("max_by(f)", "Element with the largest f"),
+    ("min", "Smallest element"),
+    ("min_by(f)", "Element with the smallest f"),
+    ("not", "Logical negation"),
+    ("nulls", "Keeps inputs that are null"),
+    ("numbers", "Keeps inputs that are numbers"),
+    ("objects", "Keeps inputs that are objects"),
+    ("path(f)", "Path arrays of the values f selects"),
+    ("paths", "Paths of every value"),
+    ("paths(f)", "Paths of the values for which f is true"),
+    ("range(n)", "0 up to n - 1"),
+    ("range(from; upto)", "from up to upto - 1"),
+    ("recurse", "Every value, recursively"),
+    ("recurse(f)", "Applies f recursively"),
+    ("reverse", "Reverses an array or string"),
+    ("rtrimstr(s)", "Removes a suffix"),
+    ("scalars", "Keeps inputs that aren't arrays or objects"),
+    ("scan(re)", "Every match of a regex"),
+    ("select(f)", "Keeps the input if f is true"),
+    ("setpath(path; v)", "Sets the value at a path array"),
+    ("sort", "Sorts an array"),
+    ("sort_by(f)", "Sorts an array by f"),
+    ("split(s)", "Splits a string on a separator"),
+    ("splits(re)", "Splits a string on a regex"),
+    ("startswith(s)", "Whether the string starts with s"),
+    ("strings", "Keeps inputs that are strings"),
+    ("sub(re; s)", "Replaces the first regex match"),
+    ("test(re)", "Whether a regex matches"),
+    ("to_entries", "Object to [{key, value}] pairs"),
+    ("todate", "Unix time to an ISO 8601 date"),
+    ("fromdate", "ISO 8601 date to Unix time"),
+    ("now", "Current Unix time"),
+    ("tojson", "Serializes to a JSON string"),
+    ("tonumber", "Parses a number"),
+    ("tostream", "Streams [path, leaf] events"),
+    ("tostring", "Converts to a string"),
+    ("transpose", "Transposes an array of arrays"),
+    ("type", "Name of the input's type"),
+    ("unique", "Sorted distinct elements"),
+    ("unique_by(f)", "Distinct elements by f"),
+    ("until(cond; next)", "Applies next until cond is true"),
+    ("utf8bytelength", "Length of a string in UTF-8 bytes"),
+    ("values", "Keeps inputs that aren't null"),
+    ("walk(f)", "Applies f to every value, bottom-up"),
+    ("while(cond; update)", "Repeats update while cond is true"),
+    ("with_entries(f)", "Applies f to every {key, value} pair"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stages(filter: &str) -> Vec<&str> {
+        split_pipeline(filter).into_iter().map(|range| filter[range].trim()).collect()
+    }
+
+    #[test]
+    fn splits_top_level_pipes() {
+        assert_eq!(stages(".a | .b[] | select(.x | . > 1)"), [".a", ".b[]", "select(.x | . > 1)"]);
+        assert_eq!(stages(r#""a|b" | length"#), [r#""a|b""#, "length"]);
+        assert_eq!(stages("if . then .a | .b else . end | keys"), ["if . then .a | .b else . end", "keys"]);
+    }
+
+    #[test]
+    fn keeps_bindings_with_their_body() {
+        assert_eq!(stages(".a as $x | $x | .b"), [".a as $x | $x", ".b"]);
+        assert_eq!(stages("label $out | .a | .b"), ["label $out | .a", ".b"]);
+        assert_eq!(stages("reduce .[] as $x (0; . + $x) | . * 2"), ["reduce .[] as $x (0; . + $x)", ". * 2"]);
+        assert_eq!(stages("foreach .[] as $x (0; . + $x) | tostring"), ["foreach .[] as $x (0; . + $x)", "tostring"]);
+        // A binding inside a definition ends with it
+        assert_eq!(stages("def f: . as $x | $x; .a | f"), ["def f: . as $x | $x; .a", "f"]);
+        assert_eq!(stages("def f: reduce .[] as $x (0; .); .a | f"), ["def f: reduce .[] as $x (0; .); .a", "f"]);
+    }
+}
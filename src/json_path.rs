@@ -0,0 +1,277 @@
+// Paths to nodes inside a parsed JSON document, and the different textual
+// notations we can render them in (JSONPath, JSON Pointer, jq, JavaScript).
+use serde_json::Value;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct JsonPath {
+    segments: Vec<PathSegment>,
+}
+
+impl JsonPath {
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    pub fn child_key(&self, key: &str) -> Self {
+        let mut segments = self.segments.clone();
+        segments.push(PathSegment::Key(key.to_string()));
+        Self { segments }
+    }
+
+    pub fn child_index(&self, index: usize) -> Self {
+        let mut segments = self.segments.clone();
+        segments.push(PathSegment::Index(index));
+        Self { segments }
+    }
+
+    // Parses a JSON Pointer (RFC 6901). Whether a numeric token is an array
+    // index or an object key depends on the document, so `root` is walked
+    // alongside the pointer.
+    pub fn from_json_pointer(pointer: &str, root: &Value) -> Option<JsonPath> {
+        if pointer.is_empty() {
+            return Some(Self::root());
+        }
+        let mut path = Self::root();
+        let mut node = Some(root);
+        for token in pointer.strip_prefix('/')?.split('/') {
+            let token = token.replace("~1", "/").replace("~0", "~");
+            match (node, token.parse::<usize>()) {
+                (Some(Value::Array(arr)), Ok(index)) => {
+                    node = arr.get(index);
+                    path = path.child_index(index);
+                }
+                _ => {
+                    node = node.and_then(|n| n.get(&token));
+                    path = path.child_key(&token);
+                }
+            }
+        }
+        Some(path)
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    // The ancestor made of the first `len` segments
+    pub fn truncated(&self, len: usize) -> JsonPath {
+        Self { segments: self.segments[..len.min(self.segments.len())].to_vec() }
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    pub fn last(&self) -> Option<&PathSegment> {
+        self.segments.last()
+    }
+
+    pub fn parent(&self) -> Option<JsonPath> {
+        let (_, rest) = self.segments.split_last()?;
+        Some(Self { segments: rest.to_vec() })
+    }
+
+    // JSONPath, e.g. `$.data[0].id`. Keys that aren't plain identifiers use
+    // the bracketed, single-quoted form from RFC 9535: `$['a.b']`.
+    pub fn to_jsonpath(&self) -> String {
+        let mut out = String::from("$");
+        for segment in &self.segments {
+            match segment {
+                PathSegment::Key(key) if is_identifier(key) => {
+                    out.push('.');
+                    out.push_str(key);
+                }
+                PathSegment::Key(key) => {
+                    out.push_str("['");
+                    push_escaped(&mut out, key, '\'');
+                    out.push_str("']");
+                }
+                PathSegment::Index(index) => out.push_str(&format!("[{}]", index)),
+            }
+        }
+        out
+    }
+
+    // RFC 9535 normalized path, e.g. `$['data'][0]['id']`: every key in
+    // brackets and single quotes, so each node has exactly one spelling.
+    pub fn to_normalized_path(&self) -> String {
+        let mut out = String::from("$");
+        for segment in &self.segments {
+            match segment {
+                PathSegment::Key(key) => {
+                    out.push_str("['");
+                    push_escaped(&mut out, key, '\'');
+                    out.push_str("']");
+                }
+                PathSegment::Index(index) => out.push_str(&format!("[{}]", index)),
+            }
+        }
+        out
+    }
+
+    // JSON Pointer (RFC 6901), e.g. `/data/0/id`. The root is the empty string.
+    pub fn to_json_pointer(&self) -> String {
+        let mut out = String::new();
+        for segment in &self.segments {
+            out.push('/');
+            match segment {
+                // `~` must be escaped before `/`, otherwise `/` -> `~1` -> `~01`
+                PathSegment::Key(key) => out.push_str(&key.replace('~', "~0").replace('/', "~1")),
+                PathSegment::Index(index) => out.push_str(&index.to_string()),
+            }
+        }
+        out
+    }
+
+    // jq filter, e.g. `.data[0].id`. The root is `.`, other keys are quoted: `."a.b"`.
+    pub fn to_jq(&self) -> String {
+        if self.segments.is_empty() {
+            return ".".to_string();
+        }
+        let mut out = String::new();
+        for segment in &self.segments {
+            match segment {
+                PathSegment::Key(key) if is_identifier(key) => {
+                    out.push('.');
+                    out.push_str(key);
+                }
+                PathSegment::Key(key) => {
+                    out.push_str(".\"");
+                    push_escaped(&mut out, key, '"');
+                    out.push('"');
+                }
+                PathSegment::Index(index) => {
+                    if out.is_empty() {
+                        out.push('.');
+                    }
+                    out.push_str(&format!("[{}]", index));
+                }
+            }
+        }
+        out
+    }
+
+    // JavaScript accessor on a variable named `json`, e.g. `json.data[0].id`,
+    // falling back to `json["a.b"]` for keys that aren't valid identifiers.
+    pub fn to_javascript(&self) -> String {
+        let mut out = String::from("json");
+        for segment in &self.segments {
+            match segment {
+                PathSegment::Key(key) if is_identifier(key) => {
+                    out.push('.');
+                    out.push_str(key);
+                }
+                PathSegment::Key(key) => {
+                    out.push_str("[\"");
+                    push_escaped(&mut out, key, '"');
+                    out.push_str("\"]");
+                }
+                PathSegment::Index(index) => out.push_str(&format!("[{}]", index)),
+            }
+        }
+        out
+    }
+}
+
+// Keys that can be written with dot notation in JSONPath, jq and JavaScript.
+// This is the intersection of the three grammars (no `$` since jq rejects it).
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// Appends `text` as the body of a string literal delimited by `quote`,
+// escaping the quote, backslashes and control characters.
+fn push_escaped(out: &mut String, text: &str, quote: char) {
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{08}' => out.push_str("\\b"),
+            '\u{0c}' => out.push_str("\\f"),
+            c if c == quote => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::query::run_jsonpath;
+
+    // Keys that need quoting or escaping in at least one syntax
+    const KEYS: [&str; 10] = [
+        "plain", "a.b", "a/b", "m~n", "~1", "it's", "back\\slash", "quote\"d", "line\nbreak", "",
+    ];
+
+    fn document() -> Value {
+        let mut map = serde_json::Map::new();
+        for key in KEYS {
+            map.insert(key.to_string(), json!([{ key: true }]));
+        }
+        Value::Object(map)
+    }
+
+    fn paths() -> Vec<JsonPath> {
+        KEYS.iter().map(|key| JsonPath::root().child_key(key).child_index(0).child_key(key)).collect()
+    }
+
+    #[test]
+    fn formats_paths_in_each_syntax() {
+        let path = JsonPath::root().child_key("data").child_index(0).child_key("a.b");
+        assert_eq!(path.to_jsonpath(), "$.data[0]['a.b']");
+        assert_eq!(path.to_normalized_path(), "$['data'][0]['a.b']");
+        assert_eq!(path.to_json_pointer(), "/data/0/a.b");
+        assert_eq!(path.to_jq(), ".data[0].\"a.b\"");
+        assert_eq!(path.to_javascript(), "json.data[0][\"a.b\"]");
+        assert_eq!(JsonPath::root().child_index(2).to_jq(), ".[2]");
+        assert_eq!(JsonPath::root().to_json_pointer(), "");
+        assert_eq!(JsonPath::root().child_key("it's").to_jsonpath(), "$['it\\'s']");
+        assert_eq!(JsonPath::root().child_key("m~/n").to_json_pointer(), "/m~0~1n");
+    }
+
+    #[test]
+    fn json_pointers_round_trip() {
+        let document = document();
+        for path in paths() {
+            let pointer = path.to_json_pointer();
+            assert_eq!(JsonPath::from_json_pointer(&pointer, &document), Some(path.clone()), "{}", pointer);
+            assert_eq!(document.pointer(&pointer), Some(&Value::Bool(true)), "{}", pointer);
+        }
+        // Numeric tokens are keys in objects and indexes in arrays
+        let document = json!({ "0": [10, 11] });
+        let expected = JsonPath::root().child_key("0").child_index(1);
+        assert_eq!(JsonPath::from_json_pointer("/0/1", &document), Some(expected));
+    }
+
+    #[test]
+    fn jsonpaths_select_their_node() {
+        let document = document();
+        for path in paths() {
+            for query in [path.to_jsonpath(), path.to_normalized_path()] {
+                let matches = run_jsonpath(&document, &query).unwrap();
+                assert_eq!(matches.len(), 1, "{}", query);
+                assert_eq!(matches[0].path, path, "{}", query);
+            }
+        }
+    }
+}
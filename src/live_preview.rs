@@ -0,0 +1,163 @@
+// Live query preview: while enabled, the query is re-run on a background
+// thread shortly after the user stops typing, and the result is shown in its
+// own pane next to the tree, which keeps showing the document. While a jq
+// filter is obviously unfinished (an open bracket or string, a trailing
+// `|`) or fails, the last good result stays up. A jq run or SQL query that
+// is still going when the query changes is stopped, as is one that runs too
+// long or returns too much.
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, TryRecvError, channel};
+use std::time::{Duration, Instant};
+
+use eframe::egui;
+use egui::Color32;
+use serde_json::Value;
+
+use crate::jq_options::{JqLimits, JqOptions};
+use crate::jq_syntax::is_complete;
+use crate::query::{self, QueryLanguage};
+
+// How long typing has to pause before the query runs
+const DEBOUNCE: Duration = Duration::from_millis(300);
+// Longer results are cut off in the pane
+const MAX_PREVIEW_CHARS: usize = 100_000;
+// Limits for a jq run or SQL query, past which the preview gives up on it
+const MAX_RUN_TIME: Duration = Duration::from_secs(5);
+const MAX_OUTPUT_BYTES: usize = 16 * 1024 * 1024;
+
+#[derive(Default)]
+pub struct LivePreview {
+    pub enabled: bool,
+    // Hash of the language, query and document text to preview, and when
+    // it last changed
+    wanted: Option<u64>,
+    changed_at: Option<Instant>,
+    // The last request that was run or skipped
+    handled: Option<u64>,
+    running: Option<Receiver<Result<Value, String>>>,
+    // Stops the running jq or SQL query
+    cancel: Arc<AtomicBool>,
+    // The last good result: its pretty-printed text and size
+    result: Option<(String, String)>,
+    // Why the pane doesn't show the current query's result, and whether
+    // that's an error
+    status: Option<(String, bool)>,
+}
+
+impl LivePreview {
+    // Collects a finished run and starts the next one when due. Call every
+    // frame.
+    pub fn update(
+        &mut self,
+        ctx: &egui::Context,
+        language: QueryLanguage,
+        query: &str,
+        options: &JqOptions,
+        input: &str,
+    ) {
+        if !self.enabled {
+            if self.running.take().is_some() {
+                self.cancel.store(true, Ordering::Relaxed);
+            }
+            return;
+        }
+        if let Some(receiver) = &self.running {
+            match receiver.try_recv() {
+                Ok(Ok(value)) => {
+                    self.result = Some((preview_text(&value), query::result_size(&value)));
+                    self.status = None;
+                    self.running = None;
+                }
+                Ok(Err(e)) => {
+                    self.status = Some((e, true));
+                    self.running = None;
+                }
+                Err(TryRecvError::Disconnected) => self.running = None,
+                Err(TryRecvError::Empty) => {}
+            }
+        }
+
+        let mut hasher = DefaultHasher::new();
+        (language.label(), query, options, input).hash(&mut hasher);
+        let wanted = hasher.finish();
+        if self.wanted != Some(wanted) {
+            self.wanted = Some(wanted);
+            self.changed_at = Some(Instant::now());
+            // Its result would be stale by the time it arrives
+            if self.running.take().is_some() {
+                self.cancel.store(true, Ordering::Relaxed);
+            }
+        }
+        if self.handled == Some(wanted) || self.running.is_some() {
+            return;
+        }
+        let waited = self.changed_at.map_or(DEBOUNCE, |at| at.elapsed());
+        if waited < DEBOUNCE {
+            ctx.request_repaint_after(DEBOUNCE - waited);
+            return;
+        }
+        self.handled = Some(wanted);
+        if query.trim().is_empty() {
+            self.result = None;
+            self.status = None;
+        } else if language == QueryLanguage::Jq && !is_complete(query) {
+            self.status = Some(("The filter is incomplete; showing the last result.".to_string(), false));
+        } else {
+            let (sender, receiver) = channel();
+            let (ctx, query, options, input) = (ctx.clone(), query.to_string(), options.clone(), input.to_string());
+            self.cancel = Arc::new(AtomicBool::new(false));
+            let limits = JqLimits {
+                timeout: Some(MAX_RUN_TIME),
+                max_output_bytes: Some(MAX_OUTPUT_BYTES),
+                cancel: self.cancel.clone(),
+            };
+            std::thread::spawn(move || {
+                let _ = sender.send(query::evaluate(language, &input, &query, &options, &limits));
+                ctx.request_repaint();
+            });
+            self.running = Some(receiver);
+        }
+    }
+
+    pub fn show(&self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.strong("Live preview");
+            if self.running.is_some() {
+                ui.spinner();
+            }
+            if let Some((_, size)) = &self.result {
+                ui.weak(size);
+            }
+        });
+        match &self.status {
+            Some((message, true)) => {
+                ui.colored_label(Color32::LIGHT_RED, message);
+            }
+            Some((message, false)) => {
+                ui.weak(message);
+            }
+            None => {}
+        }
+        ui.separator();
+        match &self.result {
+            Some((text, _)) => {
+                egui::ScrollArea::both().id_salt("live_preview_scroll").show(ui, |ui| {
+                    ui.add(egui::Label::new(egui::RichText::new(text).monospace()).extend());
+                });
+            }
+            None => {
+                ui.weak("The query's result appears here as you type.");
+            }
+        }
+    }
+}
+
+fn preview_text(value: &Value) -> String {
+    let text = serde_json::to_string_pretty(value).unwrap_or_default();
+    match text.char_indices().nth(MAX_PREVIEW_CHARS) {
+        Some((cut, _)) => format!("{}\n… (cut off; run the query to see all of it)", &text[..cut]),
+        None => text,
+    }
+}
@@ -3,97 +3,53 @@ use serde_json::{self, Value};
 
 use egui::TextStyle;
 use egui::text::{LayoutJob, TextFormat};
-use egui::{CollapsingHeader, Color32,Ui};
+use egui::{Color32,Ui};
 // use std::process::{Command, Stdio}; // For process command
 
-fn create_highlighted_layout_sections(
-    ui: &egui::Ui,
-    full_text_content: &str, // Renamed to clearly indicate it's the full content
-    search_query: &str,
-    default_text_color: Color32,
-    is_strong: bool,
-) -> (String, Vec<egui::text::LayoutSection>) {
-    // Returns a tuple: (full_text, sections)
-    let mut sections = Vec::new();
-    // let current_text_byte_offset = 0; // Tracks byte offset for Ranges
-
-    let base_font_id = TextStyle::Body.resolve(ui.style());
-
-    if is_strong {
-        //     base_font_id.weight = FontWeight::Bold;
-    }
-
-    let default_format = TextFormat {
-        font_id: base_font_id.clone(),
-        extra_letter_spacing: 0.0, // Add this line
-        line_height: None,         // Add this line
-        color: default_text_color,
-        italics: false,
-        strikethrough: Default::default(), // Use Default for Stroke
-        underline: Default::default(),     // Use Default for Stroke
-        background: Color32::TRANSPARENT,
-        valign: egui::Align::Center,
-    };
-
-    let highlighted_format = TextFormat {
-        font_id: base_font_id.clone(),
-        extra_letter_spacing: 0.0, // Add this line
-        line_height: None,         // Add this line
-        color: Color32::BLACK,
-        background: Color32::from_rgb(255, 255, 0),
-        italics: false,
-        strikethrough: Default::default(),
-        underline: Default::default(),
-        valign: egui::Align::Center,
-    };
-
-    if search_query.is_empty() {
-        // If no search query, the entire text is one section with default format
-        sections.push(egui::text::LayoutSection {
-            leading_space: 0.0,
-            byte_range: 0..full_text_content.len(),
-            format: default_format,
-        });
-        return (full_text_content.to_string(), sections);
-    }
-
-    let lower_full_text = full_text_content.to_lowercase();
-    let lower_search_query = search_query.to_lowercase();
-    let mut last_end_byte = 0;
-
-    for (start_byte, matched_str) in lower_full_text.match_indices(&lower_search_query) {
-        let matched_len_bytes = matched_str.len(); // Get the byte length of the matched string slice
-
-        // Add the part before the match (if any)
-        if start_byte > last_end_byte {
-            sections.push(egui::text::LayoutSection {
-                leading_space: 0.0,
-                byte_range: last_end_byte..start_byte,
-                format: default_format.clone(),
-            });
-        }
-
-        // Add the matched part with highlight
-        sections.push(egui::text::LayoutSection {
-            leading_space: 0.0,
-            byte_range: start_byte..(start_byte + matched_len_bytes), // Use the byte length here
-            format: highlighted_format.clone(),
-        });
-
-        last_end_byte = start_byte + matched_len_bytes;
-    }
-
-    // Add the remaining part after the last match (if any)
-    if last_end_byte < full_text_content.len() {
-        sections.push(egui::text::LayoutSection {
-            leading_space: 0.0,
-            byte_range: last_end_byte..full_text_content.len(),
-            format: default_format,
-        });
-    }
-
-    (full_text_content.to_string(), sections)
-}
+mod batch;
+mod binary;
+mod codegen;
+mod convert;
+mod convert_view;
+mod diff;
+mod diff_view;
+mod document;
+mod infer;
+mod jq_options;
+mod jq_syntax;
+mod json_path;
+mod live_preview;
+mod patch;
+mod patch_view;
+mod pipeline_debugger;
+mod protobuf;
+mod protobuf_view;
+mod query;
+mod query_editor;
+mod query_library;
+mod query_library_view;
+mod schema;
+mod span_parser;
+mod sql;
+mod table_view;
+mod tree_edit;
+mod tree_nav;
+mod tree_view;
+use batch::BatchPanel;
+use binary::BinaryFormat;
+use codegen::CodegenPanel;
+use convert::DataFormat;
+use convert_view::{ConversionReport, ConvertedOutput};
+use diff_view::DiffView;
+use document::Document;
+use jq_options::{JqLimits, JqOptions};
+use json_path::JsonPath;
+use patch_view::PatchPanel;
+use pipeline_debugger::PipelineDebugger;
+use protobuf_view::{ProtobufOutput, ProtobufPanel};
+use query::QueryLanguage;
+use query_library_view::QueryLibraryPanel;
+use tree_view::{TreeContext, render_breadcrumbs, render_json_value};
 
 fn parse_json_to_value(json_string: &str) -> Result<Value, String> {
     // Attempt to parse the input string into a serde_json::Value.
@@ -110,214 +66,6 @@ fn parse_json_to_value(json_string: &str) -> Result<Value, String> {
     }
 }
 
-fn render_json_value(
-    ui: &mut egui::Ui,
-    key_name: Option<&str>,
-    value: &Value,
-    path_segment: &str,
-    search_query: &str,
-) {
-    ui.horizontal(|ui| {
-        // let mut key_text = String::new();
-        // Display the key name if provided (for object fields)
-        if let Some(key) = key_name {
-            // key_text = format!("\"{}\":", key);
-            // let mut rich_key_text = RichText::new(key_text.clone())
-            //     .strong()
-            //     .color(Color32::LIGHT_BLUE);
-            // if !search_query.is_empty() && key.to_lowercase().contains(&search_query.to_lowercase())
-            // {
-            //     rich_key_text = rich_key_text.color(Color32::BLACK); // Yellow highlight
-            //     rich_key_text = rich_key_text.background_color(Color32::YELLOW); // Yellow highlight
-            // }
-            // ui.add(egui::Label::new(rich_key_text));
-            // ui.add_space(5.0);
-            let key_display = format!("\"{}\":", key);
-            // Get the full text and its sections
-            let (full_text, layout_sections) = create_highlighted_layout_sections(
-                ui,
-                &key_display,
-                search_query,
-                Color32::LIGHT_BLUE,
-                true,
-            );
-            // Create LayoutJob from the full text and the sections
-            let layout_job = LayoutJob {
-                text: full_text,           // The entire string for the job
-                sections: layout_sections, // The formatting sections
-                // wrap: true, // Typically want wrapping for labels
-                ..Default::default()
-            };
-            ui.add(egui::Label::new(egui::WidgetText::LayoutJob(layout_job)));
-            ui.add_space(5.0);
-        }
-
-        match value {
-            Value::Object(map) => {
-                // Header for objects
-                let header_text = format!("{{ ... }} ({} items)", map.len());
-                // Create a unique ID for this collapsing header
-                let id = ui.make_persistent_id(format!("{}_object", path_segment));
-
-                CollapsingHeader::new(header_text)
-                    .id_salt(id)
-                    .default_open(true) // Starts open by default
-                    .show(ui, |ui| {
-                        // Indent the content within the collapsing header
-                        ui.indent("object_indent", |ui| {
-                            for (key, val) in map {
-                                // Recursively render each key-value pair in the object
-                                render_json_value(
-                                    ui,
-                                    Some(key),
-                                    val,
-                                    &format!("{}.{}", path_segment, key),
-                                    search_query,
-                                );
-                            }
-                        });
-                    });
-            }
-            Value::Array(arr) => {
-                // Header for arrays
-                let header_text = format!("[ ... ] ({} items)", arr.len());
-                // Create a unique ID for this collapsing header
-                let id = ui.make_persistent_id(format!("{}_array", path_segment));
-
-                CollapsingHeader::new(header_text)
-                    .id_salt(id)
-                    .default_open(true) // Starts open by default
-                    .show(ui, |ui| {
-                        // Indent the content within the collapsing header
-                        ui.indent("array_indent", |ui| {
-                            for (index, val) in arr.iter().enumerate() {
-                                // Recursively render each element in the array.
-                                // We pass `None` for key_name as array elements don't have keys,
-                                // but the index is part of the unique path.
-                                render_json_value(
-                                    ui,
-                                    None,
-                                    val,
-                                    &format!("{}[{}]", path_segment, index),
-                                    search_query,
-                                );
-                            }
-                        });
-                    });
-            }
-            // Handle primitive JSON types
-            Value::String(s) => {
-                // key_text = format!("\"{}\"", s);
-                // let mut rich_key_text = RichText::new(key_text.clone())
-                //     .strong()
-                //     .color(Color32::GREEN);
-                // if !search_query.is_empty() && s.to_lowercase().contains(&search_query.to_lowercase())
-                // {
-                //     rich_key_text = rich_key_text.color(Color32::BLACK); // Yellow highlight
-                //     rich_key_text = rich_key_text.background_color(Color32::YELLOW); // Yellow highlight
-                // }
-                // ui.add(egui::Label::new(rich_key_text));
-                // ui.add_space(5.0);
-                let (full_text, layout_sections) = create_highlighted_layout_sections(
-                    ui,
-                    &format!("\"{}\"", s),
-                    search_query,
-                    Color32::GREEN,
-                    false,
-                );
-                let layout_job = LayoutJob {
-                    text: full_text,
-                    sections: layout_sections,
-                    // wrap: true,
-                    ..Default::default()
-                };
-                ui.label(egui::WidgetText::LayoutJob(layout_job));
-            }
-            Value::Number(n) => {
-                // let num_str = n.to_string(); // Immutable copy for search check
-                // let mut rich_key_text = RichText::new(num_str.clone())
-                //     .strong()
-                //     .color(Color32::YELLOW);
-                // if !search_query.is_empty() && num_str.to_lowercase().contains(&search_query.to_lowercase())
-                // {
-                //     rich_key_text = rich_key_text.color(Color32::BLACK); // Yellow highlight
-                //     rich_key_text = rich_key_text.background_color(Color32::YELLOW); // Yellow highlight
-                // }
-                // ui.add(egui::Label::new(rich_key_text));
-                // ui.add_space(5.0);
-                let (full_text, layout_sections) = create_highlighted_layout_sections(
-                    ui,
-                    &n.to_string(),
-                    search_query,
-                    Color32::YELLOW,
-                    false,
-                );
-                let layout_job = LayoutJob {
-                    text: full_text,
-                    sections: layout_sections,
-                    // wrap: true,
-                    ..Default::default()
-                };
-                ui.label(egui::WidgetText::LayoutJob(layout_job));
-            }
-            Value::Bool(b) => {
-                // let bool_str = b.to_string(); // Immutable copy for search check
-                // let mut rich_key_text = RichText::new(bool_str.clone())
-                //     .strong()
-                //     .color(Color32::KHAKI);
-                // if !search_query.is_empty() && bool_str.to_lowercase().contains(&search_query.to_lowercase())
-                // {
-                //     rich_key_text = rich_key_text.color(Color32::BLACK); // Yellow highlight
-                //     rich_key_text = rich_key_text.background_color(Color32::YELLOW); // Yellow highlight
-                // }
-                // ui.add(egui::Label::new(rich_key_text));
-                // ui.add_space(5.0);
-                let (full_text, layout_sections) = create_highlighted_layout_sections(
-                    ui,
-                    &b.to_string(),
-                    search_query,
-                    Color32::KHAKI,
-                    false,
-                );
-                let layout_job = LayoutJob {
-                    text: full_text,
-                    sections: layout_sections,
-                    // wrap: true,
-                    ..Default::default()
-                };
-                ui.label(egui::WidgetText::LayoutJob(layout_job));
-            }
-            Value::Null => {
-                // let null_str = "null".to_string(); // Immutable copy for search check
-                // let mut rich_key_text = RichText::new(null_str.clone())
-                //     .strong()
-                //     .color(Color32::DARK_GRAY);
-                // if !search_query.is_empty() && null_str.to_lowercase().contains(&search_query.to_lowercase())
-                // {
-                //     rich_key_text = rich_key_text.color(Color32::BLACK); // Yellow highlight
-                //     rich_key_text = rich_key_text.background_color(Color32::YELLOW); // Yellow highlight
-                // }
-                // ui.add(egui::Label::new(rich_key_text));
-                // ui.add_space(5.0);
-                let (full_text, layout_sections) = create_highlighted_layout_sections(
-                    ui,
-                    "null",
-                    search_query,
-                    Color32::DARK_GRAY,
-                    false,
-                );
-                let layout_job = LayoutJob {
-                    text: full_text,
-                    sections: layout_sections,
-                    // wrap: true,
-                    ..Default::default()
-                };
-                ui.label(egui::WidgetText::LayoutJob(layout_job));
-            }
-        }
-    });
-}
-
 // fn main() -> Result<(), eframe::Error> {
 //     // Configure the options for our eframe application window.
 //     // We set the initial window size to be 320x240 logical pixels.
@@ -417,6 +165,47 @@ fn json_highlighter(ui: &egui::Ui, text: &str) -> LayoutJob {
 }
 
 
+// Newline-delimited JSON: one value per non-blank line
+fn parse_ndjson(text: &str) -> Result<Vec<Value>, String> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| {
+            serde_json::from_str(line).map_err(|e| format!("Invalid JSON on line {}: {}", number + 1, e))
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy)]
+enum SchemaSamples {
+    Document,
+    ArrayElements, // Each element of a top-level array is a sample
+    NdjsonLines,
+    AllDocuments,
+}
+
+impl SchemaSamples {
+    const ALL: [SchemaSamples; 4] = [
+        SchemaSamples::Document,
+        SchemaSamples::ArrayElements,
+        SchemaSamples::NdjsonLines,
+        SchemaSamples::AllDocuments,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            SchemaSamples::Document => "This document",
+            SchemaSamples::ArrayElements => "Elements of the top-level array",
+            SchemaSamples::NdjsonLines => "NDJSON lines",
+            SchemaSamples::AllDocuments => "All open documents",
+        }
+    }
+}
+
+fn char_to_byte_index(text: &str, char_index: usize) -> usize {
+    text.char_indices().nth(char_index).map_or(text.len(), |(byte, _)| byte)
+}
+
 fn calculate_desired_rows_from_available_height(ui: &mut Ui, available_height: f32) -> usize {
     // Get the height of a single line of text for the default Body style
     // TextEdit typically uses the Body text style by default.
@@ -434,14 +223,39 @@ fn calculate_desired_rows_from_available_height(ui: &mut Ui, available_height: f
     }
 }
 
+// What was picked from the "Convert" menu, run after the UI pass
+#[derive(Clone, Copy)]
+enum Conversion {
+    Import(DataFormat),
+    Export(DataFormat),
+    OpenBinary(BinaryFormat),
+    DecodePastedBinary(BinaryFormat), // Hex or base64 in the editor
+    ExportBinary(BinaryFormat),
+}
+
 // JQ Execution Function
-fn execute_jq_query(json_input: &str, query: &str) -> Result<String, String> {
-    use std::io::Write;
+fn execute_jq_query(json_input: &str, query: &str, options: &JqOptions) -> Result<String, String> {
+    execute_jq_query_limited(json_input, query, options, &JqLimits::default())
+}
+
+// Runs jq, killing it when the run is cancelled, takes too long or prints
+// too much. Input is written and output read on their own threads, so a jq
+// that stops reading or never finishes can't block the caller.
+fn execute_jq_query_limited(
+    json_input: &str,
+    query: &str,
+    options: &JqOptions,
+    limits: &JqLimits,
+) -> Result<String, String> {
+    use std::io::{Read, Write};
     use std::process::{Command, Stdio}; // Ensure Stdio is in scope
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::{Duration, Instant};
 
    // println!("query, {}!",query);
 
     let mut child = Command::new("jq")
+        .args(options.command_args()?)
         .arg(query)
         // Corrected lines: Use Stdio::piped() instead of Stdio::Piped
         .stdin(Stdio::piped())
@@ -455,38 +269,461 @@ fn execute_jq_query(json_input: &str, query: &str) -> Result<String, String> {
             )
         })?;
 
-    if let Some(mut stdin) = child.stdin.take() {
-        stdin
-            .write_all(json_input.as_bytes())
-            .map_err(|e| format!("Failed to write to jq stdin: {}", e))?;
-    }
-
-    let output = child
-        .wait_with_output()
-        .map_err(|e| format!("Failed to wait for jq process: {}", e))?;
+    let (stdin, mut stdout, mut stderr) = (child.stdin.take(), child.stdout.take(), child.stderr.take());
+    let started = Instant::now();
+    let too_long = AtomicBool::new(false);
+    std::thread::scope(|scope| {
+        // With --null-input jq doesn't read its input, and may exit before it
+        // could be written
+        if let Some(mut stdin) = stdin
+            && !options.null_input
+        {
+            // A failed write means jq exited early; its status says why
+            scope.spawn(move || {
+                let _ = stdin.write_all(json_input.as_bytes());
+            });
+        }
+        let output = scope.spawn(|| {
+            let mut output = Vec::new();
+            let mut buffer = [0; 64 * 1024];
+            while let Some(Ok(read @ 1..)) = stdout.as_mut().map(|stdout| stdout.read(&mut buffer)) {
+                output.extend_from_slice(&buffer[..read]);
+                if limits.max_output_bytes.is_some_and(|max| output.len() > max) {
+                    too_long.store(true, Ordering::Relaxed);
+                    break;
+                }
+            }
+            output
+        });
+        let errors = scope.spawn(|| {
+            let mut errors = Vec::new();
+            if let Some(stderr) = stderr.as_mut() {
+                let _ = stderr.read_to_end(&mut errors);
+            }
+            errors
+        });
 
-    if output.status.success() {
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
-    } else {
-        Err(String::from_utf8_lossy(&output.stderr).to_string())
-    }
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break Ok(status),
+                Ok(None) => {}
+                Err(e) => break Err(format!("Failed to wait for jq process: {}", e)),
+            }
+            let stopped = if limits.cancel.load(Ordering::Relaxed) {
+                Some("The jq run was cancelled.".to_string())
+            } else if too_long.load(Ordering::Relaxed) {
+                Some(format!("jq printed more than {} bytes.", limits.max_output_bytes.unwrap_or_default()))
+            } else if let Some(timeout) = limits.timeout
+                && started.elapsed() > timeout
+            {
+                Some(format!("jq was still running after {} seconds.", timeout.as_secs_f32()))
+            } else {
+                None
+            };
+            if let Some(reason) = stopped {
+                let _ = child.kill();
+                let _ = child.wait();
+                break Err(reason);
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        };
+        let (output, errors) = (output.join().unwrap_or_default(), errors.join().unwrap_or_default());
+        if status?.success() {
+            Ok(String::from_utf8_lossy(&output).to_string())
+        } else {
+            Err(String::from_utf8_lossy(&errors).to_string())
+        }
+    })
 }
 
 // Define our application struct.
 // We use `Default` trait to easily create an instance with default values.
 // #[cfg(feature = "egui_example")]
-#[derive(Default)]
 struct JsonFormatterApp {
-    input_json: String,
-    // Store the parsed JSON Value directly for structured display
-    parsed_json_value: Option<Value>,
-    error_message: Option<String>,
+    // Open documents, one per tab
+    documents: Vec<Document>,
+    active_document: usize,
+    untitled_count: usize, // For naming new documents "Untitled N"
+    confirm_close: Option<u64>, // Id of an edited document waiting to be closed
     search_query: String,
 
-    // New fields for JQ integration:
-    jq_query_input: String,    // The text field for user's JQ query
     cached_layout_job: Option<LayoutJob>,
         last_input_json: String,
+
+    expand_depth: usize,
+    show_schema_window: bool,
+    show_codegen_window: bool,
+    codegen: CodegenPanel,
+    show_diff_window: bool,
+    diff_view: DiffView,
+    show_patch_window: bool,
+    patch_panel: PatchPanel,
+    show_protobuf_window: bool,
+    protobuf_panel: ProtobufPanel,
+    show_query_library_window: bool,
+    query_library: QueryLibraryPanel,
+    show_pipeline_window: bool,
+    pipeline_debugger: PipelineDebugger,
+    show_batch_window: bool,
+    batch_panel: BatchPanel,
+    conversion_report: Option<ConversionReport>,
+}
+
+impl Default for JsonFormatterApp {
+    fn default() -> Self {
+        Self {
+            documents: vec![Document::new("Untitled 1".to_string(), String::new())],
+            active_document: 0,
+            untitled_count: 1,
+            confirm_close: None,
+            search_query: String::new(),
+            cached_layout_job: None,
+            last_input_json: String::new(),
+            expand_depth: 0,
+            show_schema_window: false,
+            show_codegen_window: false,
+            codegen: CodegenPanel::default(),
+            show_diff_window: false,
+            diff_view: DiffView::default(),
+            show_patch_window: false,
+            patch_panel: PatchPanel::default(),
+            show_protobuf_window: false,
+            protobuf_panel: ProtobufPanel::default(),
+            show_query_library_window: false,
+            query_library: QueryLibraryPanel::load(),
+            show_pipeline_window: false,
+            pipeline_debugger: PipelineDebugger::default(),
+            show_batch_window: false,
+            batch_panel: BatchPanel::default(),
+            conversion_report: None,
+        }
+    }
+}
+
+impl JsonFormatterApp {
+    fn new_untitled_document(&mut self) {
+        self.untitled_count += 1;
+        let title = format!("Untitled {}", self.untitled_count);
+        self.open_document(Document::new(title, String::new()));
+    }
+
+    // Adds a document as a new tab and switches to it
+    fn open_document(&mut self, document: Document) {
+        self.documents.push(document);
+        self.active_document = self.documents.len() - 1;
+    }
+
+    fn close_document(&mut self, index: usize) {
+        self.documents.remove(index);
+        if self.documents.is_empty() {
+            self.untitled_count = 0;
+            self.new_untitled_document();
+        } else if self.active_document >= index && self.active_document > 0 {
+            self.active_document -= 1;
+        }
+    }
+
+    // Infers a schema from the chosen samples and opens it as a new document
+    fn generate_schema_document(&mut self, samples: SchemaSamples) {
+        let doc = &self.documents[self.active_document];
+        let values = match samples {
+            SchemaSamples::Document => parse_json_to_value(&doc.input_json).map(|value| vec![value]),
+            SchemaSamples::ArrayElements => match parse_json_to_value(&doc.input_json) {
+                Ok(Value::Array(elements)) => Ok(elements),
+                Ok(_) => Err("The document is not an array.".to_string()),
+                Err(e) => Err(e),
+            },
+            SchemaSamples::NdjsonLines => parse_ndjson(&doc.input_json),
+            SchemaSamples::AllDocuments => self
+                .documents
+                .iter()
+                .filter(|d| !d.input_json.trim().is_empty())
+                .map(|d| parse_json_to_value(&d.input_json).map_err(|e| format!("{}: {}", d.title, e)))
+                .collect(),
+        };
+        let title = format!("Schema of {}", doc.title);
+        match values.and_then(|values| {
+            serde_json::to_string_pretty(&schema::generate_schema(&values)).map_err(|e| e.to_string())
+        }) {
+            Ok(text) => self.open_document(Document::new(title, text)),
+            Err(e) => self.documents[self.active_document].error_message = Some(e),
+        }
+    }
+
+    // Opens converted data as a new document, listing anything lost on the way
+    fn open_converted(&mut self, title: String, format_label: &str, result: Result<(Value, Vec<String>), String>) {
+        let result = result.and_then(|(value, warnings)| {
+            let text = serde_json::to_string_pretty(&value).map_err(|e| e.to_string())?;
+            Ok((text, warnings))
+        });
+        match result {
+            Ok((text, warnings)) => {
+                self.open_document(Document::new(title.clone(), text));
+                if !warnings.is_empty() {
+                    self.conversion_report = Some(ConversionReport {
+                        title: format!("Imported {} from {}", title, format_label),
+                        output: None,
+                        summary: None,
+                        warnings,
+                        save_error: None,
+                    });
+                }
+            }
+            Err(e) => self.documents[self.active_document].error_message = Some(e),
+        }
+    }
+
+    // Converts a YAML/TOML/XML/CSV file to JSON and opens it as a new document
+    fn import_file(&mut self, format: DataFormat) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter(format.label(), format.extensions())
+            .pick_file()
+        else {
+            return;
+        };
+        let title = path.file_name().map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into_owned());
+        let result = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+            .and_then(|text| format.import(&text));
+        self.open_converted(title, format.label(), result);
+    }
+
+    fn open_binary_file(&mut self, format: BinaryFormat) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter(format.label(), format.extensions())
+            .pick_file()
+        else {
+            return;
+        };
+        let title = path.file_name().map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into_owned());
+        let result = std::fs::read(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+            .and_then(|bytes| format.decode(&bytes));
+        self.open_converted(title, format.label(), result);
+    }
+
+    // Decodes hex or base64 pasted into the editor into a new document
+    fn decode_pasted_binary(&mut self, format: BinaryFormat) {
+        let doc = &self.documents[self.active_document];
+        let title = format!("{} from {}", format.label(), doc.title);
+        let result = binary::decode_blob_text(&doc.input_json).and_then(|bytes| format.decode(&bytes));
+        self.open_converted(title, format.label(), result);
+    }
+
+    fn export_document(&mut self, format: DataFormat) {
+        let doc = &mut self.documents[self.active_document];
+        let result = parse_json_to_value(&doc.input_json).and_then(|value| format.export(&value));
+        match result {
+            Ok((text, warnings)) => {
+                self.conversion_report = Some(ConversionReport {
+                    title: format!("{} as {}", doc.title, format.label()),
+                    output: Some((ConvertedOutput::Text(text), format.extensions()[0])),
+                    summary: None,
+                    warnings,
+                    save_error: None,
+                });
+            }
+            Err(e) => doc.error_message = Some(e),
+        }
+    }
+
+    fn export_binary(&mut self, format: BinaryFormat) {
+        let doc = &mut self.documents[self.active_document];
+        let result = parse_json_to_value(&doc.input_json)
+            .and_then(|value| format.encode(&value).map(|encoded| (value, encoded)));
+        match result {
+            Ok((value, (bytes, warnings))) => {
+                self.conversion_report = Some(ConversionReport {
+                    title: format!("{} as {}", doc.title, format.label()),
+                    summary: Some(binary::size_comparison(format.label(), bytes.len(), &value)),
+                    output: Some((ConvertedOutput::Bytes(bytes), format.extensions()[0])),
+                    warnings,
+                    save_error: None,
+                });
+            }
+            Err(e) => doc.error_message = Some(e),
+        }
+    }
+
+    fn show_conversion_window(&mut self, ctx: &egui::Context) {
+        let Some(report) = &mut self.conversion_report else {
+            return;
+        };
+        let mut open = true;
+        egui::Window::new(report.title.clone())
+            .id(egui::Id::new("conversion_window"))
+            .open(&mut open)
+            .default_width(500.0)
+            .default_height(500.0)
+            .show(ctx, |ui| report.show(ui));
+        if !open {
+            self.conversion_report = None;
+        }
+    }
+
+    fn show_document_tabs(&mut self, ctx: &egui::Context) {
+        let mut close = None;
+        egui::TopBottomPanel::top("document_tabs").show(ctx, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                for (index, document) in self.documents.iter().enumerate() {
+                    ui.selectable_value(&mut self.active_document, index, &document.title);
+                    if ui.small_button("x").on_hover_text("Close").clicked() {
+                        close = Some(index);
+                    }
+                    ui.separator();
+                }
+                if ui.button("+").on_hover_text("New document").clicked() {
+                    self.new_untitled_document();
+                }
+            });
+        });
+        if let Some(index) = close {
+            if self.documents[index].is_edited() {
+                self.confirm_close = Some(self.documents[index].id);
+            } else {
+                self.close_document(index);
+            }
+        }
+        if let Some(id) = self.confirm_close {
+            self.show_confirm_close(ctx, id);
+        }
+    }
+
+    fn show_confirm_close(&mut self, ctx: &egui::Context, id: u64) {
+        let Some(index) = self.documents.iter().position(|document| document.id == id) else {
+            self.confirm_close = None;
+            return;
+        };
+        let (mut close, mut cancel) = (false, false);
+        egui::Window::new("Close Document?")
+            .id(egui::Id::new("confirm_close_window"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!("\"{}\" has been edited. Close it and discard the changes?", self.documents[index].title));
+                ui.horizontal(|ui| {
+                    close = ui.button("Discard and Close").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+        if close {
+            self.close_document(index);
+        }
+        if close || cancel {
+            self.confirm_close = None;
+        }
+    }
+
+    fn show_schema_window(&mut self, ctx: &egui::Context) {
+        let document = &mut self.documents[self.active_document];
+        egui::Window::new(format!("JSON Schema - {}", document.title))
+            .id(egui::Id::new("schema_window"))
+            .open(&mut self.show_schema_window)
+            .default_width(450.0)
+            .show(ctx, |ui| document.schema_panel(ui));
+    }
+
+    fn show_codegen_window(&mut self, ctx: &egui::Context) {
+        let document = &self.documents[self.active_document];
+        let codegen = &mut self.codegen;
+        egui::Window::new(format!("Generate Code - {}", document.title))
+            .id(egui::Id::new("codegen_window"))
+            .open(&mut self.show_codegen_window)
+            .default_width(500.0)
+            .default_height(500.0)
+            .show(ctx, |ui| codegen.show(ui, &document.input_json));
+    }
+
+    fn show_diff_window(&mut self, ctx: &egui::Context) {
+        let documents = &self.documents;
+        let diff_view = &mut self.diff_view;
+        diff_view.pick_defaults(documents, self.active_document);
+        egui::Window::new("Compare Documents")
+            .id(egui::Id::new("diff_window"))
+            .open(&mut self.show_diff_window)
+            .default_width(800.0)
+            .default_height(600.0)
+            .show(ctx, |ui| diff_view.show(ui, documents));
+    }
+
+    fn show_patch_window(&mut self, ctx: &egui::Context) {
+        let documents = &mut self.documents;
+        let patch_panel = &mut self.patch_panel;
+        let active = self.active_document;
+        egui::Window::new("Patch")
+            .id(egui::Id::new("patch_window"))
+            .open(&mut self.show_patch_window)
+            .default_width(500.0)
+            .default_height(600.0)
+            .show(ctx, |ui| patch_panel.show(ui, documents, active));
+    }
+
+    fn show_protobuf_window(&mut self, ctx: &egui::Context) {
+        let document = &self.documents[self.active_document];
+        let protobuf_panel = &mut self.protobuf_panel;
+        let output = egui::Window::new("Protobuf")
+            .id(egui::Id::new("protobuf_window"))
+            .open(&mut self.show_protobuf_window)
+            .default_width(450.0)
+            .show(ctx, |ui| protobuf_panel.show(ui, document))
+            .and_then(|response| response.inner.flatten());
+        match output {
+            Some(ProtobufOutput::Decoded(title, value, warnings)) => {
+                self.open_converted(title, "Protobuf", Ok((value, warnings)));
+            }
+            Some(ProtobufOutput::Encoded(report)) => self.conversion_report = Some(report),
+            None => {}
+        }
+    }
+
+    fn show_query_library_window(&mut self, ctx: &egui::Context) {
+        let document = &mut self.documents[self.active_document];
+        let query_library = &mut self.query_library;
+        let picked = egui::Window::new("Query Library")
+            .id(egui::Id::new("query_library_window"))
+            .open(&mut self.show_query_library_window)
+            .default_width(450.0)
+            .default_height(500.0)
+            .show(ctx, |ui| query_library.show(ui, document))
+            .and_then(|response| response.inner.flatten());
+        if let Some(saved) = picked {
+            document.query_language = saved.language;
+            document.jq_query_input = saved.query;
+            document.jq_options = saved.jq_options;
+        }
+    }
+
+    fn show_pipeline_window(&mut self, ctx: &egui::Context) {
+        let document = &mut self.documents[self.active_document];
+        let pipeline_debugger = &mut self.pipeline_debugger;
+        let prefix = egui::Window::new("Pipeline Debugger")
+            .id(egui::Id::new("pipeline_window"))
+            .open(&mut self.show_pipeline_window)
+            .default_width(500.0)
+            .default_height(500.0)
+            .show(ctx, |ui| pipeline_debugger.show(ui, document))
+            .and_then(|response| response.inner.flatten());
+        if let Some(prefix) = prefix {
+            document.jq_query_input = prefix;
+        }
+    }
+
+    fn show_batch_window(&mut self, ctx: &egui::Context) {
+        let documents = &self.documents;
+        let batch_panel = &mut self.batch_panel;
+        let active = self.active_document;
+        let open = egui::Window::new("Batch Query")
+            .id(egui::Id::new("batch_window"))
+            .open(&mut self.show_batch_window)
+            .default_width(500.0)
+            .default_height(500.0)
+            .show(ctx, |ui| batch_panel.show(ui, documents, active))
+            .and_then(|response| response.inner.flatten());
+        if let Some((title, value)) = open {
+            self.open_converted(title, "the batch", Ok((value, Vec::new())));
+        }
+    }
 }
 
 // Implement the `eframe::App` trait for our `MyApp` struct.
@@ -500,6 +737,52 @@ impl eframe::App for JsonFormatterApp {
     // `_frame`: The eframe Frame, used for interacting with the native window (e.g., requesting repaint).
     // `ui`: The egui Ui, which represents the current region where widgets can be added.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.show_document_tabs(ctx);
+        if self.show_schema_window {
+            self.show_schema_window(ctx);
+        }
+        if self.show_codegen_window {
+            self.show_codegen_window(ctx);
+        }
+        if self.show_diff_window {
+            self.show_diff_window(ctx);
+        }
+        if self.show_patch_window {
+            self.show_patch_window(ctx);
+        }
+        if self.show_protobuf_window {
+            self.show_protobuf_window(ctx);
+        }
+        if self.show_query_library_window {
+            self.show_query_library_window(ctx);
+        }
+        if self.show_pipeline_window {
+            self.show_pipeline_window(ctx);
+        }
+        if self.show_batch_window {
+            self.show_batch_window(ctx);
+        }
+        self.show_conversion_window(ctx);
+        let mut convert = None;
+        let doc = &mut self.documents[self.active_document];
+        let mut generate_schema_from = None;
+        let mut open_sql_result = None;
+
+        // Ctrl+Z / Ctrl+Shift+Z undo tree edits, unless a text field has focus
+        // and wants the shortcut for itself.
+        if ctx.memory(|m| m.focused().is_none()) {
+            let (undo, redo) = ctx.input_mut(|i| {
+                let redo = i.consume_key(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::Z);
+                let undo = i.consume_key(egui::Modifiers::COMMAND, egui::Key::Z);
+                (undo, redo)
+            });
+            if redo {
+                doc.redo_tree_edit();
+            } else if undo {
+                doc.undo_tree_edit();
+            }
+            doc.handle_tree_keyboard(ctx);
+        }
         // Create a central panel that takes up all available space.
         // Most egui applications will have a central panel or a top/side panel.
         //
@@ -526,43 +809,116 @@ impl eframe::App for JsonFormatterApp {
                 ui.horizontal(|ui| {
                 ui.label("Enter JSON and click 'Format JSON' to see the collapsible structure.");
                 if ui.button("Format JSON").clicked() {
-                    self.error_message = None; // Clear previous errors
-                    self.parsed_json_value = None; // Clear previous parsed value
+                    doc.error_message = None; // Clear previous errors
+                    doc.parsed_json_value = None; // Clear previous parsed value
+                    doc.tree_editor = None;
+                    doc.tree_is_query_result = false;
+                    doc.query_matches.clear();
 
-                    match parse_json_to_value(&self.input_json) {
+                    match parse_json_to_value(&doc.input_json) {
                         Ok(value) => {
 
-                            // self.parsed_json_value = Some(value);
+                            // doc.parsed_json_value = Some(value);
                             match serde_json::to_string_pretty(&value) {
                                                                 Ok(pretty_json_string) => {
-                                                                    self.input_json = pretty_json_string; // Update the input area
-                                                                    self.parsed_json_value = Some(value); // Keep the parsed value for the collapsible view
+                                                                    doc.input_json = pretty_json_string; // Update the input area
+                                                                    doc.parsed_json_value = Some(value); // Keep the parsed value for the collapsible view
                                                                 }
                                                                 Err(e) => {
-                                                                    self.error_message = Some(format!("Error pretty-printing JSON: {}", e));
+                                                                    doc.error_message = Some(format!("Error pretty-printing JSON: {}", e));
                                                                 }
                                                             }
                         }
                         Err(e) => {
-                            self.error_message = Some(e);
+                            doc.error_message = Some(e);
                         }
                     }
                 }
 
                 // Add the "Copy to Clipboard" button
-                                   if let Some(value) = &self.parsed_json_value {
-                                       if ui.button("Copy Formatted JSON").clicked() {
+                                   if let Some(value) = &doc.parsed_json_value
+                                       && ui.button("Copy Formatted JSON").clicked() {
                                            match serde_json::to_string_pretty(value) {
                                                Ok(pretty_json) => {
                                                    ctx.copy_text(pretty_json);
                                                }
                                                Err(e) => {
-                                                   self.error_message = Some(format!("Error serializing JSON: {}", e));
+                                                   doc.error_message = Some(format!("Error serializing JSON: {}", e));
                                                }
                                            }
+                                   }
+
+                                   ui.menu_button("Schema", |ui| {
+                                       if ui.button("Validate Against Schema…").clicked() {
+                                           self.show_schema_window = true;
+                                           ui.close_menu();
                                        }
+                                       ui.separator();
+                                       ui.label("Generate schema from:");
+                                       for samples in SchemaSamples::ALL {
+                                           if ui.button(samples.label()).clicked() {
+                                               generate_schema_from = Some(samples);
+                                               ui.close_menu();
+                                           }
+                                       }
+                                   });
+                                   if ui.button("Generate Code…").clicked() {
+                                       self.show_codegen_window = true;
+                                   }
+                                   if ui.button("Compare…").clicked() {
+                                       self.show_diff_window = true;
+                                   }
+                                   if ui.button("Patch…").clicked() {
+                                       self.show_patch_window = true;
+                                   }
+                                   ui.menu_button("Convert", |ui| {
+                                       let mut item = |ui: &mut Ui, label: String, conversion| {
+                                           if ui.button(label).clicked() {
+                                               convert = Some(conversion);
+                                               ui.close_menu();
+                                           }
+                                       };
+                                       for format in DataFormat::ALL {
+                                           item(ui, format!("Import {} File…", format.label()), Conversion::Import(format));
+                                       }
+                                       for format in BinaryFormat::ALL {
+                                           item(ui, format!("Open {} File…", format.label()), Conversion::OpenBinary(format));
+                                       }
+                                       ui.separator();
+                                       for format in BinaryFormat::ALL {
+                                           item(
+                                               ui,
+                                               format!("Decode Hex/Base64 Text as {}", format.label()),
+                                               Conversion::DecodePastedBinary(format),
+                                           );
+                                       }
+                                       ui.separator();
+                                       for format in DataFormat::ALL {
+                                           item(ui, format!("Export as {}…", format.label()), Conversion::Export(format));
+                                       }
+                                       for format in BinaryFormat::ALL {
+                                           item(ui, format!("Export as {}…", format.label()), Conversion::ExportBinary(format));
+                                       }
+                                   });
+                                   if ui.button("Protobuf…").clicked() {
+                                       self.show_protobuf_window = true;
+                                   }
+                                   if ui.button("Query Library…").clicked() {
+                                       self.show_query_library_window = true;
+                                   }
+                                   if ui.button("Batch Query…").clicked() {
+                                       self.show_batch_window = true;
+                                   }
+                                   if ui.add_enabled(doc.can_undo(), egui::Button::new("Undo Edit")).clicked() {
+                                       doc.undo_tree_edit();
+                                   }
+                                   if ui.add_enabled(doc.can_redo(), egui::Button::new("Redo Edit")).clicked() {
+                                       doc.redo_tree_edit();
                                    }
                                });
+                if let Some(error) = &doc.error_message {
+                    ui.colored_label(Color32::RED, error);
+                }
                 ui.columns(2, |columns| {
                     // Column 1
                     columns[0].vertical(|ui| {
@@ -580,7 +936,7 @@ impl eframe::App for JsonFormatterApp {
                         let mut layouter = {
                                         // We need to capture `self` (specifically, its mutable parts) here.
                                         // We are creating a closure that can mutate `self.cached_layout_job`
-                                        // and `self.last_input_json`, and read `self.input_json`.
+                                        // and `self.last_input_json`, and read `doc.input_json`.
                                         // The `move` keyword ensures that `layouter` takes ownership of these values,
                                         // but since we're passing `&mut self` into `update`, we can't `move self` directly.
                                         // Instead, we capture mutable references to the fields needed by the layouter.
@@ -592,7 +948,7 @@ impl eframe::App for JsonFormatterApp {
                                         // The TextEdit::layouter expects FnMut, so the layouter can modify its captured state.
 
                                         // The fix is to make sure the parts of self that layouter needs
-                                        // are distinct from the parts TextEdit needs for its value (&mut self.input_json).
+                                        // are distinct from the parts TextEdit needs for its value (&mut doc.input_json).
                                         //
                                         // This pattern is tricky because TextEdit wants a FnMut.
                                         // A common pattern is to make the layouter a method on `self` or a function
@@ -601,7 +957,7 @@ impl eframe::App for JsonFormatterApp {
                                         // Let's create a *helper function* for the layouter logic
                                         // to avoid complex lifetime issues with nested closures capturing self.
                                         // This function will take the mutable references it needs from `self`.
-                                        // let input_json_ref = &self.input_json; // Immutable borrow for layouter to read
+                                        // let input_json_ref = &doc.input_json; // Immutable borrow for layouter to read
                                         let last_input_json_ref = &mut self.last_input_json; // Mutable borrow for layouter to update
                                         let cached_layout_job_ref = &mut self.cached_layout_job; // Mutable borrow for layouter to update
 
@@ -618,29 +974,63 @@ impl eframe::App for JsonFormatterApp {
                                         }
                                     };
 
+                        // Apply a selection requested by clicking a node in the tree
+                        let editor_id = egui::Id::new(("input_json_editor", doc.id));
+                        let mut scroll_to_selection = false;
+                        if let Some(range) = doc.pending_editor_selection.take() {
+                            let mut state = egui::text_edit::TextEditState::load(ui.ctx(), editor_id).unwrap_or_default();
+                            let start = doc.input_json[..range.start].chars().count();
+                            let end = start + doc.input_json[range].chars().count();
+                            state.cursor.set_char_range(Some(egui::text::CCursorRange::two(
+                                egui::text::CCursor::new(start),
+                                egui::text::CCursor::new(end),
+                            )));
+                            state.store(ui.ctx(), editor_id);
+                            ui.memory_mut(|m| m.request_focus(editor_id));
+                            doc.note_editor_cursor(end);
+                            scroll_to_selection = true;
+                        }
+
+                        let mut editor_cursor = None;
                         egui::ScrollArea::vertical().id_salt("raw_json_scroll_area_v").show(ui, |ui| {
 
                             egui::ScrollArea::horizontal().id_salt("raw_json_scroll_area_h").show(ui, |horizontal_ui| {
 
 
 
-                        horizontal_ui.add(
-                            // egui::TextEdit::multiline(&mut self.input_json)
+                        let output =
+                            // egui::TextEdit::multiline(&mut doc.input_json)
                             //     .desired_width(f32::INFINITY)
                             //     // .desired_rows(100) // Example: set initial rows for height
                             //     .background_color(Color32::TRANSPARENT)
                             //     .frame(true)
                             //     .desired_rows(calculated_rows.max(50)),
-                            egui::TextEdit::multiline(&mut self.input_json)
+                            egui::TextEdit::multiline(&mut doc.input_json)
+                                                    .id(editor_id)
                                                     .desired_width(f32::INFINITY)
                                                     .background_color(Color32::from_rgb(40,40,40))
                                                     .frame(true)
                                                     .desired_rows(calculated_rows.max(50))
                                                     .layouter(&mut layouter)
-
-                        );
+                                                    .show(horizontal_ui);
+
+                        if let Some(cursor_range) = output.cursor_range {
+                            if scroll_to_selection {
+                                let cursor_rect = output.galley.pos_from_cursor(&cursor_range.primary);
+                                horizontal_ui.scroll_to_rect(
+                                    cursor_rect.translate(output.galley_pos.to_vec2()),
+                                    Some(egui::Align::Center),
+                                );
+                            }
+                            if output.response.has_focus() {
+                                editor_cursor = Some(cursor_range.primary.ccursor.index);
+                            }
+                        }
                             });
                         });
+                        if let Some(cursor_char_index) = editor_cursor {
+                            doc.sync_tree_to_editor_cursor(cursor_char_index);
+                        }
 
                         ui.add_space(ui.available_height());
                     });
@@ -661,83 +1051,183 @@ impl eframe::App for JsonFormatterApp {
                         // JQ Section
                                             // ui.heading("JQ Query");
                                             ui.horizontal(|ui| {
-                                                ui.label("JQ Filter:");
-                                                ui.text_edit_singleline(&mut self.jq_query_input);
-                                                    // .hint_text(".data[0].event_dates_id");
+                                                egui::ComboBox::from_id_salt("query_language")
+                                                    .selected_text(doc.query_language.label())
+                                                    .width(80.0)
+                                                    .show_ui(ui, |ui| {
+                                                        for language in QueryLanguage::ALL {
+                                                            ui.selectable_value(&mut doc.query_language, language, language.label());
+                                                        }
+                                                    });
+                                                doc.query_editor.show(
+                                                    ui,
+                                                    egui::Id::new(("query_editor", doc.id)),
+                                                    &mut doc.jq_query_input,
+                                                    doc.query_language,
+                                                    &doc.input_json,
+                                                );
                                                 if ui.button("Run").clicked() {
                                                     // self.jq_output = None;
                                                     // self.jq_error = None;
-
-                                                    if self.input_json.is_empty() {
-                                                        self.error_message = Some("No JSON input provided to run JQ against.".to_string());
-                                                    } else if self.jq_query_input.is_empty() {
-                                                        self.error_message = Some("JQ query field cannot be empty.".to_string());
+                                                    doc.error_message = None;
+                                                    doc.query_note = None;
+
+                                                    if doc.query_language != QueryLanguage::Jq {
+                                                        doc.run_native_query();
+                                                    } else if doc.input_json.is_empty() && !doc.jq_options.null_input {
+                                                        doc.error_message = Some("No JSON input provided to run JQ against.".to_string());
+                                                    } else if doc.jq_query_input.is_empty() {
+                                                        doc.error_message = Some("JQ query field cannot be empty.".to_string());
                                                     } else {
-                                                        match execute_jq_query(&self.input_json, &self.jq_query_input) {
+                                                        match execute_jq_query(&doc.input_json, &doc.jq_query_input, &doc.jq_options) {
                                                             Ok(output) => {
                                                                 // self.jq_output = Some(output);
                                                                 // println!("output {}",output);
 
-                                                                match parse_json_to_value(&output) {
-                                                                                        Ok(value) => {
+                                                                match doc.jq_options.parse_output(&output) {
+                                                                                        Ok((value, count)) => {
+                                                                                            if count > 1 {
+                                                                                                doc.query_note = Some(format!("{} outputs collected into an array", count));
+                                                                                            }
 
-                                                                                            // self.parsed_json_value = Some(value);
+                                                                                            // doc.parsed_json_value = Some(value);
                                                                                             match serde_json::to_string_pretty(&value) {
                                                                                                 Ok(_pretty_json_string) => {
-                                                                                                // self.input_json = pretty_json_string; // Update the input area
-                                                                                                self.parsed_json_value = Some(value); // Keep the parsed value for the collapsible view
+                                                                                                // doc.input_json = pretty_json_string; // Update the input area
+                                                                                                doc.parsed_json_value = Some(value); // Keep the parsed value for the collapsible view
+                                                                                                doc.tree_editor = None;
+                                                                                                doc.tree_is_query_result = true;
+                                                                                                doc.query_matches.clear();
                                                                                                 }
                                                                                                 Err(e) => {
-                                                                                                self.error_message = Some(format!("Error pretty-printing JSON: {}", e));
+                                                                                                doc.error_message = Some(format!("Error pretty-printing JSON: {}", e));
                                                                                                 }
                                                                                             }
                                                                                         }
                                                                                         Err(e) => {
-                                                                                            self.error_message = Some(e);
+                                                                                            doc.error_message = Some(e);
                                                                                         }
                                                                                     }
 
                                                             }
                                                             Err(e) => {
                                                                 // self.jq_error = Some(e);
-                                                                 self.error_message = Some(format!("Error : {}", e));
+                                                                 doc.error_message = Some(format!("Error : {}", e));
                                                             }
                                                         }
                                                     }
+                                                    doc.record_query_run();
                                                 }
                                                 if ui.button("Clear").clicked() {
-                                                    self.jq_query_input.clear();
-                                                    match parse_json_to_value(&self.input_json) {
+                                                    doc.jq_query_input.clear();
+                                                    doc.query_matches.clear();
+                                                    doc.tree_editor = None;
+                                                    doc.tree_is_query_result = false;
+                                                    match parse_json_to_value(&doc.input_json) {
                                                                             Ok(value) => {
-                                                                                // self.parsed_json_value = Some(value);
+                                                                                // doc.parsed_json_value = Some(value);
                                                                                 match serde_json::to_string_pretty(&value) {
                                                                                     Ok(_pretty_json_string) => {
-                                                                                        // self.input_json = pretty_json_string; // Update the input area
-                                                                                        self.parsed_json_value = Some(value); // Keep the parsed value for the collapsible view
+                                                                                        // doc.input_json = pretty_json_string; // Update the input area
+                                                                                        doc.parsed_json_value = Some(value); // Keep the parsed value for the collapsible view
                                                                                     }
                                                                                     Err(e) => {
-                                                                                        self.error_message = Some(format!("Error pretty-printing JSON: {}", e));
+                                                                                        doc.error_message = Some(format!("Error pretty-printing JSON: {}", e));
                                                                                     }
                                                                                 }
                                                                             }
                                                                             Err(e) => {
-                                                                                self.error_message = Some(e);
+                                                                                doc.error_message = Some(e);
                                                                             }
                                                                         }
                                                     // self.jq_output = None;
                                                     // self.jq_error = None;
                                                 }
+                                                ui.add_enabled_ui(doc.query_language == QueryLanguage::Jq, |ui| {
+                                                    let label = match doc.jq_options.active_count() {
+                                                        0 => "Options".to_string(),
+                                                        count => format!("Options ({})", count),
+                                                    };
+                                                    ui.menu_button(label, |ui| doc.jq_options.show(ui));
+                                                });
+                                                doc.query_history_menu(ui);
+                                                if ui
+                                                    .button("Debug")
+                                                    .on_hover_text("Step through the jq filter's pipeline stages")
+                                                    .clicked()
+                                                {
+                                                    self.show_pipeline_window = true;
+                                                }
+                                                ui.checkbox(&mut doc.live_preview.enabled, "Live")
+                                                    .on_hover_text("Show the query's result next to the tree as you type");
                                             });
+                                            doc.live_preview.update(
+                                                ui.ctx(),
+                                                doc.query_language,
+                                                &doc.jq_query_input,
+                                                &doc.jq_options,
+                                                &doc.input_json,
+                                            );
+                                            if doc.live_preview.enabled {
+                                                egui::SidePanel::right(egui::Id::new(("live_preview", doc.id)))
+                                                    .resizable(true)
+                                                    .default_width(ui.available_width() / 2.0)
+                                                    .show_inside(ui, |ui| doc.live_preview.show(ui));
+                                            }
                                              // Render the parsed JSON value if available
-                                             if let Some(value) = &self.parsed_json_value {
-                                                 egui::ScrollArea::vertical().id_salt("formatted_json_scroll_area_v").show(ui, |ui| {
+                                             let mut tree_actions = Vec::new();
+                                             ui.horizontal(|ui| {
+                                                 if ui.button("Expand All").clicked() {
+                                                     doc.expansion.expand_all();
+                                                 }
+                                                 if ui.button("Collapse All").clicked() {
+                                                     doc.expansion.collapse_all();
+                                                 }
+                                                 if ui.button("Expand to Depth").clicked() {
+                                                     doc.expansion.expand_to_depth(self.expand_depth);
+                                                 }
+                                                 ui.add(egui::DragValue::new(&mut self.expand_depth).range(0..=64));
+                                             });
+                                             doc.query_matches_panel(ui);
+                                             if doc.tree_is_query_result
+                                                 && let Some(note) = &doc.query_note
+                                             {
+                                                 ui.weak(note);
+                                             }
+                                             if let Some(rows) = doc.sql_tables_panel(ui) {
+                                                 open_sql_result = Some((format!("SQL result from {}", doc.title), rows));
+                                             }
+                                             if let Some(path) = &doc.selected_path {
+                                                 render_breadcrumbs(ui, path, &mut tree_actions);
+                                             }
+                                             doc.drop_stale_schema_errors();
+                                             let tree_marks = doc.tree_marks();
+                                             if let Some(value) = &doc.parsed_json_value {
+                                                 let mut tree_ctx = TreeContext {
+                                                     search_query: &self.search_query,
+                                                     editable: !doc.tree_is_query_result,
+                                                     editor: &mut doc.tree_editor,
+                                                     expansion: &doc.expansion,
+                                                     tables: &mut doc.table_views,
+                                                     marks: &tree_marks,
+                                                     selected: doc.selected_path.as_ref(),
+                                                     reveal_selected: doc.reveal_selected,
+                                                     actions: &mut tree_actions,
+                                                 };
+                                                 let scroll = egui::ScrollArea::vertical().id_salt("formatted_json_scroll_area_v").show(ui, |ui| {
                                                       egui::ScrollArea::horizontal().id_salt("formatted_json_scroll_area_h").show(ui, |horizontal_ui| {
-                                                     render_json_value(horizontal_ui, None, value, "$",&self.search_query);
+                                                     render_json_value(horizontal_ui, None, value, &JsonPath::root(), &mut tree_ctx);
                                                       });
                                                  });
+                                                 doc.tree_rect = Some(scroll.inner_rect);
                                              } else {
+                                                 doc.tree_rect = None;
                                                  // ui.label("Enter JSON and click 'Format JSON' to see the collapsible structure.");
                                              }
+                                             doc.reveal_selected = false;
+                                             for action in tree_actions {
+                                                 doc.apply_tree_action(action);
+                                             }
                                              ui.label("JQ query must return valid JSON.");
                                              ui.separator(); // Visual separator
 
@@ -773,6 +1263,21 @@ impl eframe::App for JsonFormatterApp {
                 });
                 ui.add_space(ui.available_height());
             });
+
+        if let Some(samples) = generate_schema_from {
+            self.generate_schema_document(samples);
+        }
+        if let Some((title, rows)) = open_sql_result {
+            self.open_converted(title, "SQL", Ok((rows, Vec::new())));
+        }
+        match convert {
+            Some(Conversion::Import(format)) => self.import_file(format),
+            Some(Conversion::Export(format)) => self.export_document(format),
+            Some(Conversion::OpenBinary(format)) => self.open_binary_file(format),
+            Some(Conversion::DecodePastedBinary(format)) => self.decode_pasted_binary(format),
+            Some(Conversion::ExportBinary(format)) => self.export_binary(format),
+            None => {}
+        }
     }
 }
 
@@ -0,0 +1,399 @@
+// JSON Patch (RFC 6902) and JSON Merge Patch (RFC 7396): generating one from
+// two documents and applying one to a document. JSON Patch operations are
+// applied one by one and each reports its own outcome, so a failed `test` or
+// a missing path can be pointed at.
+use serde_json::{Map, Value, json};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PatchFormat {
+    JsonPatch,
+    MergePatch,
+}
+
+impl PatchFormat {
+    pub const ALL: [PatchFormat; 2] = [PatchFormat::JsonPatch, PatchFormat::MergePatch];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PatchFormat::JsonPatch => "JSON Patch (RFC 6902)",
+            PatchFormat::MergePatch => "Merge Patch (RFC 7396)",
+        }
+    }
+
+    // JSON Patches are arrays of operations; anything else is a merge patch
+    pub fn detect(patch: &Value) -> PatchFormat {
+        if patch.is_array() { PatchFormat::JsonPatch } else { PatchFormat::MergePatch }
+    }
+}
+
+// The operations that turn `from` into `to`. Arrays are compared by
+// position: changed elements are patched in place, extra ones removed from
+// the end backwards and new ones appended.
+pub fn generate_json_patch(from: &Value, to: &Value) -> Value {
+    let mut ops = Vec::new();
+    diff_ops(from, to, "", &mut ops);
+    Value::Array(ops)
+}
+
+fn diff_ops(from: &Value, to: &Value, pointer: &str, ops: &mut Vec<Value>) {
+    match (from, to) {
+        (Value::Object(f), Value::Object(t)) => {
+            for (key, f_child) in f {
+                let child = format!("{}/{}", pointer, escape_token(key));
+                match t.get(key) {
+                    Some(t_child) => diff_ops(f_child, t_child, &child, ops),
+                    None => ops.push(json!({ "op": "remove", "path": child })),
+                }
+            }
+            for (key, t_child) in t.iter().filter(|(key, _)| !f.contains_key(*key)) {
+                let child = format!("{}/{}", pointer, escape_token(key));
+                ops.push(json!({ "op": "add", "path": child, "value": t_child }));
+            }
+        }
+        (Value::Array(f), Value::Array(t)) => {
+            let common = f.len().min(t.len());
+            for index in 0..common {
+                diff_ops(&f[index], &t[index], &format!("{}/{}", pointer, index), ops);
+            }
+            for index in (common..f.len()).rev() {
+                ops.push(json!({ "op": "remove", "path": format!("{}/{}", pointer, index) }));
+            }
+            for (index, t_child) in t.iter().enumerate().skip(common) {
+                ops.push(json!({ "op": "add", "path": format!("{}/{}", pointer, index), "value": t_child }));
+            }
+        }
+        _ if values_equal(from, to) => {}
+        _ => ops.push(json!({ "op": "replace", "path": pointer, "value": to })),
+    }
+}
+
+// The merge patch that turns `from` into `to`. Merge patches can't set a
+// member to `null` (null means "delete") or change part of an array, so the
+// result isn't always exact; `apply_merge_patch` can be used to check.
+pub fn generate_merge_patch(from: &Value, to: &Value) -> Value {
+    match (from, to) {
+        (Value::Object(f), Value::Object(t)) => {
+            let mut patch = Map::new();
+            for key in f.keys().filter(|key| !t.contains_key(*key)) {
+                patch.insert(key.clone(), Value::Null);
+            }
+            for (key, t_child) in t {
+                match f.get(key) {
+                    Some(f_child) if values_equal(f_child, t_child) => {}
+                    Some(f_child @ Value::Object(_)) if t_child.is_object() => {
+                        patch.insert(key.clone(), generate_merge_patch(f_child, t_child));
+                    }
+                    _ => {
+                        patch.insert(key.clone(), t_child.clone());
+                    }
+                }
+            }
+            Value::Object(patch)
+        }
+        _ => to.clone(),
+    }
+}
+
+pub fn apply_merge_patch(target: &Value, patch: &Value) -> Value {
+    let Value::Object(patch) = patch else {
+        return patch.clone();
+    };
+    let mut result = match target {
+        Value::Object(map) => map.clone(),
+        _ => Map::new(),
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            result.remove(key);
+        } else {
+            let merged = apply_merge_patch(result.get(key).unwrap_or(&Value::Null), value);
+            result.insert(key.clone(), merged);
+        }
+    }
+    Value::Object(result)
+}
+
+// How one JSON Patch operation went
+pub struct OpOutcome {
+    // e.g. `replace /a/b`
+    pub summary: String,
+    pub error: Option<String>,
+}
+
+// Applies the operations in order to a copy of `target`. A failed operation
+// is reported and skipped; the patch as a whole only succeeded when every
+// outcome is free of errors.
+pub fn apply_json_patch(target: &Value, patch: &Value) -> Result<(Value, Vec<OpOutcome>), String> {
+    let ops = patch
+        .as_array()
+        .ok_or("A JSON Patch must be an array of operations.")?;
+    let mut document = target.clone();
+    let outcomes = ops
+        .iter()
+        .enumerate()
+        .map(|(index, op)| {
+            let summary = format!(
+                "#{} {} {}",
+                index + 1,
+                op.get("op").and_then(Value::as_str).unwrap_or("?"),
+                op.get("path").and_then(Value::as_str).unwrap_or("?")
+            );
+            OpOutcome {
+                summary,
+                error: apply_op(&mut document, op).err(),
+            }
+        })
+        .collect();
+    Ok((document, outcomes))
+}
+
+fn apply_op(document: &mut Value, op: &Value) -> Result<(), String> {
+    let member = |name: &str| op.get(name).ok_or_else(|| format!("missing \"{}\"", name));
+    let pointer = |name: &str| -> Result<&str, String> {
+        member(name)?
+            .as_str()
+            .ok_or_else(|| format!("\"{}\" must be a string", name))
+    };
+    let path = pointer("path")?;
+    match op.get("op").and_then(Value::as_str) {
+        Some("add") => add(document, path, member("value")?.clone()),
+        Some("remove") => remove(document, path).map(drop),
+        Some("replace") => {
+            let node = get_mut(document, path)?;
+            *node = member("value")?.clone();
+            Ok(())
+        }
+        Some("move") => {
+            let from = pointer("from")?;
+            if path != from && path.starts_with(from) && path[from.len()..].starts_with('/') {
+                return Err(format!("can't move {} into itself", from));
+            }
+            let value = remove(document, from)?;
+            add(document, path, value)
+        }
+        Some("copy") => {
+            let value = get_mut(document, pointer("from")?)?.clone();
+            add(document, path, value)
+        }
+        Some("test") => {
+            let expected = member("value")?;
+            let actual = get_mut(document, path)?;
+            if values_equal(actual, expected) {
+                Ok(())
+            } else {
+                Err(format!("test failed: value is {}, expected {}", actual, expected))
+            }
+        }
+        Some(other) => Err(format!("unknown operation \"{}\"", other)),
+        None => Err("missing \"op\"".to_string()),
+    }
+}
+
+fn parse_pointer(pointer: &str) -> Result<Vec<String>, String> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    let rest = pointer
+        .strip_prefix('/')
+        .ok_or_else(|| format!("invalid JSON Pointer \"{}\"", pointer))?;
+    Ok(rest
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+fn escape_token(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn array_index(token: &str, len: usize, pointer: &str) -> Result<usize, String> {
+    match token.parse::<usize>() {
+        Ok(index) if index < len && (token == "0" || !token.starts_with('0')) => Ok(index),
+        _ => Err(format!("path {} does not exist", pointer)),
+    }
+}
+
+fn get_mut<'a>(document: &'a mut Value, pointer: &str) -> Result<&'a mut Value, String> {
+    let mut node = document;
+    for token in parse_pointer(pointer)? {
+        node = match node {
+            Value::Object(map) => map.get_mut(&token),
+            Value::Array(arr) => {
+                let index = array_index(&token, arr.len(), pointer)?;
+                arr.get_mut(index)
+            }
+            _ => None,
+        }
+        .ok_or_else(|| format!("path {} does not exist", pointer))?;
+    }
+    Ok(node)
+}
+
+// The parent container of `pointer` and the last token; `None` for the root
+fn parent_mut<'a>(document: &'a mut Value, pointer: &str) -> Result<Option<(&'a mut Value, String)>, String> {
+    let mut tokens = parse_pointer(pointer)?;
+    let Some(last) = tokens.pop() else {
+        return Ok(None);
+    };
+    let parent_pointer: String = tokens.iter().map(|t| format!("/{}", escape_token(t))).collect();
+    let parent = get_mut(document, &parent_pointer)
+        .map_err(|_| format!("parent of {} does not exist", pointer))?;
+    Ok(Some((parent, last)))
+}
+
+fn add(document: &mut Value, pointer: &str, value: Value) -> Result<(), String> {
+    let Some((parent, last)) = parent_mut(document, pointer)? else {
+        *document = value;
+        return Ok(());
+    };
+    match parent {
+        Value::Object(map) => {
+            map.insert(last, value);
+            Ok(())
+        }
+        Value::Array(arr) if last == "-" => {
+            arr.push(value);
+            Ok(())
+        }
+        Value::Array(arr) => {
+            // Inserting right after the last element is allowed
+            let index = array_index(&last, arr.len() + 1, pointer)?;
+            arr.insert(index, value);
+            Ok(())
+        }
+        _ => Err(format!("parent of {} is not an object or array", pointer)),
+    }
+}
+
+fn remove(document: &mut Value, pointer: &str) -> Result<Value, String> {
+    let Some((parent, last)) = parent_mut(document, pointer)? else {
+        return Err("can't remove the whole document".to_string());
+    };
+    match parent {
+        Value::Object(map) => map
+            .remove(&last)
+            .ok_or_else(|| format!("path {} does not exist", pointer)),
+        Value::Array(arr) => {
+            let index = array_index(&last, arr.len(), pointer)?;
+            Ok(arr.remove(index))
+        }
+        _ => Err(format!("path {} does not exist", pointer)),
+    }
+}
+
+// JSON equality, where numbers compare by value (`1` equals `1.0`)
+fn values_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x == y || x.as_f64() == y.as_f64(),
+        (Value::Array(x), Value::Array(y)) => x.len() == y.len() && x.iter().zip(y).all(|(x, y)| values_equal(x, y)),
+        (Value::Object(x), Value::Object(y)) => {
+            x.len() == y.len() && x.iter().all(|(key, x)| y.get(key).is_some_and(|y| values_equal(x, y)))
+        }
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The document after applying `patch`, or the first operation's error
+    fn apply(target: Value, patch: Value) -> Result<Value, String> {
+        let (document, outcomes) = apply_json_patch(&target, &patch)?;
+        match outcomes.into_iter().find_map(|outcome| outcome.error) {
+            Some(e) => Err(e),
+            None => Ok(document),
+        }
+    }
+
+    #[test]
+    fn applies_the_rfc_6902_operations() {
+        let target = json!({ "a": { "b": 1 }, "list": [1, 2] });
+        let patch = json!([
+            { "op": "add", "path": "/a/c", "value": 2 },
+            { "op": "remove", "path": "/a/b" },
+            { "op": "replace", "path": "/list/0", "value": 0 },
+            { "op": "copy", "from": "/a", "path": "/copy" },
+            { "op": "move", "from": "/copy/c", "path": "/moved" },
+        ]);
+        assert_eq!(
+            apply(target, patch),
+            Ok(json!({ "a": { "c": 2 }, "list": [0, 2], "copy": {}, "moved": 2 }))
+        );
+    }
+
+    #[test]
+    fn dash_appends_to_an_array() {
+        let patch = json!([{ "op": "add", "path": "/list/-", "value": 3 }]);
+        assert_eq!(apply(json!({ "list": [1, 2] }), patch), Ok(json!({ "list": [1, 2, 3] })));
+        // `-` only means "after the end" when adding
+        let patch = json!([{ "op": "replace", "path": "/list/-", "value": 3 }]);
+        assert!(apply(json!({ "list": [1, 2] }), patch).is_err());
+        // Inserting right after the last element is allowed, but not past it
+        assert!(apply(json!([1]), json!([{ "op": "add", "path": "/1", "value": 2 }])).is_ok());
+        assert!(apply(json!([1]), json!([{ "op": "add", "path": "/2", "value": 2 }])).is_err());
+        assert!(apply(json!([1, 2]), json!([{ "op": "remove", "path": "/01" }])).is_err());
+    }
+
+    #[test]
+    fn cannot_move_a_value_into_its_own_child() {
+        let target = json!({ "a": { "b": 1 }, "ab": 0 });
+        let patch = json!([{ "op": "move", "from": "/a", "path": "/a/b/c" }]);
+        assert!(apply(target.clone(), patch).is_err());
+        // A sibling whose name starts the same is not a child
+        let patch = json!([{ "op": "move", "from": "/a", "path": "/ab" }]);
+        assert_eq!(apply(target, patch), Ok(json!({ "ab": { "b": 1 } })));
+    }
+
+    #[test]
+    fn test_compares_numbers_by_value() {
+        let target = json!({ "n": 1, "list": [{ "x": 1.0 }] });
+        assert!(apply(target.clone(), json!([{ "op": "test", "path": "/n", "value": 1.0 }])).is_ok());
+        assert!(apply(target.clone(), json!([{ "op": "test", "path": "/list", "value": [{ "x": 1 }] }])).is_ok());
+        assert!(apply(target.clone(), json!([{ "op": "test", "path": "/n", "value": "1" }])).is_err());
+        assert!(apply(target, json!([{ "op": "test", "path": "/missing", "value": null }])).is_err());
+    }
+
+    #[test]
+    fn escapes_keys_in_pointers() {
+        let from = json!({ "a/b": 1, "m~n": [1], "": { "~1": true } });
+        let to = json!({ "a/b": 2, "m~n": [], "": { "~1": false } });
+        let patch = generate_json_patch(&from, &to);
+        assert!(patch.as_array().unwrap().iter().any(|op| op["path"] == "/a~1b"));
+        assert!(patch.as_array().unwrap().iter().any(|op| op["path"] == "//~01"));
+        assert_eq!(apply(from, patch), Ok(to));
+    }
+
+    #[test]
+    fn generated_patches_reproduce_the_target() {
+        let from = json!({ "keep": 1, "drop": [1, 2, 3], "nested": { "x": [1, 2], "y": null } });
+        let to = json!({ "keep": 1, "drop": [1], "nested": { "x": [1, 2, 3], "z": "new" }, "added": {} });
+        assert_eq!(apply(from.clone(), generate_json_patch(&from, &to)), Ok(to.clone()));
+        assert_eq!(apply_merge_patch(&from, &generate_merge_patch(&from, &to)), to);
+    }
+
+    #[test]
+    fn applies_merge_patches() {
+        // The example from RFC 7396
+        let target = json!({
+            "title": "Goodbye!",
+            "author": { "givenName": "John", "familyName": "Doe" },
+            "tags": ["example", "sample"],
+            "content": "This will be unchanged"
+        });
+        let patch = json!({
+            "title": "Hello!",
+            "phoneNumber": "+01-123-456-7890",
+            "author": { "familyName": null },
+            "tags": ["example"]
+        });
+        let expected = json!({
+            "title": "Hello!",
+            "author": { "givenName": "John" },
+            "tags": ["example"],
+            "content": "This will be unchanged",
+            "phoneNumber": "+01-123-456-7890"
+        });
+        assert_eq!(apply_merge_patch(&target, &patch), expected);
+    }
+}
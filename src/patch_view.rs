@@ -0,0 +1,217 @@
+// The "Patch" window: generate a JSON Patch or Merge Patch from two open
+// documents, and apply a patch to the active document with a live preview
+// and per-operation results before the document is changed.
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use eframe::egui;
+use egui::Color32;
+
+use crate::document::Document;
+use crate::parse_json_to_value;
+use crate::patch::{
+    OpOutcome, PatchFormat, apply_json_patch, apply_merge_patch, generate_json_patch, generate_merge_patch,
+};
+
+struct Preview {
+    format: PatchFormat,
+    outcomes: Vec<OpOutcome>,
+    result: String, // Pretty-printed patched document
+}
+
+impl Preview {
+    fn succeeded(&self) -> bool {
+        self.outcomes.iter().all(|outcome| outcome.error.is_none())
+    }
+}
+
+pub struct PatchPanel {
+    from_document: Option<u64>,
+    to_document: Option<u64>,
+    generate_format: PatchFormat,
+    patch_text: String,
+    status: Option<(Color32, String)>,
+    preview: Result<Option<Preview>, String>,
+    previewed: Option<u64>, // Hash of the inputs behind `preview`
+}
+
+impl Default for PatchPanel {
+    fn default() -> Self {
+        PatchPanel {
+            from_document: None,
+            to_document: None,
+            generate_format: PatchFormat::JsonPatch,
+            patch_text: String::new(),
+            status: None,
+            preview: Ok(None),
+            previewed: None,
+        }
+    }
+}
+
+impl PatchPanel {
+    fn generate(&mut self, documents: &[Document]) {
+        let find = |id: Option<u64>| documents.iter().find(|d| Some(d.id) == id);
+        let (Some(from), Some(to)) = (find(self.from_document), find(self.to_document)) else {
+            self.status = Some((Color32::RED, "Pick two documents.".to_string()));
+            return;
+        };
+        let parse = |document: &Document| {
+            parse_json_to_value(&document.input_json).map_err(|e| format!("{}: {}", document.title, e))
+        };
+        let (from, to) = match parse(from).and_then(|f| Ok((f, parse(to)?))) {
+            Ok(values) => values,
+            Err(e) => {
+                self.status = Some((Color32::RED, e));
+                return;
+            }
+        };
+        let patch = match self.generate_format {
+            PatchFormat::JsonPatch => generate_json_patch(&from, &to),
+            PatchFormat::MergePatch => generate_merge_patch(&from, &to),
+        };
+        self.status = match self.generate_format {
+            PatchFormat::MergePatch if apply_merge_patch(&from, &patch) != to => Some((
+                Color32::YELLOW,
+                "This merge patch doesn't reproduce the target exactly: merge patches can't set values to null."
+                    .to_string(),
+            )),
+            _ => None,
+        };
+        self.patch_text = serde_json::to_string_pretty(&patch).unwrap_or_default();
+    }
+
+    // Re-applies the patch whenever it or the document changes
+    fn refresh_preview(&mut self, document: &Document) {
+        let mut hasher = DefaultHasher::new();
+        (&self.patch_text, &document.input_json).hash(&mut hasher);
+        let key = hasher.finish();
+        if self.previewed == Some(key) {
+            return;
+        }
+        self.previewed = Some(key);
+        if self.patch_text.trim().is_empty() {
+            self.preview = Ok(None);
+            return;
+        }
+        self.preview = (|| {
+            let patch = parse_json_to_value(&self.patch_text).map_err(|e| format!("Patch: {}", e))?;
+            let target = parse_json_to_value(&document.input_json).map_err(|e| format!("{}: {}", document.title, e))?;
+            let format = PatchFormat::detect(&patch);
+            let (result, outcomes) = match format {
+                PatchFormat::JsonPatch => apply_json_patch(&target, &patch)?,
+                PatchFormat::MergePatch => (apply_merge_patch(&target, &patch), Vec::new()),
+            };
+            let result = serde_json::to_string_pretty(&result).map_err(|e| e.to_string())?;
+            Ok(Some(Preview {
+                format,
+                outcomes,
+                result,
+            }))
+        })();
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, documents: &mut [Document], active: usize) {
+        if self.from_document.is_none() {
+            self.from_document = documents.get(active).map(|d| d.id);
+            self.to_document = documents.get((active + 1) % documents.len()).map(|d| d.id);
+        }
+
+        ui.heading("Generate");
+        ui.horizontal(|ui| {
+            for (label, selection) in [("From:", &mut self.from_document), ("To:", &mut self.to_document)] {
+                ui.label(label);
+                let selected = documents
+                    .iter()
+                    .find(|d| Some(d.id) == *selection)
+                    .map_or("(none)", |d| d.title.as_str());
+                egui::ComboBox::from_id_salt(("patch_document", label))
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        for document in documents.iter() {
+                            ui.selectable_value(selection, Some(document.id), &document.title);
+                        }
+                    });
+            }
+        });
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("patch_generate_format")
+                .selected_text(self.generate_format.label())
+                .show_ui(ui, |ui| {
+                    for format in PatchFormat::ALL {
+                        ui.selectable_value(&mut self.generate_format, format, format.label());
+                    }
+                });
+            if ui.button("Generate").clicked() {
+                self.generate(documents);
+            }
+        });
+        if let Some((color, status)) = &self.status {
+            ui.colored_label(*color, status);
+        }
+
+        ui.separator();
+        let document = &mut documents[active];
+        ui.heading(format!("Apply to {}", document.title));
+        ui.horizontal(|ui| {
+            if ui.button("Load File…").clicked()
+                && let Some(path) = rfd::FileDialog::new().add_filter("JSON", &["json"]).pick_file()
+            {
+                match std::fs::read_to_string(&path) {
+                    Ok(text) => self.patch_text = text,
+                    Err(e) => self.status = Some((Color32::RED, format!("Failed to read {}: {}", path.display(), e))),
+                }
+            }
+            if ui.button("Copy Patch").clicked() {
+                ui.ctx().copy_text(self.patch_text.clone());
+            }
+            if ui.button("Clear").clicked() {
+                self.patch_text.clear();
+            }
+        });
+        egui::ScrollArea::vertical()
+            .id_salt("patch_text_scroll")
+            .max_height(180.0)
+            .show(ui, |ui| {
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.patch_text)
+                        .code_editor()
+                        .hint_text("[{ \"op\": \"replace\", \"path\": \"/a\", \"value\": 1 }]")
+                        .desired_rows(8)
+                        .desired_width(f32::INFINITY),
+                );
+            });
+
+        self.refresh_preview(document);
+        let preview = match &self.preview {
+            Ok(Some(preview)) => preview,
+            Ok(None) => return,
+            Err(e) => {
+                ui.colored_label(Color32::RED, e);
+                return;
+            }
+        };
+        ui.label(format!("Detected {}", preview.format.label()));
+        for outcome in &preview.outcomes {
+            match &outcome.error {
+                None => ui.colored_label(Color32::GREEN, format!("{}: ok", outcome.summary)),
+                Some(e) => ui.colored_label(Color32::RED, format!("{}: {}", outcome.summary, e)),
+            };
+        }
+        let apply = ui
+            .add_enabled(preview.succeeded(), egui::Button::new("Apply to Document"))
+            .on_disabled_hover_text("Every operation has to succeed before the patch can be applied");
+        ui.label("Result:");
+        egui::ScrollArea::both().id_salt("patch_result_scroll").show(ui, |ui| {
+            let mut result = preview.result.as_str();
+            ui.add(
+                egui::TextEdit::multiline(&mut result)
+                    .code_editor()
+                    .desired_width(f32::INFINITY),
+            );
+        });
+        if apply.clicked() {
+            document.replace_input(preview.result.clone());
+        }
+    }
+}
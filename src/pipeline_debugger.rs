@@ -0,0 +1,202 @@
+// The "Pipeline Debugger" window: splits the active document's jq filter
+// into its top-level `|` stages and runs every prefix of the pipeline, so
+// each stage shows how many values come out of it and what they look like.
+// The first stage that errors or produces nothing is where to look.
+use std::sync::mpsc::{Receiver, channel};
+
+use eframe::egui;
+use egui::Color32;
+use serde_json::Value;
+
+use crate::document::{Document, preview};
+use crate::execute_jq_query;
+use crate::jq_options::JqOptions;
+use crate::jq_syntax::split_pipeline;
+use crate::query::QueryLanguage;
+
+// Values shown per stage
+const PREVIEW_VALUES: usize = 5;
+
+struct StageOutput {
+    count: usize,
+    // e.g. "object ×3, null"
+    shape: String,
+    preview: Vec<String>,
+}
+
+struct Stage {
+    text: String,
+    // Where the pipeline up to and including this stage ends in the filter
+    prefix_end: usize,
+    // `None` until it has run
+    outcome: Option<Result<StageOutput, String>>,
+}
+
+#[derive(Default)]
+pub struct PipelineDebugger {
+    filter: String,
+    stages: Vec<Stage>,
+    running: Option<Receiver<(usize, Result<StageOutput, String>)>>,
+    error: Option<String>,
+}
+
+impl PipelineDebugger {
+    fn start(&mut self, ctx: &egui::Context, document: &Document) {
+        self.error = None;
+        self.stages.clear();
+        if document.query_language != QueryLanguage::Jq {
+            self.error = Some("The debugger steps through jq filters; switch the query language to jq.".to_string());
+            return;
+        }
+        if document.jq_query_input.trim().is_empty() {
+            self.error = Some("The query field cannot be empty.".to_string());
+            return;
+        }
+        self.filter = document.jq_query_input.clone();
+        self.stages = split_pipeline(&self.filter)
+            .into_iter()
+            .map(|range| Stage {
+                text: self.filter[range.clone()].trim().to_string(),
+                prefix_end: range.end,
+                outcome: None,
+            })
+            .collect();
+
+        let (sender, receiver) = channel();
+        let prefixes: Vec<String> = self.stages.iter().map(|stage| self.filter[..stage.prefix_end].to_string()).collect();
+        // Stages are shown as JSON, whatever the query prints
+        let options = JqOptions {
+            raw_output: false,
+            ..document.jq_options.clone()
+        };
+        let (ctx, input) = (ctx.clone(), document.input_json.clone());
+        std::thread::spawn(move || {
+            for (index, prefix) in prefixes.iter().enumerate() {
+                let outcome = execute_jq_query(&input, prefix, &options).and_then(|output| summarize(&output));
+                let failed = outcome.is_err();
+                if sender.send((index, outcome)).is_err() {
+                    return;
+                }
+                ctx.request_repaint();
+                // Later stages fail the same way
+                if failed {
+                    return;
+                }
+            }
+        });
+        self.running = Some(receiver);
+    }
+
+    // Returns a prefix of the filter to put in the query field
+    pub fn show(&mut self, ui: &mut egui::Ui, document: &Document) -> Option<String> {
+        if let Some(receiver) = &self.running {
+            for (index, outcome) in receiver.try_iter() {
+                self.stages[index].outcome = Some(outcome);
+            }
+            if self.stages.iter().all(|stage| stage.outcome.is_some())
+                || self.stages.iter().any(|stage| matches!(stage.outcome, Some(Err(_))))
+            {
+                self.running = None;
+            }
+        }
+
+        let mut use_prefix = None;
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(self.running.is_none(), egui::Button::new("Step Through Filter"))
+                .on_hover_text("Runs the active document's jq filter one stage at a time")
+                .clicked()
+            {
+                self.start(ui.ctx(), document);
+            }
+            if self.running.is_some() {
+                ui.spinner();
+            }
+        });
+        if let Some(error) = &self.error {
+            ui.colored_label(Color32::RED, error);
+        }
+        if self.stages.is_empty() {
+            return None;
+        }
+        ui.label(egui::RichText::new(&self.filter).monospace());
+        ui.separator();
+
+        // The first stage where the data runs dry
+        let culprit = self.stages.iter().position(|stage| match &stage.outcome {
+            Some(Ok(output)) => output.count == 0,
+            Some(Err(_)) => true,
+            None => false,
+        });
+        egui::ScrollArea::vertical().id_salt("pipeline_stages").show(ui, |ui| {
+            for (index, stage) in self.stages.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    let number = egui::RichText::new(format!("{}.", index + 1)).strong();
+                    ui.label(if culprit == Some(index) { number.color(Color32::LIGHT_RED) } else { number });
+                    ui.label(egui::RichText::new(&stage.text).monospace());
+                    if ui.small_button("Use up to Here").clicked() {
+                        use_prefix = Some(self.filter[..stage.prefix_end].trim_end().to_string());
+                    }
+                });
+                ui.indent(("stage", index), |ui| match &stage.outcome {
+                    None if self.running.is_some() => {
+                        ui.weak("Running…");
+                    }
+                    None => {
+                        ui.weak("Not run: an earlier stage failed.");
+                    }
+                    Some(Err(e)) => {
+                        ui.colored_label(Color32::LIGHT_RED, e.trim());
+                    }
+                    Some(Ok(output)) => {
+                        match output.count {
+                            0 => ui.colored_label(Color32::LIGHT_RED, "No output"),
+                            1 => ui.label(format!("1 output: {}", output.shape)),
+                            count => ui.label(format!("{} outputs: {}", count, output.shape)),
+                        };
+                        for value in &output.preview {
+                            ui.label(egui::RichText::new(value).monospace().weak());
+                        }
+                        if output.count > output.preview.len() {
+                            ui.weak(format!("… and {} more", output.count - output.preview.len()));
+                        }
+                    }
+                });
+            }
+        });
+        use_prefix
+    }
+}
+
+// Counts and previews the values jq printed
+fn summarize(output: &str) -> Result<StageOutput, String> {
+    let values = serde_json::Deserializer::from_str(output)
+        .into_iter::<Value>()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("jq printed something that isn't JSON: {}", e))?;
+    let mut kinds: Vec<(&str, usize)> = Vec::new();
+    for value in &values {
+        let kind = match value {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        };
+        match kinds.iter_mut().find(|(k, _)| *k == kind) {
+            Some((_, count)) => *count += 1,
+            None => kinds.push((kind, 1)),
+        }
+    }
+    let shape = kinds
+        .iter()
+        .map(|(kind, count)| if *count == 1 { kind.to_string() } else { format!("{} ×{}", kind, count) })
+        .collect::<Vec<_>>()
+        .join(", ");
+    Ok(StageOutput {
+        count: values.len(),
+        shape,
+        preview: values.iter().take(PREVIEW_VALUES).map(preview).collect(),
+    })
+}
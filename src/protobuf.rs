@@ -0,0 +1,145 @@
+// Protocol Buffers messages, decoded with a descriptor set (compiled, or
+// built here from `.proto` files) into the canonical proto3 JSON mapping and
+// encoded back from it. Field names are lowerCamelCase, enums are names,
+// 64-bit integers and bytes are strings, as the mapping prescribes.
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, SerializeOptions};
+use serde_json::Value;
+
+// Loaded message definitions and where they came from
+pub struct ProtoSchema {
+    pool: DescriptorPool,
+    pub source: String,
+}
+
+impl ProtoSchema {
+    // A serialized `FileDescriptorSet`, e.g. from `protoc --descriptor_set_out`
+    pub fn from_descriptor_set(path: &Path) -> Result<ProtoSchema, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let pool = DescriptorPool::decode(bytes.as_slice())
+            .map_err(|e| format!("{} is not a valid FileDescriptorSet: {}", path.display(), e))?;
+        Ok(ProtoSchema {
+            pool,
+            source: file_name(path),
+        })
+    }
+
+    // Imports are looked up next to the files themselves; the well-known
+    // `google/protobuf/*.proto` types are built in
+    pub fn compile(paths: &[PathBuf]) -> Result<ProtoSchema, String> {
+        // Each folder once, in the order the files were picked
+        let mut seen = HashSet::new();
+        let includes: Vec<&Path> = paths
+            .iter()
+            .filter_map(|path| path.parent())
+            .filter(|dir| seen.insert(*dir))
+            .collect();
+        let mut compiler = protox::Compiler::new(includes).map_err(|e| e.to_string())?;
+        compiler
+            .include_imports(true)
+            .open_files(paths)
+            .map_err(|e| format!("Failed to compile: {}", e))?;
+        Ok(ProtoSchema {
+            pool: compiler.descriptor_pool(),
+            source: paths.iter().map(|path| file_name(path)).collect::<Vec<_>>().join(", "),
+        })
+    }
+
+    // Fully qualified names of the message types a payload can be, without
+    // the synthetic entry types of map fields
+    pub fn message_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .pool
+            .all_messages()
+            .filter(|message| !message.is_map_entry())
+            .map(|message| message.full_name().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    // Returns the JSON and a warning for each field the descriptor doesn't
+    // know, since the JSON mapping has no way to carry those
+    pub fn decode(&self, message_name: &str, bytes: &[u8], with_defaults: bool) -> Result<(Value, Vec<String>), String> {
+        let descriptor = self
+            .pool
+            .get_message_by_name(message_name)
+            .ok_or_else(|| format!("Unknown message type {}", message_name))?;
+        let message = DynamicMessage::decode(descriptor, bytes)
+            .map_err(|e| format!("Not a valid {} message: {}", message_name, e))?;
+        let mut warnings = Vec::new();
+        note_unknown_fields(&message, message_name, &mut warnings);
+        let options = SerializeOptions::new().skip_default_fields(!with_defaults);
+        let value = message
+            .serialize_with_options(serde_json::value::Serializer, &options)
+            .map_err(|e| e.to_string())?;
+        Ok((value, warnings))
+    }
+
+    pub fn encode(&self, message_name: &str, value: &Value) -> Result<Vec<u8>, String> {
+        let descriptor = self
+            .pool
+            .get_message_by_name(message_name)
+            .ok_or_else(|| format!("Unknown message type {}", message_name))?;
+        let message = DynamicMessage::deserialize(descriptor, value)
+            .map_err(|e| format!("The document doesn't match {}: {}", message_name, e))?;
+        Ok(message.encode_to_vec())
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned())
+}
+
+fn note_unknown_fields(message: &DynamicMessage, location: &str, warnings: &mut Vec<String>) {
+    for field in message.unknown_fields() {
+        warnings.push(format!("{}: unknown field number {} was dropped", location, field.number()));
+    }
+    for (field, value) in message.fields() {
+        let location = format!("{}.{}", location, field.name());
+        match value {
+            prost_reflect::Value::Message(child) => note_unknown_fields(child, &location, warnings),
+            prost_reflect::Value::List(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    if let prost_reflect::Value::Message(child) = item {
+                        note_unknown_fields(child, &format!("{}[{}]", location, index), warnings);
+                    }
+                }
+            }
+            prost_reflect::Value::Map(entries) => {
+                for (key, item) in entries {
+                    if let prost_reflect::Value::Message(child) = item {
+                        note_unknown_fields(child, &format!("{}[{:?}]", location, key), warnings);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// A gRPC message as sent on the wire starts with a compressed flag and a
+// 4-byte big-endian length; this returns the message inside.
+pub fn strip_grpc_frame(bytes: &[u8]) -> Result<&[u8], String> {
+    let (header, rest) = bytes
+        .split_at_checked(5)
+        .ok_or("The payload is too short for a gRPC frame.")?;
+    if header[0] != 0 {
+        return Err("The gRPC frame is compressed; decompress the payload first.".to_string());
+    }
+    let len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+    rest.get(..len)
+        .ok_or_else(|| format!("The gRPC frame says {} bytes but only {} follow.", len, rest.len()))
+}
+
+pub fn add_grpc_frame(message: &[u8]) -> Vec<u8> {
+    let mut framed = vec![0];
+    framed.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    framed.extend_from_slice(message);
+    framed
+}
+
@@ -0,0 +1,153 @@
+// The "Protobuf" window: load message definitions, pick a message type,
+// decode a binary or pasted payload into a new document, and encode the
+// active document back to binary.
+use eframe::egui;
+use egui::Color32;
+use serde_json::Value;
+
+use crate::binary::{decode_blob_text, size_comparison};
+use crate::convert_view::{ConversionReport, ConvertedOutput};
+use crate::document::Document;
+use crate::parse_json_to_value;
+use crate::protobuf::{ProtoSchema, add_grpc_frame, strip_grpc_frame};
+
+// What the app should do after the panel was drawn
+pub enum ProtobufOutput {
+    // Open a decoded message: title, JSON and lossy-conversion warnings
+    Decoded(String, Value, Vec<String>),
+    Encoded(ConversionReport),
+}
+
+#[derive(Default)]
+pub struct ProtobufPanel {
+    schema: Option<ProtoSchema>,
+    message_names: Vec<String>,
+    message_filter: String,
+    message_name: Option<String>,
+    with_defaults: bool, // Include fields that have their default value
+    grpc_frame: bool,    // Payloads carry the 5-byte gRPC message prefix
+    error: Option<String>,
+}
+
+impl ProtobufPanel {
+    fn set_schema(&mut self, schema: Result<ProtoSchema, String>) {
+        match schema {
+            Ok(schema) => {
+                self.message_names = schema.message_names();
+                if !self.message_name.as_ref().is_some_and(|name| self.message_names.contains(name)) {
+                    self.message_name = self.message_names.first().cloned();
+                }
+                self.schema = Some(schema);
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    fn decode(&self, source: &str, bytes: Result<Vec<u8>, String>) -> Result<ProtobufOutput, String> {
+        let (Some(schema), Some(message_name)) = (&self.schema, &self.message_name) else {
+            return Err("Load message definitions and pick a message type first.".to_string());
+        };
+        let bytes = bytes?;
+        let payload = if self.grpc_frame { strip_grpc_frame(&bytes)? } else { bytes.as_slice() };
+        let (value, warnings) = schema.decode(message_name, payload, self.with_defaults)?;
+        let short_name = message_name.rsplit('.').next().unwrap_or(message_name);
+        Ok(ProtobufOutput::Decoded(format!("{} from {}", short_name, source), value, warnings))
+    }
+
+    fn encode(&self, document: &Document) -> Result<ProtobufOutput, String> {
+        let (Some(schema), Some(message_name)) = (&self.schema, &self.message_name) else {
+            return Err("Load message definitions and pick a message type first.".to_string());
+        };
+        let value = parse_json_to_value(&document.input_json)?;
+        let mut bytes = schema.encode(message_name, &value)?;
+        if self.grpc_frame {
+            bytes = add_grpc_frame(&bytes);
+        }
+        Ok(ProtobufOutput::Encoded(ConversionReport {
+            title: format!("{} as {}", document.title, message_name),
+            summary: Some(size_comparison("Protobuf", bytes.len(), &value)),
+            output: Some((ConvertedOutput::Bytes(bytes), "bin")),
+            warnings: Vec::new(),
+            save_error: None,
+        }))
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, document: &Document) -> Option<ProtobufOutput> {
+        ui.horizontal(|ui| {
+            if ui.button("Load Descriptor Set…").clicked()
+                && let Some(path) = rfd::FileDialog::new()
+                    .add_filter("FileDescriptorSet", &["pb", "desc", "binpb", "protoset"])
+                    .pick_file()
+            {
+                self.set_schema(ProtoSchema::from_descriptor_set(&path));
+            }
+            if ui.button("Load .proto Files…").clicked()
+                && let Some(paths) = rfd::FileDialog::new().add_filter("Protocol Buffers", &["proto"]).pick_files()
+            {
+                self.set_schema(ProtoSchema::compile(&paths));
+            }
+        });
+        match &self.schema {
+            Some(schema) => ui.label(format!("Definitions: {} ({} message types)", schema.source, self.message_names.len())),
+            None => ui.label("Load a compiled FileDescriptorSet or the .proto files of your messages."),
+        };
+
+        ui.horizontal(|ui| {
+            ui.label("Message type:");
+            egui::ComboBox::from_id_salt("protobuf_message")
+                .selected_text(self.message_name.as_deref().unwrap_or("(none)"))
+                .width(300.0)
+                .show_ui(ui, |ui| {
+                    ui.add(egui::TextEdit::singleline(&mut self.message_filter).hint_text("Filter"));
+                    let filter = self.message_filter.to_lowercase();
+                    for name in self.message_names.iter().filter(|name| name.to_lowercase().contains(&filter)) {
+                        ui.selectable_value(&mut self.message_name, Some(name.clone()), name);
+                    }
+                });
+        });
+        ui.checkbox(&mut self.with_defaults, "Include fields with default values");
+        ui.checkbox(&mut self.grpc_frame, "gRPC framing (5-byte length prefix)");
+
+        ui.separator();
+        let mut result = None;
+        ui.horizontal(|ui| {
+            if ui.button("Decode File…").clicked()
+                && let Some(path) = rfd::FileDialog::new().pick_file()
+            {
+                let source = path.file_name().map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into_owned());
+                let bytes = std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e));
+                result = Some(self.decode(&source, bytes));
+            }
+            if ui
+                .button("Decode Hex/Base64 Text")
+                .on_hover_text("Decodes the hex or base64 payload pasted into the active document")
+                .clicked()
+            {
+                result = Some(self.decode(&document.title, decode_blob_text(&document.input_json)));
+            }
+            if ui
+                .button("Encode Active Document")
+                .on_hover_text("Encodes the active document's JSON as the selected message type")
+                .clicked()
+            {
+                result = Some(self.encode(document));
+            }
+        });
+        let output = match result {
+            Some(Ok(output)) => {
+                self.error = None;
+                Some(output)
+            }
+            Some(Err(e)) => {
+                self.error = Some(e);
+                None
+            }
+            None => None,
+        };
+        if let Some(error) = &self.error {
+            ui.colored_label(Color32::RED, error);
+        }
+        output
+    }
+}
@@ -0,0 +1,149 @@
+// Query languages evaluated in-process, next to jq which runs as an external
+// program. JSONPath returns the matched nodes of the document itself, so the
+// tree can keep showing the whole document with the matches outlined;
+// JMESPath builds a new value, which replaces the tree like a jq result, and
+// so does SQL (see `sql.rs`), whose rows are shown as a table.
+use std::time::{Duration, SystemTime};
+
+use serde_json::Value;
+use serde_json_path::PathElement;
+
+use crate::jq_options::{JqLimits, JqOptions};
+use crate::json_path::JsonPath;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QueryLanguage {
+    #[default]
+    Jq,
+    JsonPath,
+    JmesPath,
+    Sql,
+}
+
+impl QueryLanguage {
+    pub const ALL: [QueryLanguage; 4] =
+        [QueryLanguage::Jq, QueryLanguage::JsonPath, QueryLanguage::JmesPath, QueryLanguage::Sql];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            QueryLanguage::Jq => "jq",
+            QueryLanguage::JsonPath => "JSONPath",
+            QueryLanguage::JmesPath => "JMESPath",
+            QueryLanguage::Sql => "SQL",
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<QueryLanguage> {
+        QueryLanguage::ALL.into_iter().find(|language| language.label().eq_ignore_ascii_case(label))
+    }
+
+    pub fn hint(self) -> &'static str {
+        match self {
+            QueryLanguage::Jq => ".data[] | select(.active)",
+            QueryLanguage::JsonPath => "$.data[?@.active].name",
+            QueryLanguage::JmesPath => "data[?active].name",
+            QueryLanguage::Sql => "SELECT status, count(*) FROM data GROUP BY status",
+        }
+    }
+}
+
+// One node a query selected
+pub struct QueryMatch {
+    pub path: JsonPath,
+    pub value: Value,
+}
+
+// Runs a query of any language against a document's text and returns its
+// result as one value: the matched values for JSONPath, the rows for SQL.
+// `options` only apply to jq, `limits` to jq and SQL.
+// Safe to call off the UI thread.
+pub fn evaluate(
+    language: QueryLanguage,
+    input: &str,
+    query: &str,
+    options: &JqOptions,
+    limits: &JqLimits,
+) -> Result<Value, String> {
+    let root = || crate::parse_json_to_value(input);
+    match language {
+        QueryLanguage::Jq => {
+            let output = crate::execute_jq_query_limited(input, query, options, limits)?;
+            options.parse_output(&output).map(|(value, _)| value)
+        }
+        QueryLanguage::JsonPath => {
+            let matches = run_jsonpath(&root()?, query)?;
+            Ok(Value::Array(matches.into_iter().map(|found| found.value).collect()))
+        }
+        QueryLanguage::JmesPath => run_jmespath(&root()?, query),
+        QueryLanguage::Sql => crate::sql::run_sql(&root()?, query, limits).map(|(_, rows)| rows),
+    }
+}
+
+// A query that was run, for the document's history
+pub struct QueryRun {
+    pub language: QueryLanguage,
+    pub query: String,
+    pub at: SystemTime,
+    // A description of the result's size, or the error
+    pub outcome: Result<String, String>,
+}
+
+impl QueryRun {
+    // e.g. "just now", "5 min ago", "yesterday"
+    pub fn age(&self) -> String {
+        let seconds = SystemTime::now().duration_since(self.at).unwrap_or(Duration::ZERO).as_secs();
+        match seconds {
+            0..60 => "just now".to_string(),
+            60..3600 => format!("{} min ago", seconds / 60),
+            3600..86400 => format!("{} h ago", seconds / 3600),
+            86400..172800 => "yesterday".to_string(),
+            _ => format!("{} days ago", seconds / 86400),
+        }
+    }
+}
+
+// Size of a query result as shown in the history, e.g. "12 items, 1.4 KB"
+pub fn result_size(value: &Value) -> String {
+    let count = match value {
+        Value::Array(items) => format!("{} items", items.len()),
+        Value::Object(map) => format!("{} keys", map.len()),
+        _ => "1 value".to_string(),
+    };
+    let bytes = value.to_string().len();
+    if bytes < 1024 {
+        format!("{}, {} B", count, bytes)
+    } else {
+        format!("{}, {:.1} KB", count, bytes as f64 / 1024.0)
+    }
+}
+
+// RFC 9535 JSONPath, including filters, slices and recursive descent
+pub fn run_jsonpath(root: &Value, query: &str) -> Result<Vec<QueryMatch>, String> {
+    let path = serde_json_path::JsonPath::parse(query).map_err(|e| format!("Invalid JSONPath: {}", e))?;
+    Ok(path
+        .query_located(root)
+        .into_iter()
+        .map(|node| {
+            let mut path = JsonPath::root();
+            for element in node.location().iter() {
+                path = match element {
+                    PathElement::Name(key) => path.child_key(key),
+                    PathElement::Index(index) => path.child_index(*index),
+                };
+            }
+            QueryMatch {
+                path,
+                value: node.node().clone(),
+            }
+        })
+        .collect())
+}
+
+
+// JMESPath as used by `aws --query`
+pub fn run_jmespath(root: &Value, query: &str) -> Result<Value, String> {
+    let expression = jmespath::compile(query).map_err(|e| e.to_string())?;
+    let result = expression.search(root).map_err(|e| e.to_string())?;
+    serde_json::to_value(&*result).map_err(|e| e.to_string())
+}
+
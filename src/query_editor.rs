@@ -0,0 +1,228 @@
+// The query field. For jq it highlights the filter, marks the bracket at the
+// cursor and its partner, and pops up completions: keys of the document
+// after `.`, builtins with their signatures while a function name is typed
+// or after `|`. Up/Down pick a suggestion, Tab or Enter insert it, Escape
+// closes the list. The field can switch to a multi-line editor for long
+// filters.
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use eframe::egui;
+use egui::text::{CCursor, CCursorRange, LayoutJob, TextFormat};
+use egui::{Color32, TextStyle};
+use serde_json::Value;
+
+use crate::jq_syntax::{Completion, TokenKind, bracket_partners, complete, tokenize};
+use crate::query::QueryLanguage;
+use crate::{char_to_byte_index, parse_json_to_value};
+
+// Suggestions shown without scrolling
+const VISIBLE_SUGGESTIONS: usize = 10;
+
+#[derive(Default)]
+pub struct QueryEditor {
+    pub multiline: bool,
+    completion: Option<Completion>,
+    selected: usize,
+    scroll_to_selected: bool, // The selection moved with the keyboard
+    // The document text parsed for key completion, and the text's hash
+    source: Option<(u64, Option<Value>)>,
+}
+
+impl QueryEditor {
+    fn source(&mut self, document_text: &str) -> Option<&Value> {
+        let mut hasher = DefaultHasher::new();
+        document_text.hash(&mut hasher);
+        let hash = hasher.finish();
+        if self.source.as_ref().is_none_or(|(cached, _)| *cached != hash) {
+            self.source = Some((hash, parse_json_to_value(document_text).ok()));
+        }
+        self.source.as_ref().and_then(|(_, value)| value.as_ref())
+    }
+
+    // Replaces the typed word with suggestion `index` and puts the cursor
+    // after it
+    fn accept(&mut self, ctx: &egui::Context, id: egui::Id, query: &mut String, index: usize) {
+        let Some(completion) = self.completion.take() else {
+            return;
+        };
+        let Some(suggestion) = completion.suggestions.get(index) else {
+            return;
+        };
+        query.replace_range(completion.replace.clone(), &suggestion.insert);
+        let cursor = query[..completion.replace.start + suggestion.insert.len()].chars().count();
+        let mut state = egui::text_edit::TextEditState::load(ctx, id).unwrap_or_default();
+        state.cursor.set_char_range(Some(CCursorRange::one(CCursor::new(cursor))));
+        state.store(ctx, id);
+        ctx.memory_mut(|m| m.request_focus(id));
+    }
+
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        id: egui::Id,
+        query: &mut String,
+        language: QueryLanguage,
+        document_text: &str,
+    ) -> egui::Response {
+        let is_jq = language == QueryLanguage::Jq;
+        if !is_jq {
+            self.completion = None;
+        }
+        let focused = ui.memory(|m| m.has_focus(id));
+        if let Some(completion) = &self.completion
+            && focused
+        {
+            let count = completion.suggestions.len();
+            let (down, up, accept, close) = ui.input_mut(|i| {
+                (
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::Tab)
+                        || i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+                    i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+                )
+            });
+            self.scroll_to_selected = down || up;
+            if down {
+                self.selected = (self.selected + 1) % count;
+            } else if up {
+                self.selected = (self.selected + count - 1) % count;
+            } else if accept {
+                self.accept(ui.ctx(), id, query, self.selected);
+            } else if close {
+                self.completion = None;
+            }
+        }
+
+        let cursor = egui::text_edit::TextEditState::load(ui.ctx(), id)
+            .and_then(|state| state.cursor.char_range())
+            .map(|range| char_to_byte_index(query, range.primary.index));
+        let mut layouter = |ui: &egui::Ui, text: &str, wrap_width: f32| {
+            let mut job = if is_jq { highlight(ui, text, cursor) } else { plain(ui, text) };
+            job.wrap.max_width = wrap_width;
+            ui.fonts(|f| f.layout_job(job))
+        };
+        let edit = if self.multiline {
+            egui::TextEdit::multiline(query).desired_rows(4)
+        } else {
+            egui::TextEdit::singleline(query)
+        };
+        let output = edit
+            .id(id)
+            .hint_text(language.hint())
+            .layouter(&mut layouter)
+            .show(ui);
+        ui.toggle_value(&mut self.multiline, "⇕")
+            .on_hover_text("Edit the query on several lines");
+
+        let new_cursor = output.cursor_range.map(|range| range.primary.ccursor);
+        if !output.response.has_focus() {
+            self.completion = None;
+        } else if output.response.changed()
+            && is_jq
+            && let Some(ccursor) = new_cursor
+        {
+            let byte = char_to_byte_index(query, ccursor.index);
+            let completion = complete(query, byte, self.source(document_text));
+            self.completion = completion;
+            self.selected = 0;
+        } else if new_cursor.map(|c| char_to_byte_index(query, c.index)) != cursor {
+            // The cursor was moved rather than typed forward
+            self.completion = None;
+        }
+
+        if let Some(completion) = &self.completion
+            && let Some(ccursor) = new_cursor
+        {
+            let cursor_rect = output.galley.pos_from_ccursor(ccursor);
+            let position = output.galley_pos + cursor_rect.left_bottom().to_vec2();
+            let mut clicked = None;
+            egui::Area::new(id.with("completion"))
+                .order(egui::Order::Foreground)
+                .fixed_pos(position)
+                .show(ui.ctx(), |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        let row_height = ui.text_style_height(&TextStyle::Monospace) + ui.spacing().item_spacing.y;
+                        egui::ScrollArea::vertical()
+                            .max_height(row_height * VISIBLE_SUGGESTIONS as f32)
+                            .show(ui, |ui| {
+                                for (index, suggestion) in completion.suggestions.iter().enumerate() {
+                                    let mut text = LayoutJob::default();
+                                    let format = TextFormat {
+                                        font_id: TextStyle::Monospace.resolve(ui.style()),
+                                        color: ui.visuals().strong_text_color(),
+                                        ..Default::default()
+                                    };
+                                    text.append(&suggestion.label, 0.0, format.clone());
+                                    text.append(
+                                        &suggestion.detail,
+                                        16.0,
+                                        TextFormat { color: ui.visuals().weak_text_color(), ..format },
+                                    );
+                                    let response = ui.selectable_label(index == self.selected, text);
+                                    if index == self.selected && self.scroll_to_selected {
+                                        response.scroll_to_me(None);
+                                    }
+                                    if response.clicked() {
+                                        clicked = Some(index);
+                                    }
+                                }
+                            });
+                    });
+                });
+            if let Some(index) = clicked {
+                self.accept(ui.ctx(), id, query, index);
+            }
+        }
+        output.response
+    }
+}
+
+fn base_format(ui: &egui::Ui) -> TextFormat {
+    TextFormat {
+        color: ui.visuals().text_color(),
+        font_id: TextStyle::Monospace.resolve(ui.style()),
+        ..Default::default()
+    }
+}
+
+fn plain(ui: &egui::Ui, text: &str) -> LayoutJob {
+    LayoutJob::single_section(text.to_string(), base_format(ui))
+}
+
+// Colors jq tokens. The bracket just before or at the cursor and its partner
+// get a background; brackets without a partner are red.
+fn highlight(ui: &egui::Ui, text: &str, cursor: Option<usize>) -> LayoutJob {
+    let base = base_format(ui);
+    let tokens = tokenize(text);
+    let partners = bracket_partners(text, &tokens);
+    let is_bracket = |kind: TokenKind| matches!(kind, TokenKind::Open | TokenKind::Close);
+    let at_cursor = cursor.and_then(|cursor| {
+        let before = tokens.iter().position(|t| is_bracket(t.kind) && t.range.end == cursor);
+        let after = tokens.iter().position(|t| is_bracket(t.kind) && t.range.start == cursor);
+        before.or(after)
+    });
+    let marked = at_cursor.into_iter().chain(at_cursor.and_then(|index| partners[index])).collect::<Vec<_>>();
+
+    let mut job = LayoutJob::default();
+    let mut end = 0;
+    for (index, token) in tokens.iter().enumerate() {
+        job.append(&text[end..token.range.start], 0.0, base.clone());
+        let color = match token.kind {
+            TokenKind::Field => Color32::from_rgb(150, 255, 150),
+            TokenKind::Variable => Color32::from_rgb(200, 170, 255),
+            TokenKind::Keyword | TokenKind::Format => Color32::from_rgb(255, 150, 255),
+            TokenKind::Identifier => Color32::from_rgb(120, 210, 210),
+            TokenKind::String => Color32::from_rgb(255, 150, 80),
+            TokenKind::Number => Color32::from_rgb(150, 200, 255),
+            TokenKind::Comment => Color32::GRAY,
+            TokenKind::Open | TokenKind::Close if partners[index].is_none() => Color32::RED,
+            TokenKind::Open | TokenKind::Close | TokenKind::Operator => base.color,
+        };
+        let background = if marked.contains(&index) { Color32::from_gray(90) } else { Color32::TRANSPARENT };
+        job.append(&text[token.range.clone()], 0.0, TextFormat { color, background, ..base.clone() });
+        end = token.range.end;
+    }
+    job.append(&text[end..], 0.0, base);
+    job
+}
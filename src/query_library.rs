@@ -0,0 +1,142 @@
+// Saved queries with names and descriptions, kept in a JSON file in the
+// user's config directory. Libraries can be exported and imported so a team
+// can share its standard filters. The file looks like
+//   {"queries": [{"name": "...", "description": "...", "language": "jq", "query": "..."}]}
+// with an "options" object for jq queries run with `--slurp`, variables etc.
+use std::path::{Path, PathBuf};
+
+use serde_json::{Value, json};
+
+use crate::jq_options::JqOptions;
+use crate::query::QueryLanguage;
+
+#[derive(Clone)]
+pub struct SavedQuery {
+    pub name: String,
+    pub description: String,
+    pub language: QueryLanguage,
+    pub query: String,
+    pub jq_options: JqOptions,
+}
+
+impl SavedQuery {
+    fn to_json(&self) -> Value {
+        let mut value = json!({
+            "name": self.name,
+            "description": self.description,
+            "language": self.language.label(),
+            "query": self.query,
+        });
+        if self.jq_options.active_count() > 0 {
+            value["options"] = self.jq_options.to_json();
+        }
+        value
+    }
+
+    fn from_json(value: &Value) -> Result<SavedQuery, String> {
+        let object = value.as_object().ok_or("Each saved query must be an object.")?;
+        let text = |key: &str| object.get(key).and_then(Value::as_str).unwrap_or_default().to_string();
+        let name = text("name");
+        if name.trim().is_empty() {
+            return Err("A saved query has no name.".to_string());
+        }
+        let language = match object.get("language").and_then(Value::as_str) {
+            None => QueryLanguage::Jq,
+            Some(label) => QueryLanguage::from_label(label)
+                .ok_or_else(|| format!("\"{}\" uses the unknown query language \"{}\".", name, label))?,
+        };
+        Ok(SavedQuery {
+            description: text("description"),
+            query: text("query"),
+            jq_options: object.get("options").map(JqOptions::from_json).unwrap_or_default(),
+            name,
+            language,
+        })
+    }
+
+    // Matches the name, description or query text, ignoring case
+    pub fn matches(&self, search: &str) -> bool {
+        let search = search.to_lowercase();
+        [&self.name, &self.description, &self.query]
+            .iter()
+            .any(|field| field.to_lowercase().contains(&search))
+    }
+}
+
+#[derive(Default)]
+pub struct QueryLibrary {
+    pub queries: Vec<SavedQuery>,
+}
+
+impl QueryLibrary {
+    // `None` when the platform has no config directory
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("json-formatter-gui").join("queries.json"))
+    }
+
+    // The library saved on this machine; empty if there is none yet
+    pub fn load() -> Result<QueryLibrary, String> {
+        match QueryLibrary::default_path() {
+            Some(path) if path.exists() => QueryLibrary::read(&path),
+            _ => Ok(QueryLibrary::default()),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = QueryLibrary::default_path().ok_or("There is no config directory to save queries in.")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        self.write(&path)
+    }
+
+    pub fn read(path: &Path) -> Result<QueryLibrary, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let value: Value =
+            serde_json::from_str(&text).map_err(|e| format!("{} is not valid JSON: {}", path.display(), e))?;
+        let entries = value
+            .get("queries")
+            .and_then(Value::as_array)
+            .ok_or_else(|| format!("{} has no \"queries\" array.", path.display()))?;
+        let queries = entries.iter().map(SavedQuery::from_json).collect::<Result<_, _>>()?;
+        Ok(QueryLibrary { queries })
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let queries: Vec<Value> = self.queries.iter().map(SavedQuery::to_json).collect();
+        let text = serde_json::to_string_pretty(&json!({ "queries": queries })).map_err(|e| e.to_string())?;
+        std::fs::write(path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    // Adds a query, replacing the one with the same name. Returns whether
+    // one was replaced.
+    pub fn insert(&mut self, query: SavedQuery) -> bool {
+        match self.queries.iter_mut().find(|saved| saved.name == query.name) {
+            Some(saved) => {
+                *saved = query;
+                true
+            }
+            None => {
+                self.queries.push(query);
+                self.queries.sort_by_key(|saved| saved.name.to_lowercase());
+                false
+            }
+        }
+    }
+
+    // Adds every query of another library file, returning how many were new
+    // and how many replaced a query with the same name
+    pub fn import(&mut self, path: &Path) -> Result<(usize, usize), String> {
+        let imported = QueryLibrary::read(path)?;
+        let mut added = 0;
+        let mut replaced = 0;
+        for query in imported.queries {
+            if self.insert(query) {
+                replaced += 1;
+            } else {
+                added += 1;
+            }
+        }
+        Ok((added, replaced))
+    }
+}
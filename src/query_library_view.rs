@@ -0,0 +1,152 @@
+// The "Query Library" window: save the active document's query under a name,
+// search the saved ones and load them back, and share the library as a file.
+use eframe::egui;
+use egui::Color32;
+
+use crate::document::Document;
+use crate::query_library::{QueryLibrary, SavedQuery};
+
+pub struct QueryLibraryPanel {
+    library: QueryLibrary,
+    // Set when the file on disk couldn't be read, so saving doesn't replace it
+    load_error: Option<String>,
+    search: String,
+    name: String,
+    description: String,
+    // Result of the last action, shown under the buttons
+    status: Option<Result<String, String>>,
+}
+
+impl QueryLibraryPanel {
+    pub fn load() -> Self {
+        let (library, load_error) = match QueryLibrary::load() {
+            Ok(library) => (library, None),
+            Err(e) => (QueryLibrary::default(), Some(e)),
+        };
+        QueryLibraryPanel {
+            library,
+            load_error,
+            search: String::new(),
+            name: String::new(),
+            description: String::new(),
+            status: None,
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        match &self.load_error {
+            Some(e) => Err(format!("{} Fix or remove the file to save the library.", e)),
+            None => self.library.save(),
+        }
+    }
+
+    // Returns a query the user picked to load into the active document
+    pub fn show(&mut self, ui: &mut egui::Ui, document: &Document) -> Option<SavedQuery> {
+        let mut picked = None;
+        ui.label(egui::RichText::new("Save the active query").strong());
+        egui::Grid::new("query_library_save").num_columns(2).show(ui, |ui| {
+            ui.label("Name:");
+            ui.text_edit_singleline(&mut self.name);
+            ui.end_row();
+            ui.label("Description:");
+            ui.text_edit_singleline(&mut self.description);
+            ui.end_row();
+        });
+        let can_save = !self.name.trim().is_empty() && !document.jq_query_input.trim().is_empty();
+        if ui
+            .add_enabled(can_save, egui::Button::new("Save"))
+            .on_disabled_hover_text("Needs a name and a query in the active document")
+            .clicked()
+        {
+            let replaced = self.library.insert(SavedQuery {
+                name: self.name.trim().to_string(),
+                description: self.description.trim().to_string(),
+                language: document.query_language,
+                query: document.jq_query_input.clone(),
+                jq_options: document.jq_options.clone(),
+            });
+            self.status = Some(self.save().map(|()| {
+                let verb = if replaced { "Replaced" } else { "Saved" };
+                format!("{} \"{}\".", verb, self.name.trim())
+            }));
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("Search:");
+            ui.text_edit_singleline(&mut self.search);
+            if ui.button("Import…").clicked()
+                && let Some(path) = rfd::FileDialog::new().add_filter("JSON", &["json"]).pick_file()
+            {
+                self.status = Some(self.library.import(&path).and_then(|(added, replaced)| {
+                    self.save()?;
+                    Ok(format!("Imported {} new and {} replaced queries.", added, replaced))
+                }));
+            }
+            if ui.button("Export…").clicked()
+                && let Some(path) = rfd::FileDialog::new()
+                    .add_filter("JSON", &["json"])
+                    .set_file_name("queries.json")
+                    .save_file()
+            {
+                self.status = Some(
+                    self.library
+                        .write(&path)
+                        .map(|()| format!("Exported {} queries.", self.library.queries.len())),
+                );
+            }
+        });
+        match &self.status {
+            Some(Ok(message)) => {
+                ui.label(message);
+            }
+            Some(Err(e)) => {
+                ui.colored_label(Color32::RED, e);
+            }
+            None => {}
+        }
+        if let Some(e) = &self.load_error {
+            ui.colored_label(Color32::RED, e);
+        }
+
+        let mut delete = None;
+        egui::ScrollArea::vertical().id_salt("query_library_scroll").show(ui, |ui| {
+            if self.library.queries.is_empty() {
+                ui.label("No saved queries yet.");
+            }
+            for (index, saved) in self.library.queries.iter().enumerate() {
+                if !saved.matches(&self.search) {
+                    continue;
+                }
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(&saved.name).strong());
+                    ui.weak(saved.language.label());
+                    if ui.button("Use").on_hover_text("Loads the query into the active document").clicked() {
+                        picked = Some(saved.clone());
+                    }
+                    if ui.button("Delete").clicked() {
+                        delete = Some(index);
+                    }
+                });
+                if !saved.description.is_empty() {
+                    ui.label(&saved.description);
+                }
+                ui.label(egui::RichText::new(&saved.query).monospace());
+                if let Ok(args) = saved.jq_options.command_args()
+                    && !args.is_empty()
+                {
+                    ui.weak(format!("jq {}", args.join(" ")));
+                }
+            }
+        });
+        if let Some(index) = delete {
+            let removed = self.library.queries.remove(index);
+            self.status = Some(self.save().map(|()| format!("Deleted \"{}\".", removed.name)));
+        }
+        if let Some(path) = QueryLibrary::default_path() {
+            ui.weak(format!("Stored in {}", path.display()));
+        }
+        picked
+    }
+}
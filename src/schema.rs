@@ -0,0 +1,147 @@
+// JSON Schema validation of the current document, and schema generation from
+// sample documents. Validation errors carry both the instance location (as a
+// `JsonPath`, so the tree and editor can point at the node) and the schema
+// location of the keyword that failed.
+use jsonschema::Draft;
+use serde_json::{Map, Value, json};
+
+use crate::infer::Shape;
+use crate::json_path::JsonPath;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SchemaDraft {
+    // Use the schema's `$schema`, falling back to 2020-12
+    #[default]
+    Auto,
+    Draft7,
+    Draft202012,
+}
+
+impl SchemaDraft {
+    pub const ALL: [SchemaDraft; 3] = [SchemaDraft::Auto, SchemaDraft::Draft202012, SchemaDraft::Draft7];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SchemaDraft::Auto => "Auto ($schema)",
+            SchemaDraft::Draft7 => "Draft 7",
+            SchemaDraft::Draft202012 => "Draft 2020-12",
+        }
+    }
+}
+
+pub struct SchemaError {
+    pub instance_path: JsonPath,
+    // JSON Pointer into the schema, e.g. `/properties/id/type`
+    pub schema_path: String,
+    pub message: String,
+}
+
+pub fn validate(schema: &Value, instance: &Value, draft: SchemaDraft) -> Result<Vec<SchemaError>, String> {
+    let mut options = jsonschema::options();
+    match draft {
+        SchemaDraft::Auto => {}
+        SchemaDraft::Draft7 => options = options.with_draft(Draft::Draft7),
+        SchemaDraft::Draft202012 => options = options.with_draft(Draft::Draft202012),
+    }
+    let validator = options
+        .build(schema)
+        .map_err(|e| format!("Invalid schema: {}", e))?;
+    Ok(validator
+        .iter_errors(instance)
+        .map(|error| SchemaError {
+            instance_path: JsonPath::from_json_pointer(error.instance_path().as_str(), instance)
+                .unwrap_or_default(),
+            schema_path: error.schema_path().as_str().to_string(),
+            message: error.to_string(),
+        })
+        .collect())
+}
+
+// Builds a draft 2020-12 schema that accepts every sample.
+pub fn generate_schema(samples: &[Value]) -> Value {
+    let shape = Shape::from_samples(samples);
+    let mut schema = shape_schema(&shape);
+    if let Value::Object(map) = &mut schema {
+        map.insert(
+            "$schema".to_string(),
+            Value::from("https://json-schema.org/draft/2020-12/schema"),
+        );
+    }
+    schema
+}
+
+fn shape_schema(shape: &Shape) -> Value {
+    let mut variants = Vec::new();
+    if shape.nulls > 0 {
+        variants.push(json!({ "type": "null" }));
+    }
+    if shape.booleans > 0 {
+        variants.push(json!({ "type": "boolean" }));
+    }
+    if shape.floats > 0 {
+        variants.push(json!({ "type": "number" }));
+    } else if shape.integers > 0 {
+        variants.push(json!({ "type": "integer" }));
+    }
+    if let Some(strings) = &shape.strings {
+        let mut schema = json!({ "type": "string" });
+        if let Some(format) = strings.format() {
+            schema["format"] = Value::from(format.name());
+        } else if let Some(values) = strings.enum_values() {
+            schema["enum"] = Value::from(values);
+        }
+        variants.push(schema);
+    }
+    if let Some(array) = &shape.array {
+        let mut schema = json!({ "type": "array" });
+        if array.items.occurrences > 0 {
+            schema["items"] = shape_schema(&array.items);
+        }
+        variants.push(schema);
+    }
+    if let Some(object) = &shape.object {
+        let properties: Map<String, Value> = object
+            .fields
+            .iter()
+            .map(|(key, field)| (key.clone(), shape_schema(field)))
+            .collect();
+        let mut required: Vec<&str> = object
+            .fields
+            .iter()
+            .filter(|(_, field)| object.is_required(field))
+            .map(|(key, _)| key.as_str())
+            .collect();
+        required.sort_unstable();
+        let mut schema = json!({ "type": "object", "properties": properties });
+        if !required.is_empty() {
+            schema["required"] = Value::from(required);
+        }
+        variants.push(schema);
+    }
+    merge_variants(variants)
+}
+
+// Collapses the per-type schemas into one: `{"type": [..]}` when they are
+// bare types, a nullable single type when there is one real type plus null,
+// and `anyOf` otherwise.
+fn merge_variants(mut variants: Vec<Value>) -> Value {
+    let is_bare = |v: &Value| v.as_object().is_some_and(|o| o.len() == 1);
+    match variants.len() {
+        0 => json!({}),
+        1 => variants.remove(0),
+        _ if variants.iter().all(is_bare) => {
+            let types: Vec<Value> = variants.iter().map(|v| v["type"].clone()).collect();
+            json!({ "type": types })
+        }
+        2 if variants[0]["type"] == "null" => {
+            let mut schema = variants.remove(1);
+            let other_type = schema["type"].clone();
+            schema["type"] = json!([other_type, "null"]);
+            if let Some(values) = schema.get_mut("enum").and_then(Value::as_array_mut) {
+                values.push(Value::Null);
+            }
+            schema
+        }
+        _ => json!({ "anyOf": variants }),
+    }
+}
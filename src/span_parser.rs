@@ -0,0 +1,229 @@
+// A JSON scanner that records where every node lives in the source text.
+// serde_json throws byte offsets away, so this is what lets the editor and
+// the tree point at the same node.
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::json_path::JsonPath;
+
+pub struct NodeSpan {
+    pub path: JsonPath,
+    // Byte range of the value itself
+    pub span: Range<usize>,
+    // Byte range of the quoted key, for object members
+    pub key_span: Option<Range<usize>>,
+}
+
+impl NodeSpan {
+    // The key (if any) through the end of the value
+    fn full_range(&self) -> Range<usize> {
+        let start = self.key_span.as_ref().map_or(self.span.start, |k| k.start);
+        start..self.span.end
+    }
+}
+
+#[derive(Default)]
+pub struct SpanIndex {
+    // Nodes in document order, parents before their children
+    nodes: Vec<NodeSpan>,
+    by_path: HashMap<JsonPath, usize>,
+}
+
+impl SpanIndex {
+    pub fn parse(text: &str) -> Result<SpanIndex, String> {
+        let mut parser = Parser {
+            text,
+            bytes: text.as_bytes(),
+            pos: 0,
+            index: SpanIndex::default(),
+        };
+        parser.skip_whitespace();
+        parser.parse_value(JsonPath::root(), None)?;
+        parser.skip_whitespace();
+        if parser.pos < parser.bytes.len() {
+            return Err(parser.error("trailing characters"));
+        }
+        Ok(parser.index)
+    }
+
+    pub fn find(&self, path: &JsonPath) -> Option<&NodeSpan> {
+        self.by_path.get(path).map(|&i| &self.nodes[i])
+    }
+
+    // The innermost node whose key or value contains the byte `offset`.
+    // A cursor sitting just after a value still counts as being on it.
+    pub fn node_at(&self, offset: usize) -> Option<&NodeSpan> {
+        self.nodes.iter().rev().find(|node| {
+            let range = node.full_range();
+            range.start <= offset && offset <= range.end
+        })
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+    index: SpanIndex,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> String {
+        let line = self.bytes[..self.pos].iter().filter(|&&byte| byte == b'\n').count() + 1;
+        format!("{} at line {}", message, line)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", byte as char)))
+        }
+    }
+
+    fn parse_value(&mut self, path: JsonPath, key_span: Option<Range<usize>>) -> Result<(), String> {
+        let start = self.pos;
+        // Reserve our slot first so parents stay ahead of their children
+        let slot = self.index.nodes.len();
+        self.index.nodes.push(NodeSpan {
+            path: path.clone(),
+            span: start..start,
+            key_span,
+        });
+        match self.peek() {
+            Some(b'{') => self.parse_object(&path)?,
+            Some(b'[') => self.parse_array(&path)?,
+            Some(b'"') => self.skip_string()?,
+            Some(b't') => self.skip_literal("true")?,
+            Some(b'f') => self.skip_literal("false")?,
+            Some(b'n') => self.skip_literal("null")?,
+            Some(b'-' | b'0'..=b'9') => self.skip_number(),
+            _ => return Err(self.error("expected a JSON value")),
+        }
+        self.index.nodes[slot].span = start..self.pos;
+        self.index.by_path.insert(path, slot);
+        Ok(())
+    }
+
+    fn parse_object(&mut self, path: &JsonPath) -> Result<(), String> {
+        self.expect(b'{')?;
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(());
+        }
+        loop {
+            self.skip_whitespace();
+            let key_start = self.pos;
+            self.skip_string()?;
+            let key_span = key_start..self.pos;
+            let key: String = serde_json::from_str(&self.text[key_span.clone()])
+                .map_err(|e| self.error(&e.to_string()))?;
+            self.skip_whitespace();
+            self.expect(b':')?;
+            self.skip_whitespace();
+            self.parse_value(path.child_key(&key), Some(key_span))?;
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn parse_array(&mut self, path: &JsonPath) -> Result<(), String> {
+        self.expect(b'[')?;
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(());
+        }
+        let mut index = 0;
+        loop {
+            self.skip_whitespace();
+            self.parse_value(path.child_index(index), None)?;
+            index += 1;
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn skip_string(&mut self) -> Result<(), String> {
+        self.expect(b'"')?;
+        while let Some(byte) = self.peek() {
+            self.pos += 1;
+            match byte {
+                // A trailing `\` in truncated input has nothing to escape
+                b'\\' => self.pos = (self.pos + 1).min(self.bytes.len()),
+                b'"' => return Ok(()),
+                _ => {}
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    fn skip_literal(&mut self, literal: &str) -> Result<(), String> {
+        if self.text[self.pos..].starts_with(literal) {
+            self.pos += literal.len();
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", literal)))
+        }
+    }
+
+    fn skip_number(&mut self) {
+        while matches!(self.peek(), Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')) {
+            self.pos += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_spans_of_nested_nodes() {
+        let text = r#"{"a": [1, {"b c": "x"}]}"#;
+        let index = SpanIndex::parse(text).unwrap();
+        let path = JsonPath::root().child_key("a").child_index(1).child_key("b c");
+        let node = index.find(&path).unwrap();
+        assert_eq!(&text[node.span.clone()], r#""x""#);
+        assert_eq!(&text[node.key_span.clone().unwrap()], r#""b c""#);
+        let first = index.node_at(text.find('1').unwrap()).unwrap();
+        assert_eq!(first.path, JsonPath::root().child_key("a").child_index(0));
+    }
+
+    #[test]
+    fn truncated_input_is_an_error() {
+        // Every prefix of these is invalid somewhere, and none may panic
+        for text in [r#"{"a": "x\"y", "b": [1, true]}"#, "{\"a\": \"\\\u{e9}\"}", "[\"\\\n"] {
+            for end in (0..text.len()).filter(|&end| text.is_char_boundary(end)) {
+                assert!(SpanIndex::parse(&text[..end]).is_err(), "{:?}", &text[..end]);
+            }
+        }
+        assert_eq!(SpanIndex::parse("{\"a\": \"\\").err().unwrap(), "unterminated string at line 1");
+    }
+}
@@ -0,0 +1,376 @@
+// SQL over the arrays in a document, run by an in-memory SQLite database.
+// Every array becomes a table named after its path (`$.data.orders` is
+// `data_orders`, a top-level array is `root`) with one column per object key,
+// in the order the keys first appear. Arrays of plain values, and elements
+// that aren't objects, use a `value` column. Nested objects and arrays are
+// stored as JSON text, so SQLite's `json_extract` and friends work on them.
+//
+// Every table has a `_row` column numbering its rows from 1. Arrays at the
+// same path inside the elements of another array share one table:
+// `$.orders[*].items` is `orders_items`, holding the items of every order,
+// and its `_parent` column is the `_row` of the order each item belongs to,
+// for joins like
+//   SELECT o.id, i.sku FROM orders o JOIN orders_items i ON i._parent = o._row
+use std::collections::HashSet;
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use serde_json::{Map, Value};
+
+use crate::binary::binary_json;
+use crate::jq_options::JqLimits;
+use crate::json_path::JsonPath;
+
+pub struct SqlTable {
+    pub name: String,
+    // JSONPath with `[*]` for the enclosing arrays, e.g. `$.orders[*].items`
+    pub path: String,
+    pub columns: Vec<String>,
+    pub rows: usize,
+}
+
+// Where a column's values come from
+#[derive(PartialEq)]
+enum ColumnSource {
+    Key(String),
+    // The element itself, when it isn't an object
+    Value,
+    // The row's number. A key named `rowid` would hide SQLite's own, so
+    // joins use this instead.
+    Row,
+    // The `_row` of the enclosing array's element
+    Parent,
+}
+
+// A table being gathered, with the rows' elements and parent rows. The
+// table's column names are given to the sources once all rows are in.
+struct TableRows<'a> {
+    table: SqlTable,
+    nested: bool,
+    sources: Vec<ColumnSource>,
+    elements: Vec<(&'a Value, Option<i64>)>,
+}
+
+// Every array in the document, parents before children
+pub fn find_tables(root: &Value) -> Vec<SqlTable> {
+    gather_tables(root).into_iter().map(|rows| rows.table).collect()
+}
+
+fn gather_tables(root: &Value) -> Vec<TableRows<'_>> {
+    let mut tables = Vec::new();
+    collect_rows(root, "$", &[], None, &mut tables);
+    let mut taken = HashSet::new();
+    for rows in &mut tables {
+        let name = &rows.table.name;
+        let base = if name.starts_with(|c: char| c.is_ascii_digit()) { format!("t_{}", name) } else { name.clone() };
+        rows.table.name = unique_name(&base, &mut taken);
+        rows.table.rows = rows.elements.len();
+        if rows.sources.is_empty() {
+            rows.sources.push(ColumnSource::Value);
+        }
+        if rows.nested {
+            rows.sources.insert(0, ColumnSource::Parent);
+        }
+        rows.sources.insert(0, ColumnSource::Row);
+        // Keys differing only in case, or a key named like `_row` or
+        // `_parent`, would otherwise be the same column
+        let mut taken_columns = HashSet::new();
+        rows.table.columns = rows
+            .sources
+            .iter()
+            .map(|source| match source {
+                ColumnSource::Key(key) => unique_name(key, &mut taken_columns),
+                ColumnSource::Value => unique_name("value", &mut taken_columns),
+                ColumnSource::Row => unique_name("_row", &mut taken_columns),
+                ColumnSource::Parent => unique_name("_parent", &mut taken_columns),
+            })
+            .collect();
+    }
+    tables
+}
+
+// `path` is the JSONPath of the node with `[*]` for array indices, `words`
+// the object keys along it, which make up the table name
+fn collect_rows<'a>(
+    value: &'a Value,
+    path: &str,
+    words: &[String],
+    parent: Option<i64>,
+    tables: &mut Vec<TableRows<'a>>,
+) {
+    match value {
+        Value::Array(items) => {
+            let index = match tables.iter().position(|rows| rows.table.path == path) {
+                Some(index) => index,
+                None => {
+                    tables.push(TableRows {
+                        table: SqlTable {
+                            name: if words.is_empty() { "root".to_string() } else { words.join("_") },
+                            path: path.to_string(),
+                            columns: Vec::new(),
+                            rows: 0,
+                        },
+                        nested: false,
+                        sources: Vec::new(),
+                        elements: Vec::new(),
+                    });
+                    tables.len() - 1
+                }
+            };
+            let child_path = format!("{}[*]", path);
+            for item in items {
+                let rows = &mut tables[index];
+                rows.nested |= parent.is_some();
+                rows.elements.push((item, parent));
+                let row = rows.elements.len() as i64;
+                let sources = &mut rows.sources;
+                match item {
+                    Value::Object(map) => {
+                        for key in map.keys() {
+                            if !sources.iter().any(|source| matches!(source, ColumnSource::Key(k) if k == key)) {
+                                sources.push(ColumnSource::Key(key.clone()));
+                            }
+                        }
+                    }
+                    _ if !sources.contains(&ColumnSource::Value) => sources.push(ColumnSource::Value),
+                    _ => {}
+                }
+                collect_rows(item, &child_path, words, Some(row), tables);
+            }
+        }
+        Value::Object(map) => {
+            for (key, child) in map {
+                let word: String = key.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+                let words = [words, &[word]].concat();
+                collect_rows(child, &child_path(path, key), &words, parent, tables);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn child_path(path: &str, key: &str) -> String {
+    // `$.key` or `$['key']`, spelled the way `JsonPath` spells it
+    let segment = JsonPath::root().child_key(key).to_jsonpath();
+    format!("{}{}", path, &segment[1..])
+}
+
+// A number is appended when two tables or columns end up with the same
+// name; SQLite compares names case-insensitively
+fn unique_name(base: &str, taken: &mut HashSet<String>) -> String {
+    let mut name = base.to_string();
+    let mut counter = 2;
+    while !taken.insert(name.to_lowercase()) {
+        name = format!("{}_{}", base, counter);
+        counter += 1;
+    }
+    name
+}
+
+pub fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn to_sql(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(n) => SqlValue::Integer(n),
+            None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        nested => SqlValue::Text(nested.to_string()),
+    }
+}
+
+// Text holding a JSON object or array (e.g. a nested value, or the output of
+// SQLite's JSON functions) is turned back into JSON
+fn from_sql(value: ValueRef) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(n) => Value::from(n),
+        ValueRef::Real(f) => serde_json::Number::from_f64(f).map_or(Value::Null, Value::Number),
+        ValueRef::Text(bytes) => {
+            let text = String::from_utf8_lossy(bytes);
+            let trimmed = text.trim_start();
+            if (trimmed.starts_with('{') || trimmed.starts_with('['))
+                && let Ok(nested) = serde_json::from_str::<Value>(&text)
+            {
+                return nested;
+            }
+            Value::String(text.into_owned())
+        }
+        ValueRef::Blob(bytes) => binary_json(bytes),
+    }
+}
+
+fn load_tables(connection: &rusqlite::Connection, root: &Value) -> Result<(), rusqlite::Error> {
+    for rows in gather_tables(root) {
+        let table = &rows.table;
+        let column_list: Vec<String> = table
+            .columns
+            .iter()
+            .zip(&rows.sources)
+            .map(|(column, source)| match source {
+                ColumnSource::Row => format!("{} INTEGER PRIMARY KEY", quote_identifier(column)),
+                _ => quote_identifier(column),
+            })
+            .collect();
+        connection.execute_batch(&format!(
+            "CREATE TABLE {} ({});",
+            quote_identifier(&table.name),
+            column_list.join(", ")
+        ))?;
+        let placeholders = vec!["?"; column_list.len()].join(", ");
+        let mut insert = connection.prepare(&format!(
+            "INSERT INTO {} VALUES ({})",
+            quote_identifier(&table.name),
+            placeholders
+        ))?;
+        for (index, (item, parent)) in rows.elements.into_iter().enumerate() {
+            let row: Vec<SqlValue> = rows
+                .sources
+                .iter()
+                .map(|source| match (source, item) {
+                    (ColumnSource::Row, _) => SqlValue::Integer(index as i64 + 1),
+                    (ColumnSource::Parent, _) => parent.map_or(SqlValue::Null, SqlValue::Integer),
+                    (ColumnSource::Key(key), Value::Object(map)) => map.get(key).map_or(SqlValue::Null, to_sql),
+                    (ColumnSource::Value, other) if !other.is_object() => to_sql(other),
+                    _ => SqlValue::Null,
+                })
+                .collect();
+            insert.execute(rusqlite::params_from_iter(row))?;
+        }
+    }
+    Ok(())
+}
+
+// Runs a query against the document's tables. Returns the result's column
+// names, in order, and its rows as an array of objects. Repeated column
+// names get a numeric suffix so no value is lost. `limits` stop a query that
+// runs too long or returns too much, like a recursive CTE without a LIMIT.
+pub fn run_sql(root: &Value, query: &str, limits: &JqLimits) -> Result<(Vec<String>, Value), String> {
+    let connection = rusqlite::Connection::open_in_memory().map_err(|e| e.to_string())?;
+    load_tables(&connection, root).map_err(|e| format!("Failed to load the document's arrays: {}", e))?;
+    // Queries only read; the tables are rebuilt for every run anyway
+    connection
+        .execute_batch("PRAGMA query_only = ON;")
+        .map_err(|e| e.to_string())?;
+    let deadline = limits.timeout.map(|timeout| Instant::now() + timeout);
+    let cancel = limits.cancel.clone();
+    connection
+        .progress_handler(
+            1000,
+            Some(move || cancel.load(Ordering::Relaxed) || deadline.is_some_and(|at| Instant::now() > at)),
+        )
+        .map_err(|e| e.to_string())?;
+    // Says why the query was interrupted, if it was
+    let stopped = || {
+        if limits.cancel.load(Ordering::Relaxed) {
+            Some("The query was cancelled.".to_string())
+        } else if let Some(timeout) = limits.timeout
+            && deadline.is_some_and(|at| Instant::now() > at)
+        {
+            Some(format!("The query was still running after {} seconds.", timeout.as_secs_f32()))
+        } else {
+            None
+        }
+    };
+    let mut statement = connection.prepare(query).map_err(|e| e.to_string())?;
+    let mut columns: Vec<String> = Vec::new();
+    for name in statement.column_names() {
+        let mut unique = name.to_string();
+        let mut counter = 2;
+        while columns.contains(&unique) {
+            unique = format!("{}_{}", name, counter);
+            counter += 1;
+        }
+        columns.push(unique);
+    }
+    let mut rows = statement.query([]).map_err(|e| e.to_string())?;
+    let mut result = Vec::new();
+    // Roughly the size of the result as JSON
+    let mut bytes = 0;
+    loop {
+        let row = match rows.next() {
+            Ok(Some(row)) => row,
+            Ok(None) => break,
+            Err(e) => return Err(stopped().unwrap_or_else(|| e.to_string())),
+        };
+        let mut object = Map::new();
+        for (index, column) in columns.iter().enumerate() {
+            let value = row.get_ref(index).map_err(|e| e.to_string())?;
+            bytes += column.len()
+                + match value {
+                    ValueRef::Text(data) | ValueRef::Blob(data) => data.len(),
+                    _ => 8,
+                };
+            object.insert(column.clone(), from_sql(value));
+        }
+        if let Some(max) = limits.max_output_bytes
+            && bytes > max
+        {
+            return Err(format!("The query returned more than {} bytes.", max));
+        }
+        result.push(Value::Object(object));
+    }
+    Ok((columns, Value::Array(result)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicBool;
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn children_join_on_the_row_column() {
+        let document = json!({
+            "orders": [
+                { "rowid": "A-1", "items": [{ "sku": "x" }, { "sku": "y" }] },
+                { "rowid": "A-2", "items": [{ "sku": "z" }] }
+            ]
+        });
+        let query = "SELECT o.rowid AS id, i.sku FROM orders o JOIN orders_items i ON i._parent = o._row ORDER BY i._row";
+        let (columns, rows) = run_sql(&document, query, &JqLimits::default()).unwrap();
+        assert_eq!(columns, ["id", "sku"]);
+        assert_eq!(
+            rows,
+            json!([{ "id": "A-1", "sku": "x" }, { "id": "A-1", "sku": "y" }, { "id": "A-2", "sku": "z" }])
+        );
+    }
+
+    #[test]
+    fn keys_never_share_a_column() {
+        let document = json!([{ "Name": 1, "name": 2, "_row": 3 }]);
+        let tables = find_tables(&document);
+        assert_eq!(tables[0].columns, ["_row", "Name", "_row_2", "name_2"]);
+    }
+
+    #[test]
+    fn limits_stop_runaway_queries() {
+        let document = json!([1, 2, 3]);
+        let endless = "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c) SELECT x FROM c";
+        let limits = JqLimits { timeout: Some(Duration::from_millis(200)), ..Default::default() };
+        let error = run_sql(&document, endless, &limits).unwrap_err();
+        assert!(error.contains("still running"), "{}", error);
+
+        let limits = JqLimits { max_output_bytes: Some(10_000), ..Default::default() };
+        let error = run_sql(&document, endless, &limits).unwrap_err();
+        assert!(error.contains("more than 10000 bytes"), "{}", error);
+
+        let limits = JqLimits { cancel: Arc::new(AtomicBool::new(true)), ..Default::default() };
+        let error = run_sql(&document, endless, &limits).unwrap_err();
+        assert!(error.contains("cancelled"), "{}", error);
+
+        let bounded = format!("{} LIMIT 10", endless);
+        let (_, rows) = run_sql(&document, &bounded, &JqLimits::default()).unwrap();
+        assert_eq!(rows.as_array().map(Vec::len), Some(10));
+    }
+}
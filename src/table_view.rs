@@ -0,0 +1,366 @@
+// Table rendering for arrays, mostly arrays of similar objects. Columns are
+// the union of the elements' keys; the table remembers its column order,
+// hidden columns, sort and filters per array path.
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use eframe::egui;
+use egui::Color32;
+use serde_json::Value;
+
+use crate::json_path::JsonPath;
+use crate::tree_view::{TreeAction, TreeContext, render_json_value};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Column {
+    Key(String),
+    // Elements that aren't objects are shown whole in this column
+    Element,
+}
+
+impl Column {
+    fn label(&self) -> &str {
+        match self {
+            Column::Key(key) => key,
+            Column::Element => "(value)",
+        }
+    }
+
+    fn cell<'a>(&self, element: &'a Value) -> Option<&'a Value> {
+        match (self, element) {
+            (Column::Key(key), Value::Object(map)) => map.get(key),
+            (Column::Element, Value::Object(_)) => None,
+            (Column::Element, other) => Some(other),
+            _ => None,
+        }
+    }
+
+    fn cell_path(&self, element_path: &JsonPath) -> JsonPath {
+        match self {
+            Column::Key(key) => element_path.child_key(key),
+            Column::Element => element_path.clone(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct TableState {
+    columns: Vec<Column>,
+    hidden: HashSet<Column>,
+    // Column and whether it's ascending
+    sort: Option<(Column, bool)>,
+    filters: HashMap<Column, String>,
+    // Why the last Save… failed
+    save_error: Option<String>,
+}
+
+impl TableState {
+    // A table whose first columns are `keys`, in that order
+    pub fn with_columns(keys: &[String]) -> TableState {
+        TableState {
+            columns: keys.iter().cloned().map(Column::Key).collect(),
+            ..Default::default()
+        }
+    }
+
+    // Keeps the user's column order, dropping columns that disappeared and
+    // appending new ones in the order they first appear in the array.
+    fn sync_columns(&mut self, arr: &[Value]) {
+        let inferred = infer_columns(arr);
+        self.columns.retain(|c| inferred.contains(c));
+        for column in inferred {
+            if !self.columns.contains(&column) {
+                self.columns.push(column);
+            }
+        }
+    }
+
+    fn visible_columns(&self) -> Vec<Column> {
+        self.columns
+            .iter()
+            .filter(|c| !self.hidden.contains(c))
+            .cloned()
+            .collect()
+    }
+
+    // Indices of the elements that pass the filters, in sort order
+    fn visible_rows(&self, arr: &[Value]) -> Vec<usize> {
+        let filters: Vec<(&Column, String)> = self
+            .filters
+            .iter()
+            .filter(|(c, f)| !f.is_empty() && !self.hidden.contains(c))
+            .map(|(c, f)| (c, f.to_lowercase()))
+            .collect();
+        let mut rows: Vec<usize> = (0..arr.len())
+            .filter(|&i| {
+                filters.iter().all(|(column, filter)| {
+                    column
+                        .cell(&arr[i])
+                        .is_some_and(|v| cell_text(v).to_lowercase().contains(filter))
+                })
+            })
+            .collect();
+        if let Some((column, ascending)) = &self.sort {
+            rows.sort_by(|&a, &b| {
+                let ordering = compare_cells(column.cell(&arr[a]), column.cell(&arr[b]));
+                if *ascending { ordering } else { ordering.reverse() }
+            });
+        }
+        rows
+    }
+
+    fn toggle_sort(&mut self, column: &Column) {
+        self.sort = match &self.sort {
+            Some((c, true)) if c == column => Some((column.clone(), false)),
+            Some((c, false)) if c == column => None,
+            _ => Some((column.clone(), true)),
+        };
+    }
+
+    fn move_column(&mut self, column: &Column, offset: isize) {
+        // Move past hidden columns so the change is visible
+        let visible = self.visible_columns();
+        let Some(pos) = visible.iter().position(|c| c == column) else {
+            return;
+        };
+        let Some(neighbour) = pos.checked_add_signed(offset).and_then(|p| visible.get(p)) else {
+            return;
+        };
+        let a = self.columns.iter().position(|c| c == column);
+        let b = self.columns.iter().position(|c| c == neighbour);
+        if let (Some(a), Some(b)) = (a, b) {
+            self.columns.swap(a, b);
+        }
+    }
+}
+
+fn infer_columns(arr: &[Value]) -> Vec<Column> {
+    let mut columns = Vec::new();
+    let mut seen = HashSet::new();
+    for element in arr {
+        match element {
+            Value::Object(map) => {
+                for key in map.keys() {
+                    if seen.insert(Column::Key(key.clone())) {
+                        columns.push(Column::Key(key.clone()));
+                    }
+                }
+            }
+            _ => {
+                if seen.insert(Column::Element) {
+                    columns.push(Column::Element);
+                }
+            }
+        }
+    }
+    columns
+}
+
+// Plain text for a cell: strings unquoted, everything else as compact JSON
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+// Missing < null < booleans < numbers < strings < arrays < objects
+fn compare_cells(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    fn rank(value: Option<&Value>) -> u8 {
+        match value {
+            None => 0,
+            Some(Value::Null) => 1,
+            Some(Value::Bool(_)) => 2,
+            Some(Value::Number(_)) => 3,
+            Some(Value::String(_)) => 4,
+            Some(Value::Array(_)) => 5,
+            Some(Value::Object(_)) => 6,
+        }
+    }
+    match (a, b) {
+        (Some(Value::Bool(x)), Some(Value::Bool(y))) => x.cmp(y),
+        (Some(Value::Number(x)), Some(Value::Number(y))) => {
+            let (x, y) = (x.as_f64().unwrap_or(0.0), y.as_f64().unwrap_or(0.0));
+            x.total_cmp(&y)
+        }
+        (Some(Value::String(x)), Some(Value::String(y))) => x.cmp(y),
+        (Some(Value::Array(x)), Some(Value::Array(y))) => x.len().cmp(&y.len()),
+        (Some(Value::Object(x)), Some(Value::Object(y))) => x.len().cmp(&y.len()),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum ExportFormat {
+    Csv,
+    Tsv,
+}
+
+fn export_field(text: &str, format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Csv => {
+            if text.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", text.replace('"', "\"\""))
+            } else {
+                text.to_string()
+            }
+        }
+        ExportFormat::Tsv => text
+            .replace('\\', "\\\\")
+            .replace('\t', "\\t")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r"),
+    }
+}
+
+// The visible columns and rows, in display order, with a header line
+fn export_table(state: &TableState, arr: &[Value], format: ExportFormat) -> String {
+    let separator = match format {
+        ExportFormat::Csv => ",",
+        ExportFormat::Tsv => "\t",
+    };
+    let columns = state.visible_columns();
+    let mut out = columns
+        .iter()
+        .map(|c| export_field(c.label(), format))
+        .collect::<Vec<_>>()
+        .join(separator);
+    out.push('\n');
+    for row in state.visible_rows(arr) {
+        let line = columns
+            .iter()
+            .map(|c| c.cell(&arr[row]).map(cell_text).unwrap_or_default())
+            .map(|text| export_field(&text, format))
+            .collect::<Vec<_>>()
+            .join(separator);
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+pub fn render_table(ui: &mut egui::Ui, arr: &[Value], path: &JsonPath, ctx: &mut TreeContext) {
+    let mut state = ctx.tables.remove(path).unwrap_or_default();
+    state.sync_columns(arr);
+    let columns = state.visible_columns();
+    let rows = state.visible_rows(arr);
+
+    ui.horizontal(|ui| {
+        ui.label(format!("{} of {} rows", rows.len(), arr.len()));
+        ui.menu_button("Columns", |ui| {
+            for column in &state.columns {
+                let mut shown = !state.hidden.contains(column);
+                if ui.checkbox(&mut shown, column.label()).changed() {
+                    if shown {
+                        state.hidden.remove(column);
+                    } else {
+                        state.hidden.insert(column.clone());
+                    }
+                }
+            }
+            if ui.button("Show all").clicked() {
+                state.hidden.clear();
+            }
+        });
+        if ui.button("Copy as CSV").clicked() {
+            ui.ctx().copy_text(export_table(&state, arr, ExportFormat::Csv));
+        }
+        if ui.button("Copy as TSV").clicked() {
+            ui.ctx().copy_text(export_table(&state, arr, ExportFormat::Tsv));
+        }
+        ui.menu_button("Save…", |ui| {
+            for (label, format, extension) in [("CSV", ExportFormat::Csv, "csv"), ("TSV", ExportFormat::Tsv, "tsv")] {
+                if ui.button(format!("As {}…", label)).clicked() {
+                    ui.close_menu();
+                    if let Some(file) = rfd::FileDialog::new()
+                        .add_filter(label, &[extension])
+                        .set_file_name(format!("table.{}", extension))
+                        .save_file()
+                    {
+                        state.save_error = std::fs::write(&file, export_table(&state, arr, format))
+                            .err()
+                            .map(|e| format!("Failed to save {}: {}", file.display(), e));
+                    }
+                }
+            }
+        });
+    });
+    if let Some(error) = &state.save_error {
+        ui.colored_label(Color32::RED, error);
+    }
+
+    egui::Grid::new(("table", path))
+        .striped(true)
+        .min_col_width(40.0)
+        .show(ui, |ui| {
+            ui.label("#");
+            for column in &columns {
+                let arrow = match &state.sort {
+                    Some((c, true)) if c == column => " ^",
+                    Some((c, false)) if c == column => " v",
+                    _ => "",
+                };
+                let header = ui.add(
+                    egui::Button::new(
+                        egui::RichText::new(format!("{}{}", column.label(), arrow))
+                            .strong()
+                            .color(Color32::LIGHT_BLUE),
+                    )
+                    .frame(false),
+                );
+                if header.clicked() {
+                    state.toggle_sort(column);
+                }
+                header.context_menu(|ui| {
+                    if ui.button("Move left").clicked() {
+                        state.move_column(column, -1);
+                        ui.close_menu();
+                    }
+                    if ui.button("Move right").clicked() {
+                        state.move_column(column, 1);
+                        ui.close_menu();
+                    }
+                    if ui.button("Hide").clicked() {
+                        state.hidden.insert(column.clone());
+                        ui.close_menu();
+                    }
+                });
+            }
+            ui.end_row();
+
+            ui.label("");
+            for column in &columns {
+                let filter = state.filters.entry(column.clone()).or_default();
+                ui.add(
+                    egui::TextEdit::singleline(filter)
+                        .hint_text("filter")
+                        .desired_width(80.0),
+                );
+            }
+            ui.end_row();
+
+            for row in rows {
+                let element_path = path.child_index(row);
+                let index_label = ui.add(
+                    egui::Label::new(egui::RichText::new(row.to_string()).weak())
+                        .sense(egui::Sense::click()),
+                );
+                if index_label.clicked() {
+                    ctx.actions.push(TreeAction::Select(element_path.clone()));
+                }
+                for column in &columns {
+                    match column.cell(&arr[row]) {
+                        Some(cell) => {
+                            render_json_value(ui, None, cell, &column.cell_path(&element_path), ctx)
+                        }
+                        None => {
+                            ui.label(egui::RichText::new("-").weak());
+                        }
+                    }
+                }
+                ui.end_row();
+            }
+        });
+
+    ctx.tables.insert(path.clone(), state);
+}
@@ -0,0 +1,313 @@
+// Structural edits made from the tree view. Each edit is applied to a parsed
+// `Value` and addressed by a `JsonPath`, so the tree never needs to hold a
+// mutable borrow of the document while it is being drawn.
+use serde_json::{Map, Value};
+
+use crate::json_path::{JsonPath, PathSegment};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValueKind {
+    String,
+    Number,
+    Boolean,
+    Null,
+    Object,
+    Array,
+}
+
+impl ValueKind {
+    pub const ALL: [ValueKind; 6] = [
+        ValueKind::String,
+        ValueKind::Number,
+        ValueKind::Boolean,
+        ValueKind::Null,
+        ValueKind::Object,
+        ValueKind::Array,
+    ];
+
+    pub fn of(value: &Value) -> Self {
+        match value {
+            Value::String(_) => ValueKind::String,
+            Value::Number(_) => ValueKind::Number,
+            Value::Bool(_) => ValueKind::Boolean,
+            Value::Null => ValueKind::Null,
+            Value::Object(_) => ValueKind::Object,
+            Value::Array(_) => ValueKind::Array,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ValueKind::String => "String",
+            ValueKind::Number => "Number",
+            ValueKind::Boolean => "Boolean",
+            ValueKind::Null => "Null",
+            ValueKind::Object => "Object",
+            ValueKind::Array => "Array",
+        }
+    }
+
+    // Converts `value` to this kind, keeping as much of the original as makes sense.
+    pub fn convert(self, value: &Value) -> Value {
+        match self {
+            ValueKind::String => match value {
+                Value::String(s) => Value::String(s.clone()),
+                Value::Null => Value::String(String::new()),
+                other => Value::String(other.to_string()),
+            },
+            ValueKind::Number => match value {
+                Value::Number(n) => Value::Number(n.clone()),
+                Value::String(s) => s.trim().parse().map(Value::Number).unwrap_or(Value::from(0)),
+                Value::Bool(b) => Value::from(*b as u8),
+                _ => Value::from(0),
+            },
+            ValueKind::Boolean => Value::Bool(match value {
+                Value::Bool(b) => *b,
+                Value::Null => false,
+                Value::Number(n) => n.as_f64() != Some(0.0),
+                Value::String(s) => !s.is_empty() && s != "false",
+                Value::Array(a) => !a.is_empty(),
+                Value::Object(o) => !o.is_empty(),
+            }),
+            ValueKind::Null => Value::Null,
+            ValueKind::Object => match value {
+                Value::Object(o) => Value::Object(o.clone()),
+                Value::Array(a) => Value::Object(
+                    a.iter()
+                        .enumerate()
+                        .map(|(i, v)| (i.to_string(), v.clone()))
+                        .collect(),
+                ),
+                _ => Value::Object(Map::new()),
+            },
+            ValueKind::Array => match value {
+                Value::Array(a) => Value::Array(a.clone()),
+                Value::Object(o) => Value::Array(o.values().cloned().collect()),
+                Value::Null => Value::Array(Vec::new()),
+                other => Value::Array(vec![other.clone()]),
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum EditOp {
+    SetValue { path: JsonPath, value: Value },
+    RenameKey { path: JsonPath, new_key: String },
+    Remove { path: JsonPath },
+    // Adds a `null` member with a fresh key to the object at `path`
+    AddKey { path: JsonPath },
+    // Inserts `null` into the array at `path` before `index`
+    InsertElement { path: JsonPath, index: usize },
+    Duplicate { path: JsonPath },
+    ChangeType { path: JsonPath, kind: ValueKind },
+}
+
+pub fn apply_edit(root: &mut Value, op: EditOp) -> Result<(), String> {
+    match op {
+        EditOp::SetValue { path, value } => {
+            *node_mut(root, &path)? = value;
+        }
+        EditOp::ChangeType { path, kind } => {
+            let node = node_mut(root, &path)?;
+            *node = kind.convert(node);
+        }
+        EditOp::RenameKey { path, new_key } => {
+            let Some(PathSegment::Key(old_key)) = path.last() else {
+                return Err("Only object members can be renamed.".to_string());
+            };
+            if *old_key == new_key {
+                return Ok(());
+            }
+            let map = parent_object(root, &path)?;
+            if map.contains_key(&new_key) {
+                return Err(format!("Key \"{}\" already exists.", new_key));
+            }
+            let value = map
+                .remove(old_key)
+                .ok_or_else(|| format!("Key \"{}\" not found.", old_key))?;
+            map.insert(new_key, value);
+        }
+        EditOp::Remove { path } => match path.last() {
+            Some(PathSegment::Key(key)) => {
+                parent_object(root, &path)?
+                    .remove(key)
+                    .ok_or_else(|| format!("Key \"{}\" not found.", key))?;
+            }
+            Some(PathSegment::Index(index)) => {
+                let arr = parent_array(root, &path)?;
+                if *index >= arr.len() {
+                    return Err(format!("Index {} out of bounds.", index));
+                }
+                arr.remove(*index);
+            }
+            None => return Err("The root value cannot be removed.".to_string()),
+        },
+        EditOp::AddKey { path } => {
+            let Value::Object(map) = node_mut(root, &path)? else {
+                return Err("Keys can only be added to objects.".to_string());
+            };
+            let key = unique_key(map, "new_key");
+            map.insert(key, Value::Null);
+        }
+        EditOp::InsertElement { path, index } => {
+            let Value::Array(arr) = node_mut(root, &path)? else {
+                return Err("Elements can only be inserted into arrays.".to_string());
+            };
+            arr.insert(index.min(arr.len()), Value::Null);
+        }
+        EditOp::Duplicate { path } => match path.last() {
+            Some(PathSegment::Key(key)) => {
+                let map = parent_object(root, &path)?;
+                let value = map
+                    .get(key)
+                    .cloned()
+                    .ok_or_else(|| format!("Key \"{}\" not found.", key))?;
+                let copy_key = unique_key(map, &format!("{}_copy", key));
+                map.insert(copy_key, value);
+            }
+            Some(PathSegment::Index(index)) => {
+                let arr = parent_array(root, &path)?;
+                let value = arr
+                    .get(*index)
+                    .cloned()
+                    .ok_or_else(|| format!("Index {} out of bounds.", index))?;
+                arr.insert(index + 1, value);
+            }
+            None => return Err("The root value cannot be duplicated.".to_string()),
+        },
+    }
+    Ok(())
+}
+
+fn node_mut<'a>(root: &'a mut Value, path: &JsonPath) -> Result<&'a mut Value, String> {
+    root.pointer_mut(&path.to_json_pointer())
+        .ok_or_else(|| format!("Path {} no longer exists.", path.to_jsonpath()))
+}
+
+fn parent_object<'a>(root: &'a mut Value, path: &JsonPath) -> Result<&'a mut Map<String, Value>, String> {
+    let parent = path.parent().unwrap_or_default();
+    match node_mut(root, &parent)? {
+        Value::Object(map) => Ok(map),
+        _ => Err(format!("{} is not an object.", parent.to_jsonpath())),
+    }
+}
+
+fn parent_array<'a>(root: &'a mut Value, path: &JsonPath) -> Result<&'a mut Vec<Value>, String> {
+    let parent = path.parent().unwrap_or_default();
+    match node_mut(root, &parent)? {
+        Value::Array(arr) => Ok(arr),
+        _ => Err(format!("{} is not an array.", parent.to_jsonpath())),
+    }
+}
+
+fn unique_key(map: &Map<String, Value>, base: &str) -> String {
+    if !map.contains_key(base) {
+        return base.to_string();
+    }
+    (2..)
+        .map(|n| format!("{}_{}", base, n))
+        .find(|key| !map.contains_key(key))
+        .unwrap_or_else(|| base.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn key(name: &str) -> JsonPath {
+        JsonPath::root().child_key(name)
+    }
+
+    fn edited(mut document: Value, op: EditOp) -> Result<Value, String> {
+        apply_edit(&mut document, op).map(|()| document)
+    }
+
+    #[test]
+    fn renames_keys() {
+        let document = json!({ "a": 1, "b": 2, "a/b~c": { "x": 3 } });
+        assert_eq!(
+            edited(document.clone(), EditOp::RenameKey { path: key("a"), new_key: "c".to_string() }),
+            Ok(json!({ "c": 1, "b": 2, "a/b~c": { "x": 3 } }))
+        );
+        // Keys are addressed through escaped JSON Pointers
+        let path = key("a/b~c").child_key("x");
+        assert_eq!(
+            edited(document.clone(), EditOp::RenameKey { path, new_key: "y".to_string() }),
+            Ok(json!({ "a": 1, "b": 2, "a/b~c": { "y": 3 } }))
+        );
+        assert_eq!(
+            edited(document.clone(), EditOp::RenameKey { path: key("a"), new_key: "a".to_string() }),
+            Ok(document.clone())
+        );
+        let mut unchanged = document.clone();
+        let error = apply_edit(&mut unchanged, EditOp::RenameKey { path: key("a"), new_key: "b".to_string() });
+        assert_eq!(error, Err("Key \"b\" already exists.".to_string()));
+        assert_eq!(unchanged, document);
+        let element = JsonPath::root().child_index(0);
+        assert!(edited(json!([1]), EditOp::RenameKey { path: element, new_key: "x".to_string() }).is_err());
+    }
+
+    #[test]
+    fn inserts_and_duplicates_elements() {
+        let document = json!({ "list": [1, 2] });
+        assert_eq!(
+            edited(document.clone(), EditOp::InsertElement { path: key("list"), index: 2 }),
+            Ok(json!({ "list": [1, 2, null] }))
+        );
+        // Past the end appends
+        assert_eq!(
+            edited(document.clone(), EditOp::InsertElement { path: key("list"), index: 9 }),
+            Ok(json!({ "list": [1, 2, null] }))
+        );
+        assert_eq!(
+            edited(document.clone(), EditOp::InsertElement { path: key("list"), index: 0 }),
+            Ok(json!({ "list": [null, 1, 2] }))
+        );
+        assert_eq!(
+            edited(document.clone(), EditOp::Duplicate { path: key("list").child_index(0) }),
+            Ok(json!({ "list": [1, 1, 2] }))
+        );
+        assert_eq!(
+            edited(document.clone(), EditOp::Duplicate { path: key("list") }),
+            Ok(json!({ "list": [1, 2], "list_copy": [1, 2] }))
+        );
+        assert!(edited(document, EditOp::InsertElement { path: JsonPath::root(), index: 0 }).is_err());
+    }
+
+    #[test]
+    fn adds_keys_without_overwriting() {
+        let document = json!({ "new_key": 1 });
+        assert_eq!(
+            edited(document, EditOp::AddKey { path: JsonPath::root() }),
+            Ok(json!({ "new_key": 1, "new_key_2": null }))
+        );
+    }
+
+    #[test]
+    fn changes_types() {
+        let change = |value: Value, kind| edited(json!({ "v": value }), EditOp::ChangeType { path: key("v"), kind });
+        assert_eq!(change(json!(12), ValueKind::String), Ok(json!({ "v": "12" })));
+        assert_eq!(change(json!(" 12 "), ValueKind::Number), Ok(json!({ "v": 12 })));
+        assert_eq!(change(json!("twelve"), ValueKind::Number), Ok(json!({ "v": 0 })));
+        assert_eq!(change(json!("false"), ValueKind::Boolean), Ok(json!({ "v": false })));
+        assert_eq!(change(json!(["a", "b"]), ValueKind::Object), Ok(json!({ "v": { "0": "a", "1": "b" } })));
+        assert_eq!(change(json!({ "x": 1 }), ValueKind::Array), Ok(json!({ "v": [1] })));
+        assert_eq!(change(json!(true), ValueKind::Null), Ok(json!({ "v": null })));
+    }
+
+    #[test]
+    fn removes_nodes_but_not_the_root() {
+        let document = json!({ "a": 1, "list": [1, 2] });
+        assert_eq!(edited(document.clone(), EditOp::Remove { path: key("a") }), Ok(json!({ "list": [1, 2] })));
+        assert_eq!(
+            edited(document.clone(), EditOp::Remove { path: key("list").child_index(1) }),
+            Ok(json!({ "a": 1, "list": [1] }))
+        );
+        assert!(edited(document.clone(), EditOp::Remove { path: key("list").child_index(2) }).is_err());
+        assert!(edited(document.clone(), EditOp::Remove { path: key("missing") }).is_err());
+        assert!(edited(document, EditOp::Remove { path: JsonPath::root() }).is_err());
+    }
+}
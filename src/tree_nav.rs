@@ -0,0 +1,94 @@
+// Keyboard navigation over the tree view. The tree is flattened into the list
+// of rows currently on screen (children of collapsed containers are skipped),
+// which is what arrow keys, Home/End and type-ahead move through.
+use serde_json::Value;
+
+use crate::json_path::JsonPath;
+use crate::tree_view::ExpansionState;
+
+pub struct VisibleNode {
+    pub path: JsonPath,
+    pub key: Option<String>,
+    pub is_container: bool,
+}
+
+pub fn visible_nodes(root: &Value, expansion: &ExpansionState) -> Vec<VisibleNode> {
+    let mut nodes = Vec::new();
+    collect_visible(root, None, JsonPath::root(), expansion, &mut nodes);
+    nodes
+}
+
+fn collect_visible(
+    value: &Value,
+    key: Option<&str>,
+    path: JsonPath,
+    expansion: &ExpansionState,
+    nodes: &mut Vec<VisibleNode>,
+) {
+    let is_container = matches!(value, Value::Object(_) | Value::Array(_));
+    let open = is_container && expansion.is_open(&path);
+    nodes.push(VisibleNode {
+        path: path.clone(),
+        key: key.map(str::to_string),
+        is_container,
+    });
+    if !open {
+        return;
+    }
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                collect_visible(child, Some(key), path.child_key(key), expansion, nodes);
+            }
+        }
+        Value::Array(arr) => {
+            for (index, child) in arr.iter().enumerate() {
+                collect_visible(child, None, path.child_index(index), expansion, nodes);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Keys typed in quick succession are collected into one prefix, like in a
+// file explorer: typing "na" jumps to "name" rather than "n..." then "a...".
+#[derive(Default)]
+pub struct TypeAhead {
+    buffer: String,
+    last_input_time: f64,
+}
+
+impl TypeAhead {
+    const RESET_AFTER_SECONDS: f64 = 1.0;
+
+    pub fn push(&mut self, text: &str, now: f64) -> &str {
+        if now - self.last_input_time > Self::RESET_AFTER_SECONDS {
+            self.buffer.clear();
+        }
+        self.buffer.push_str(&text.to_lowercase());
+        self.last_input_time = now;
+        &self.buffer
+    }
+}
+
+// The next visible node after `current` whose key starts with `prefix`,
+// wrapping around. A node matching a single-character prefix is skipped
+// when it is the current one, so repeating a letter cycles through matches.
+pub fn find_by_prefix<'a>(
+    nodes: &'a [VisibleNode],
+    current: Option<usize>,
+    prefix: &str,
+) -> Option<&'a VisibleNode> {
+    let start = match current {
+        Some(index) if prefix.chars().count() == 1 => index + 1,
+        Some(index) => index,
+        None => 0,
+    };
+    (0..nodes.len())
+        .map(|offset| &nodes[(start + offset) % nodes.len()])
+        .find(|node| {
+            node.key
+                .as_ref()
+                .is_some_and(|key| key.to_lowercase().starts_with(prefix))
+        })
+}
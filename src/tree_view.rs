@@ -0,0 +1,684 @@
+// The collapsible tree shown in the right-hand column. Rendering never mutates
+// the document directly: anything that changes app state is pushed onto
+// `TreeContext::actions` and applied by the app once the tree has been drawn.
+use eframe::egui;
+use egui::TextStyle;
+use egui::text::{LayoutJob, TextFormat};
+use egui::{CollapsingHeader, Color32};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::binary;
+use crate::json_path::{JsonPath, PathSegment};
+use crate::table_view::{TableState, render_table};
+use crate::tree_edit::{EditOp, ValueKind};
+
+fn create_highlighted_layout_sections(
+    ui: &egui::Ui,
+    full_text_content: &str, // Renamed to clearly indicate it's the full content
+    search_query: &str,
+    default_text_color: Color32,
+    is_strong: bool,
+) -> (String, Vec<egui::text::LayoutSection>) {
+    // Returns a tuple: (full_text, sections)
+    let mut sections = Vec::new();
+    // let current_text_byte_offset = 0; // Tracks byte offset for Ranges
+
+    let base_font_id = TextStyle::Body.resolve(ui.style());
+
+    if is_strong {
+        //     base_font_id.weight = FontWeight::Bold;
+    }
+
+    let default_format = TextFormat {
+        font_id: base_font_id.clone(),
+        extra_letter_spacing: 0.0, // Add this line
+        line_height: None,         // Add this line
+        color: default_text_color,
+        italics: false,
+        strikethrough: Default::default(), // Use Default for Stroke
+        underline: Default::default(),     // Use Default for Stroke
+        background: Color32::TRANSPARENT,
+        valign: egui::Align::Center,
+    };
+
+    let highlighted_format = TextFormat {
+        font_id: base_font_id.clone(),
+        extra_letter_spacing: 0.0, // Add this line
+        line_height: None,         // Add this line
+        color: Color32::BLACK,
+        background: Color32::from_rgb(255, 255, 0),
+        italics: false,
+        strikethrough: Default::default(),
+        underline: Default::default(),
+        valign: egui::Align::Center,
+    };
+
+    if search_query.is_empty() {
+        // If no search query, the entire text is one section with default format
+        sections.push(egui::text::LayoutSection {
+            leading_space: 0.0,
+            byte_range: 0..full_text_content.len(),
+            format: default_format,
+        });
+        return (full_text_content.to_string(), sections);
+    }
+
+    let lower_full_text = full_text_content.to_lowercase();
+    let lower_search_query = search_query.to_lowercase();
+    let mut last_end_byte = 0;
+
+    for (start_byte, matched_str) in lower_full_text.match_indices(&lower_search_query) {
+        let matched_len_bytes = matched_str.len(); // Get the byte length of the matched string slice
+
+        // Add the part before the match (if any)
+        if start_byte > last_end_byte {
+            sections.push(egui::text::LayoutSection {
+                leading_space: 0.0,
+                byte_range: last_end_byte..start_byte,
+                format: default_format.clone(),
+            });
+        }
+
+        // Add the matched part with highlight
+        sections.push(egui::text::LayoutSection {
+            leading_space: 0.0,
+            byte_range: start_byte..(start_byte + matched_len_bytes), // Use the byte length here
+            format: highlighted_format.clone(),
+        });
+
+        last_end_byte = start_byte + matched_len_bytes;
+    }
+
+    // Add the remaining part after the last match (if any)
+    if last_end_byte < full_text_content.len() {
+        sections.push(egui::text::LayoutSection {
+            leading_space: 0.0,
+            byte_range: last_end_byte..full_text_content.len(),
+            format: default_format,
+        });
+    }
+
+    (full_text_content.to_string(), sections)
+}
+
+fn highlighted_label(
+    ui: &mut egui::Ui,
+    text: &str,
+    search_query: &str,
+    color: Color32,
+    is_strong: bool,
+) -> egui::Response {
+    let (full_text, layout_sections) =
+        create_highlighted_layout_sections(ui, text, search_query, color, is_strong);
+    let layout_job = LayoutJob {
+        text: full_text,
+        sections: layout_sections,
+        // wrap: true,
+        ..Default::default()
+    };
+    ui.add(egui::Label::new(egui::WidgetText::LayoutJob(layout_job)).sense(egui::Sense::click()))
+}
+
+// Which containers are open, keyed by path so it survives re-formatting and
+// jq runs. Nodes follow the depth rule unless they've been toggled by hand.
+#[derive(Default)]
+pub struct ExpansionState {
+    // Containers shallower than this are open; `None` means everything is open
+    default_depth: Option<usize>,
+    overrides: HashMap<JsonPath, bool>,
+}
+
+impl ExpansionState {
+    pub fn is_open(&self, path: &JsonPath) -> bool {
+        self.overrides.get(path).copied().unwrap_or_else(|| {
+            self.default_depth
+                .is_none_or(|depth| path.segments().len() < depth)
+        })
+    }
+
+    pub fn set_open(&mut self, path: &JsonPath, open: bool) {
+        self.overrides.insert(path.clone(), open);
+    }
+
+    pub fn expand_all(&mut self) {
+        self.default_depth = None;
+        self.overrides.clear();
+    }
+
+    pub fn collapse_all(&mut self) {
+        self.expand_to_depth(0);
+    }
+
+    pub fn expand_to_depth(&mut self, depth: usize) {
+        self.default_depth = Some(depth);
+        self.overrides.clear();
+    }
+
+    // Opens every ancestor of `path` so the node itself is visible
+    pub fn reveal(&mut self, path: &JsonPath) {
+        for len in 0..path.segments().len() {
+            self.set_open(&path.truncated(len), true);
+        }
+    }
+
+    // Opens or closes the container at `path` and every container below it
+    pub fn set_subtree_open(&mut self, root: &Value, path: &JsonPath, open: bool) {
+        if let Some(node) = root.pointer(&path.to_json_pointer()) {
+            self.set_open_recursive(node, path, open);
+        }
+    }
+
+    fn set_open_recursive(&mut self, value: &Value, path: &JsonPath, open: bool) {
+        match value {
+            Value::Object(map) => {
+                self.set_open(path, open);
+                for (key, child) in map {
+                    self.set_open_recursive(child, &path.child_key(key), open);
+                }
+            }
+            Value::Array(arr) => {
+                self.set_open(path, open);
+                for (index, child) in arr.iter().enumerate() {
+                    self.set_open_recursive(child, &path.child_index(index), open);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Opens or closes `path` and all the containers next to it
+    pub fn set_siblings_open(&mut self, root: &Value, path: &JsonPath, open: bool) {
+        let Some(parent) = path.parent() else {
+            return self.set_open(path, open);
+        };
+        match root.pointer(&parent.to_json_pointer()) {
+            Some(Value::Object(map)) => {
+                for (key, child) in map {
+                    if matches!(child, Value::Object(_) | Value::Array(_)) {
+                        self.set_open(&parent.child_key(key), open);
+                    }
+                }
+            }
+            Some(Value::Array(arr)) => {
+                for (index, child) in arr.iter().enumerate() {
+                    if matches!(child, Value::Object(_) | Value::Array(_)) {
+                        self.set_open(&parent.child_index(index), open);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// An outline and tooltip drawn on a node, e.g. for a schema validation error
+pub struct NodeMark {
+    pub color: Color32,
+    pub tooltip: String,
+}
+
+// Actions requested from the tree view that need to touch app state.
+// They are collected while rendering and applied after the frame's tree pass.
+pub enum TreeAction {
+    UseAsJqFilter(String),
+    Select(JsonPath),
+    SetExpanded(JsonPath, bool),
+    SetSubtreeExpanded(JsonPath, bool),
+    SetSiblingsExpanded(JsonPath, bool),
+    ToggleTableView(JsonPath),
+    Edit(EditOp),
+    Error(String),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EditTarget {
+    Key,
+    Value(ValueKind),
+}
+
+// An in-progress inline edit of a single key or primitive value.
+pub struct NodeEditor {
+    path: JsonPath,
+    target: EditTarget,
+    buffer: String,
+    focus_requested: bool,
+}
+
+impl NodeEditor {
+    fn key(path: &JsonPath, key: &str) -> Self {
+        Self {
+            path: path.clone(),
+            target: EditTarget::Key,
+            buffer: key.to_string(),
+            focus_requested: false,
+        }
+    }
+
+    pub fn value(path: &JsonPath, value: &Value) -> Self {
+        let buffer = match value {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        Self {
+            path: path.clone(),
+            target: EditTarget::Value(ValueKind::of(value)),
+            buffer,
+            focus_requested: false,
+        }
+    }
+
+    fn is_editing(&self, path: &JsonPath, target: EditTarget) -> bool {
+        self.path == *path && self.target == target
+    }
+
+    fn into_edit_op(self) -> Result<EditOp, String> {
+        let path = self.path;
+        let value = match self.target {
+            EditTarget::Key => return Ok(EditOp::RenameKey { path, new_key: self.buffer }),
+            EditTarget::Value(ValueKind::String) => Value::String(self.buffer),
+            EditTarget::Value(ValueKind::Number) => self
+                .buffer
+                .trim()
+                .parse()
+                .map(Value::Number)
+                .map_err(|_| format!("\"{}\" is not a valid number.", self.buffer))?,
+            EditTarget::Value(ValueKind::Boolean) => Value::Bool(self.buffer == "true"),
+            // Null (and anything else) is edited as a JSON literal, falling back to a string
+            EditTarget::Value(_) => {
+                serde_json::from_str(&self.buffer).unwrap_or(Value::String(self.buffer))
+            }
+        };
+        Ok(EditOp::SetValue { path, value })
+    }
+}
+
+pub struct TreeContext<'a> {
+    pub search_query: &'a str,
+    // False while the tree shows a jq result rather than the input document
+    pub editable: bool,
+    pub editor: &'a mut Option<NodeEditor>,
+    pub expansion: &'a ExpansionState,
+    // Arrays shown as tables, with each table's columns, sort and filters
+    pub tables: &'a mut HashMap<JsonPath, TableState>,
+    pub marks: &'a HashMap<JsonPath, NodeMark>,
+    // Node under the editor cursor; outlined in the tree
+    pub selected: Option<&'a JsonPath>,
+    // Scroll the selected node into view this frame
+    pub reveal_selected: bool,
+    pub actions: &'a mut Vec<TreeAction>,
+}
+
+impl TreeContext<'_> {
+    fn is_editing(&self, path: &JsonPath, target: EditTarget) -> bool {
+        self.editor.as_ref().is_some_and(|e| e.is_editing(path, target))
+    }
+
+    fn commit_editor(&mut self) {
+        if let Some(editor) = self.editor.take() {
+            match editor.into_edit_op() {
+                Ok(op) => self.actions.push(TreeAction::Edit(op)),
+                Err(e) => self.actions.push(TreeAction::Error(e)),
+            }
+        }
+    }
+}
+
+// Draws the active text editor for a key or primitive value. Enter or
+// clicking away commits the edit, Escape cancels it.
+fn inline_text_editor(ui: &mut egui::Ui, ctx: &mut TreeContext) {
+    let Some(editor) = ctx.editor.as_mut() else {
+        return;
+    };
+    let invalid = editor.target == EditTarget::Value(ValueKind::Number)
+        && editor.buffer.trim().parse::<serde_json::Number>().is_err();
+    let mut text_edit = egui::TextEdit::singleline(&mut editor.buffer).desired_width(200.0);
+    if invalid {
+        text_edit = text_edit.text_color(Color32::RED);
+    }
+    let response = ui.add(text_edit);
+    if !editor.focus_requested {
+        response.request_focus();
+        editor.focus_requested = true;
+    }
+    if response.lost_focus() {
+        if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+            *ctx.editor = None;
+        } else {
+            ctx.commit_editor();
+        }
+    }
+}
+
+fn inline_bool_editor(ui: &mut egui::Ui, ctx: &mut TreeContext) {
+    let Some(editor) = ctx.editor.as_mut() else {
+        return;
+    };
+    let mut checked = editor.buffer == "true";
+    let label = checked.to_string();
+    let response = ui.checkbox(&mut checked, label);
+    if response.changed() {
+        editor.buffer = checked.to_string();
+        ctx.commit_editor();
+    } else if response.clicked_elsewhere() || ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+        *ctx.editor = None;
+    }
+}
+
+// Right-click menu shared by every node in the tree view.
+fn node_context_menu(
+    response: &egui::Response,
+    key_name: Option<&str>,
+    value: &Value,
+    path: &JsonPath,
+    ctx: &mut TreeContext,
+) {
+    response.context_menu(|ui| {
+        ui.menu_button("Copy path", |ui| {
+            if ui.button("JSONPath").clicked() {
+                ui.ctx().copy_text(path.to_jsonpath());
+                ui.close_menu();
+            }
+            if ui.button("JSON Pointer").clicked() {
+                ui.ctx().copy_text(path.to_json_pointer());
+                ui.close_menu();
+            }
+            if ui.button("jq expression").clicked() {
+                ui.ctx().copy_text(path.to_jq());
+                ui.close_menu();
+            }
+            if ui.button("JavaScript accessor").clicked() {
+                ui.ctx().copy_text(path.to_javascript());
+                ui.close_menu();
+            }
+        });
+        ui.menu_button("Copy value", |ui| {
+            if ui.button("Pretty").clicked() {
+                if let Ok(text) = serde_json::to_string_pretty(value) {
+                    ui.ctx().copy_text(text);
+                }
+                ui.close_menu();
+            }
+            if ui.button("Minified").clicked() {
+                if let Ok(text) = serde_json::to_string(value) {
+                    ui.ctx().copy_text(text);
+                }
+                ui.close_menu();
+            }
+        });
+        if let Some(key) = key_name
+            && ui.button("Copy key").clicked()
+        {
+            ui.ctx().copy_text(key.to_string());
+            ui.close_menu();
+        }
+        if matches!(value, Value::Object(_) | Value::Array(_)) || !path.is_root() {
+            ui.menu_button("Expand / collapse", |ui| {
+                expansion_menu_items(ui, value, path, ctx);
+            });
+        }
+        if matches!(value, Value::Array(_)) {
+            let label = if ctx.tables.contains_key(path) { "View as tree" } else { "View as table" };
+            if ui.button(label).clicked() {
+                ctx.actions.push(TreeAction::ToggleTableView(path.clone()));
+                ui.close_menu();
+            }
+        }
+        ui.separator();
+        if ui.button("Use as jq filter").clicked() {
+            ctx.actions.push(TreeAction::UseAsJqFilter(path.to_jq()));
+            ui.close_menu();
+        }
+        if ctx.editable {
+            ui.separator();
+            edit_menu_items(ui, key_name, value, path, ctx);
+        }
+    });
+}
+
+fn expansion_menu_items(ui: &mut egui::Ui, value: &Value, path: &JsonPath, ctx: &mut TreeContext) {
+    let mut action = None;
+    if matches!(value, Value::Object(_) | Value::Array(_)) {
+        if ui.button("Expand all children").clicked() {
+            action = Some(TreeAction::SetSubtreeExpanded(path.clone(), true));
+        }
+        if ui.button("Collapse all children").clicked() {
+            action = Some(TreeAction::SetSubtreeExpanded(path.clone(), false));
+        }
+    }
+    if !path.is_root() {
+        if ui.button("Expand siblings").clicked() {
+            action = Some(TreeAction::SetSiblingsExpanded(path.clone(), true));
+        }
+        if ui.button("Collapse siblings").clicked() {
+            action = Some(TreeAction::SetSiblingsExpanded(path.clone(), false));
+        }
+    }
+    if let Some(action) = action {
+        ctx.actions.push(action);
+        ui.close_menu();
+    }
+}
+
+fn edit_menu_items(
+    ui: &mut egui::Ui,
+    key_name: Option<&str>,
+    value: &Value,
+    path: &JsonPath,
+    ctx: &mut TreeContext,
+) {
+    let mut op = None;
+    if !matches!(value, Value::Object(_) | Value::Array(_)) && ui.button("Edit value").clicked() {
+        *ctx.editor = Some(NodeEditor::value(path, value));
+        ui.close_menu();
+    }
+    if let Some(key) = key_name
+        && ui.button("Rename key").clicked()
+    {
+        *ctx.editor = Some(NodeEditor::key(path, key));
+        ui.close_menu();
+    }
+    match value {
+        Value::Object(_) if ui.button("Add key").clicked() => {
+            op = Some(EditOp::AddKey { path: path.clone() });
+        }
+        Value::Array(arr) if ui.button("Append element").clicked() => {
+            op = Some(EditOp::InsertElement { path: path.clone(), index: arr.len() });
+        }
+        _ => {}
+    }
+    if let (Some(PathSegment::Index(index)), Some(parent)) = (path.last(), path.parent()) {
+        if ui.button("Insert element before").clicked() {
+            op = Some(EditOp::InsertElement { path: parent.clone(), index: *index });
+        }
+        if ui.button("Insert element after").clicked() {
+            op = Some(EditOp::InsertElement { path: parent, index: index + 1 });
+        }
+    }
+    if !path.is_root() && ui.button("Duplicate").clicked() {
+        op = Some(EditOp::Duplicate { path: path.clone() });
+    }
+    ui.menu_button("Change type", |ui| {
+        let current = ValueKind::of(value);
+        for kind in ValueKind::ALL {
+            if ui.add_enabled(kind != current, egui::Button::new(kind.label())).clicked() {
+                op = Some(EditOp::ChangeType { path: path.clone(), kind });
+            }
+        }
+    });
+    if !path.is_root() && ui.button("Remove").clicked() {
+        op = Some(EditOp::Remove { path: path.clone() });
+    }
+    if let Some(op) = op {
+        ctx.actions.push(TreeAction::Edit(op));
+        ui.close_menu();
+    }
+}
+
+pub fn render_json_value(
+    ui: &mut egui::Ui,
+    key_name: Option<&str>,
+    value: &Value,
+    path: &JsonPath,
+    ctx: &mut TreeContext,
+) {
+    // The first widget of the row; this is what gets outlined when selected
+    let mut anchor: Option<egui::Response> = None;
+    ui.horizontal(|ui| {
+        // Display the key name if provided (for object fields)
+        if let Some(key) = key_name {
+            if ctx.is_editing(path, EditTarget::Key) {
+                inline_text_editor(ui, ctx);
+            } else {
+                let key_display = format!("\"{}\":", key);
+                let key_response =
+                    highlighted_label(ui, &key_display, ctx.search_query, Color32::LIGHT_BLUE, true);
+                if key_response.clicked() {
+                    ctx.actions.push(TreeAction::Select(path.clone()));
+                }
+                if ctx.editable && key_response.double_clicked() {
+                    *ctx.editor = Some(NodeEditor::key(path, key));
+                }
+                node_context_menu(&key_response, key_name, value, path, ctx);
+                anchor = Some(key_response);
+            }
+            ui.add_space(5.0);
+        }
+
+        match value {
+            Value::Object(map) => {
+                // Header for objects
+                // Wrappers for binary types and the like say what they hold
+                let header_text = match binary::type_annotation(map) {
+                    Some(annotation) => format!("{{ ... }} ({} items) · {}", map.len(), annotation),
+                    None => format!("{{ ... }} ({} items)", map.len()),
+                };
+                // Create a unique ID for this collapsing header
+                let id = ui.make_persistent_id(("object", path));
+
+                let is_open = ctx.expansion.is_open(path);
+                let collapsing = CollapsingHeader::new(header_text)
+                    .id_salt(id)
+                    .open(Some(is_open)) // Open state lives in `ExpansionState`
+                    .show(ui, |ui| {
+                        // Indent the content within the collapsing header
+                        ui.indent("object_indent", |ui| {
+                            for (key, val) in map {
+                                // Recursively render each key-value pair in the object
+                                render_json_value(ui, Some(key), val, &path.child_key(key), ctx);
+                            }
+                        });
+                    });
+                if collapsing.header_response.clicked() {
+                    ctx.actions.push(TreeAction::SetExpanded(path.clone(), !is_open));
+                    ctx.actions.push(TreeAction::Select(path.clone()));
+                }
+                node_context_menu(&collapsing.header_response, key_name, value, path, ctx);
+                anchor.get_or_insert(collapsing.header_response);
+            }
+            Value::Array(arr) => {
+                // Header for arrays
+                let header_text = format!("[ ... ] ({} items)", arr.len());
+                // Create a unique ID for this collapsing header
+                let id = ui.make_persistent_id(("array", path));
+
+                let is_open = ctx.expansion.is_open(path);
+                let collapsing = CollapsingHeader::new(header_text)
+                    .id_salt(id)
+                    .open(Some(is_open)) // Open state lives in `ExpansionState`
+                    .show(ui, |ui| {
+                        if ctx.tables.contains_key(path) {
+                            render_table(ui, arr, path, ctx);
+                            return;
+                        }
+                        // Indent the content within the collapsing header
+                        ui.indent("array_indent", |ui| {
+                            for (index, val) in arr.iter().enumerate() {
+                                // Recursively render each element in the array.
+                                // We pass `None` for key_name as array elements don't have keys,
+                                // but the index is part of the unique path.
+                                render_json_value(ui, None, val, &path.child_index(index), ctx);
+                            }
+                        });
+                    });
+                if collapsing.header_response.clicked() {
+                    ctx.actions.push(TreeAction::SetExpanded(path.clone(), !is_open));
+                    ctx.actions.push(TreeAction::Select(path.clone()));
+                }
+                node_context_menu(&collapsing.header_response, key_name, value, path, ctx);
+                anchor.get_or_insert(collapsing.header_response);
+            }
+            // Handle primitive JSON types
+            _ => {
+                let kind = ValueKind::of(value);
+                if ctx.is_editing(path, EditTarget::Value(kind)) {
+                    if kind == ValueKind::Boolean {
+                        inline_bool_editor(ui, ctx);
+                    } else {
+                        inline_text_editor(ui, ctx);
+                    }
+                    return;
+                }
+                let (text, color) = match value {
+                    Value::String(s) => (format!("\"{}\"", s), Color32::GREEN),
+                    Value::Number(n) => (n.to_string(), Color32::YELLOW),
+                    Value::Bool(b) => (b.to_string(), Color32::KHAKI),
+                    _ => ("null".to_string(), Color32::DARK_GRAY),
+                };
+                let response = highlighted_label(ui, &text, ctx.search_query, color, false);
+                if response.clicked() {
+                    ctx.actions.push(TreeAction::Select(path.clone()));
+                }
+                if ctx.editable && response.double_clicked() {
+                    *ctx.editor = Some(NodeEditor::value(path, value));
+                }
+                node_context_menu(&response, key_name, value, path, ctx);
+                anchor.get_or_insert(response);
+            }
+        }
+    });
+
+    let Some(anchor) = anchor else {
+        return;
+    };
+    if ctx.selected == Some(path) {
+        ui.painter().rect_stroke(
+            anchor.rect.expand(2.0),
+            2.0,
+            egui::Stroke::new(1.5, Color32::from_rgb(255, 150, 80)),
+            egui::StrokeKind::Outside,
+        );
+        if ctx.reveal_selected {
+            anchor.scroll_to_me(Some(egui::Align::Center));
+        }
+    }
+    if let Some(mark) = ctx.marks.get(path) {
+        ui.painter().rect_stroke(
+            anchor.rect.expand(4.0),
+            2.0,
+            egui::Stroke::new(1.5, mark.color),
+            egui::StrokeKind::Outside,
+        );
+        anchor.on_hover_text(&mark.tooltip);
+    }
+}
+
+// Clickable `$ > data > [0] > id` trail for the selected node
+pub fn render_breadcrumbs(ui: &mut egui::Ui, path: &JsonPath, actions: &mut Vec<TreeAction>) {
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 2.0;
+        if ui.small_button("$").clicked() {
+            actions.push(TreeAction::Select(JsonPath::root()));
+        }
+        for (i, segment) in path.segments().iter().enumerate() {
+            ui.label(">");
+            let label = match segment {
+                PathSegment::Key(key) => key.clone(),
+                PathSegment::Index(index) => format!("[{}]", index),
+            };
+            if ui.small_button(label).clicked() {
+                actions.push(TreeAction::Select(path.truncated(i + 1)));
+            }
+        }
+    });
+}